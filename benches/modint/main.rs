@@ -0,0 +1,208 @@
+/*
+ModContext reduces with gmp's division and powers with mpz_powm, this keeps the
+montgomery (REDC) and barrett reductions it replaced next to it so the choice can
+be rerun: cargo bench --bench modint
+
+montgomery, odd n, values kept as x R mod n with R = 2^k > n:
+    m = (T mod R) n' mod R, n' = -n^-1 mod R, (T + m n) / R is below 2n
+barrett, any n, k the bit length of n and mu = floor(4^k / n):
+    q = ((T >> (k - 1)) mu) >> (k + 1) is at most 2 below T / n
+*/
+use std::sync::Arc;
+
+use divan::{black_box, Bencher};
+use manifold_rs::math::modint::{ModContext, ModInt};
+use rug::{Complete, Integer};
+
+const SIZES: [u32; 4] = [64, 256, 1024, 2048];
+const PRODUCTS: usize = 1_000;
+
+fn main() {
+    for bits in SIZES {
+        _check(bits);
+    }
+    divan::main();
+}
+
+struct Montgomery {
+    modulus: Integer,
+    bits: u32,
+    n_prime: Integer,
+    r_mod_n: Integer,
+    r_squared: Integer,
+}
+
+impl Montgomery {
+    fn new(modulus: &Integer) -> Montgomery {
+        let bits = modulus.significant_bits();
+        let r = Integer::from(1) << bits;
+        let inverse = modulus.clone().invert(&r).unwrap();
+        let r_mod_n = (&r % modulus).complete();
+        Montgomery {
+            modulus: modulus.clone(),
+            bits,
+            n_prime: (&r - inverse).modulo(&r),
+            r_squared: Integer::from(&r_mod_n * &r_mod_n) % modulus,
+            r_mod_n,
+        }
+    }
+
+    fn redc(&self, t: Integer) -> Integer {
+        // t R^-1 mod n for 0 <= t < n R
+        let m = (t.clone().keep_bits(self.bits) * &self.n_prime).keep_bits(self.bits);
+        let mut result = (t + m * &self.modulus) >> self.bits;
+        if result >= self.modulus {
+            result -= &self.modulus;
+        }
+        result
+    }
+
+    fn to_internal(&self, value: &Integer) -> Integer {
+        self.redc(Integer::from(value * &self.r_squared))
+    }
+
+    fn mul(&self, a: &Integer, b: &Integer) -> Integer {
+        self.redc((a * b).complete())
+    }
+
+    fn pow(&self, base: &Integer, exponent: &Integer) -> Integer {
+        // base and result in montgomery form
+        let mut result = self.r_mod_n.clone();
+        for bit in (0..exponent.significant_bits()).rev() {
+            result = self.mul(&result, &result);
+            if exponent.get_bit(bit) {
+                result = self.mul(&result, base);
+            }
+        }
+        result
+    }
+}
+
+struct Barrett {
+    modulus: Integer,
+    bits: u32,
+    mu: Integer,
+}
+
+impl Barrett {
+    fn new(modulus: &Integer) -> Barrett {
+        let bits = modulus.significant_bits();
+        Barrett {
+            modulus: modulus.clone(),
+            bits,
+            mu: (Integer::from(1) << (2 * bits)) / modulus,
+        }
+    }
+
+    fn reduce(&self, t: Integer) -> Integer {
+        // t mod n for 0 <= t < n^2
+        let q = (Integer::from(&t >> (self.bits - 1)) * &self.mu) >> (self.bits + 1);
+        let mut result = t - q * &self.modulus;
+        while result >= self.modulus {
+            result -= &self.modulus;
+        }
+        result
+    }
+
+    fn mul(&self, a: &Integer, b: &Integer) -> Integer {
+        self.reduce((a * b).complete())
+    }
+
+    fn pow(&self, base: &Integer, exponent: &Integer) -> Integer {
+        let mut result = Integer::from(1);
+        for bit in (0..exponent.significant_bits()).rev() {
+            result = self.mul(&result, &result);
+            if exponent.get_bit(bit) {
+                result = self.mul(&result, base);
+            }
+        }
+        result
+    }
+}
+
+fn _inputs(bits: u32) -> (Integer, Integer, Integer) {
+    // an odd modulus of the given size, a base below it and an exponent as long
+    let modulus = (Integer::from(1) << bits) - 189u32;
+    let base = Integer::from(&modulus / 3u32) + 12_345u32;
+    let exponent = Integer::from(&modulus - 2u32);
+    (modulus, base, exponent)
+}
+
+fn _check(bits: u32) {
+    // every reduction gives the same power
+    let (modulus, base, exponent) = _inputs(bits);
+    let expected = base.clone().pow_mod(&exponent, &modulus).unwrap();
+    let context = Arc::new(ModContext::new(&modulus).unwrap());
+    assert_eq!(
+        ModInt::new(&base, &context).pow(&exponent).value(),
+        expected
+    );
+    let montgomery = Montgomery::new(&modulus);
+    let power = montgomery.pow(&montgomery.to_internal(&base), &exponent);
+    assert_eq!(montgomery.redc(power), expected);
+    assert_eq!(Barrett::new(&modulus).pow(&base, &exponent), expected);
+}
+
+#[divan::bench(args = SIZES)]
+fn products_gmp(bencher: Bencher, bits: u32) {
+    let (modulus, base, _) = _inputs(bits);
+    let context = Arc::new(ModContext::new(&modulus).unwrap());
+    let base = ModInt::new(&base, &context);
+    bencher.bench_local(|| {
+        let mut x = base.clone();
+        for _ in 0..PRODUCTS {
+            x = &x * &base;
+        }
+        black_box(x)
+    });
+}
+
+#[divan::bench(args = SIZES)]
+fn products_montgomery(bencher: Bencher, bits: u32) {
+    let (modulus, base, _) = _inputs(bits);
+    let montgomery = Montgomery::new(&modulus);
+    let base = montgomery.to_internal(&base);
+    bencher.bench_local(|| {
+        let mut x = base.clone();
+        for _ in 0..PRODUCTS {
+            x = montgomery.mul(&x, &base);
+        }
+        black_box(x)
+    });
+}
+
+#[divan::bench(args = SIZES)]
+fn products_barrett(bencher: Bencher, bits: u32) {
+    let (modulus, base, _) = _inputs(bits);
+    let barrett = Barrett::new(&modulus);
+    bencher.bench_local(|| {
+        let mut x = base.clone();
+        for _ in 0..PRODUCTS {
+            x = barrett.mul(&x, &base);
+        }
+        black_box(x)
+    });
+}
+
+#[divan::bench(args = SIZES)]
+fn pow_gmp(bencher: Bencher, bits: u32) {
+    let (modulus, base, exponent) = _inputs(bits);
+    let context = Arc::new(ModContext::new(&modulus).unwrap());
+    let base = ModInt::new(&base, &context);
+    bencher.bench_local(|| black_box(base.pow(&exponent)));
+}
+
+#[divan::bench(args = SIZES)]
+fn pow_montgomery(bencher: Bencher, bits: u32) {
+    let (modulus, base, exponent) = _inputs(bits);
+    let montgomery = Montgomery::new(&modulus);
+    let base = montgomery.to_internal(&base);
+    bencher.bench_local(|| black_box(montgomery.pow(&base, &exponent)));
+}
+
+#[divan::bench(args = SIZES)]
+fn pow_barrett(bencher: Bencher, bits: u32) {
+    let (modulus, base, exponent) = _inputs(bits);
+    let barrett = Barrett::new(&modulus);
+    bencher.bench_local(|| black_box(barrett.pow(&base, &exponent)));
+}
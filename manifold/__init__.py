@@ -3,7 +3,17 @@ from manifold_rs import (
     miller_rabin_bool_multiple,
     power_of_two_exponent_10n_py,
     collatz_sequence,
-    chinese_remainder_theorem_py
+    chinese_remainder_theorem_py,
+    is_perfect_power_py,
+    iroot_py,
+    batch_gcd_py,
+    totient_py,
+    reduced_totient_py,
+    n_order_py,
+    primitive_root_py,
+    sqrt_mod_py,
+    nthroot_mod_py,
+    polynomial_congruence_py,
 )
 
 __all__ = [
@@ -11,5 +21,15 @@ __all__ = [
     "miller_rabin_bool_multiple",
     "power_of_two_exponent_10n_py",
     "collatz_sequence",
-    "chinese_remainder_theorem_py"
+    "chinese_remainder_theorem_py",
+    "is_perfect_power_py",
+    "iroot_py",
+    "batch_gcd_py",
+    "totient_py",
+    "reduced_totient_py",
+    "n_order_py",
+    "primitive_root_py",
+    "sqrt_mod_py",
+    "nthroot_mod_py",
+    "polynomial_congruence_py",
 ]
@@ -1,16 +1,24 @@
 use pyo3::prelude::*;
 use pyo3::types::PyAny;
 use pyo3::types::PyBool;
+use pyo3::types::PyLong;
 use rug::Integer;
 use std::str::FromStr;
 
 pub mod math;
+use math::batch_gcd::batch_gcd;
 use math::chineese_remainder_theorem::chinese_remainder_theorem_impl;
 use math::collatz::{collatz_sequence_impl, Collatz};
 use math::fib_calc::fib_matrix;
 use math::inneficient::sum_of_factors_from_pentagonal_numbers;
+use math::nthroot_mod::nthroot_mod;
 use math::padic::{x_pow_y_pow_z_mod_k, NumberConfig};
+use math::perfect_power::{iroot, is_perfect_power};
+use math::poly_congruence::solve_poly_congruence;
 use math::primes::miller_rabin_impl;
+use math::primitive_root::{any_primitive_root, multiplicative_order, primitive_root};
+use math::sqrt_mod::sqrt_mod;
+use math::totient::{reduced_totient, totient};
 
 fn to_rug_integer(obj: &PyAny) -> PyResult<Integer> {
     let str_val = obj.str()?.to_string();
@@ -18,6 +26,12 @@ fn to_rug_integer(obj: &PyAny) -> PyResult<Integer> {
         .map_err(|_| pyo3::exceptions::PyValueError::new_err("Invalid integer value."))
 }
 
+fn from_rug_integer(py: Python<'_>, value: &Integer) -> PyResult<PyObject> {
+    // goes through the decimal string, same as to_rug_integer in reverse
+    let py_int = py.get_type::<PyLong>().call1((value.to_string(),))?;
+    Ok(py_int.into_py(py))
+}
+
 #[pyfunction]
 fn miller_rabin_bool_multiple(a: &PyAny, b: &PyAny) -> PyResult<Vec<Py<PyBool>>> {
     let num_a = to_rug_integer(a)?;
@@ -71,6 +85,161 @@ fn chinese_remainder_theorem_py(a_list: Vec<&PyAny>, n_list: Vec<&PyAny>) -> PyR
     }
 }
 
+#[pyfunction]
+fn is_perfect_power_py(py: Python<'_>, n: &PyAny) -> PyResult<Option<(PyObject, u32)>> {
+    let num_n = to_rug_integer(n)?;
+    match is_perfect_power(&num_n) {
+        Some((base, exponent)) => Ok(Some((from_rug_integer(py, &base)?, exponent))),
+        None => Ok(None),
+    }
+}
+
+#[pyfunction]
+fn iroot_py(py: Python<'_>, n: &PyAny, k: u32) -> PyResult<(PyObject, PyObject)> {
+    let num_n = to_rug_integer(n)?;
+    if k == 0 || (num_n < 0 && k.is_multiple_of(2)) {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "no real {}-th root of {}",
+            k, num_n
+        )));
+    }
+    let (root, rem) = iroot(&num_n, k);
+    Ok((from_rug_integer(py, &root)?, from_rug_integer(py, &rem)?))
+}
+
+#[pyfunction]
+fn batch_gcd_py(py: Python<'_>, values: Vec<&PyAny>) -> PyResult<Vec<PyObject>> {
+    let integers: Result<Vec<Integer>, _> = values.iter().map(|x| to_rug_integer(x)).collect();
+    match batch_gcd(&integers?) {
+        Ok(result) => result.iter().map(|g| from_rug_integer(py, g)).collect(),
+        Err(e) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(e)),
+    }
+}
+
+#[pyfunction]
+fn totient_py(py: Python<'_>, n: &PyAny) -> PyResult<PyObject> {
+    let num_n = to_rug_integer(n)?;
+    match totient(&num_n) {
+        Ok(result) => from_rug_integer(py, &result),
+        Err(e) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(e)),
+    }
+}
+
+#[pyfunction]
+fn reduced_totient_py(py: Python<'_>, n: &PyAny) -> PyResult<PyObject> {
+    let num_n = to_rug_integer(n)?;
+    match reduced_totient(&num_n) {
+        Ok(result) => from_rug_integer(py, &result),
+        Err(e) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(e)),
+    }
+}
+
+#[pyfunction]
+fn n_order_py(py: Python<'_>, a: &PyAny, n: &PyAny) -> PyResult<PyObject> {
+    let num_a = to_rug_integer(a)?;
+    let num_n = to_rug_integer(n)?;
+    match multiplicative_order(&num_a, &num_n) {
+        Ok(result) => from_rug_integer(py, &result),
+        Err(e) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(e)),
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (p, smallest = true))]
+fn primitive_root_py(py: Python<'_>, p: &PyAny, smallest: bool) -> PyResult<Option<PyObject>> {
+    let num_p = to_rug_integer(p)?;
+    let result = if smallest {
+        primitive_root(&num_p)
+    } else {
+        any_primitive_root(&num_p)
+    };
+    match result {
+        Ok(Some(root)) => Ok(Some(from_rug_integer(py, &root)?)),
+        Ok(None) => Ok(None),
+        Err(e) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(e)),
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (a, p, all_roots = false))]
+fn sqrt_mod_py(py: Python<'_>, a: &PyAny, p: &PyAny, all_roots: bool) -> PyResult<PyObject> {
+    // the smallest root or None, or every root as a list like sympy
+    let num_a = to_rug_integer(a)?;
+    let num_p = to_rug_integer(p)?;
+    let roots = match sqrt_mod(&num_a, &num_p, all_roots) {
+        Ok(roots) => roots,
+        Err(e) => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(e)),
+    };
+    if all_roots {
+        let values: PyResult<Vec<PyObject>> =
+            roots.iter().map(|r| from_rug_integer(py, r)).collect();
+        Ok(values?.into_py(py))
+    } else {
+        match roots.first() {
+            Some(root) => from_rug_integer(py, root),
+            None => Ok(py.None()),
+        }
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (a, n, p, all_roots = false))]
+fn nthroot_mod_py(
+    py: Python<'_>,
+    a: &PyAny,
+    n: &PyAny,
+    p: &PyAny,
+    all_roots: bool,
+) -> PyResult<PyObject> {
+    // roots of x^n = a mod p, same arguments as sympy's nthroot_mod
+    let num_a = to_rug_integer(a)?;
+    let num_n = to_rug_integer(n)?;
+    let num_p = to_rug_integer(p)?;
+    if num_n <= 0 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "n should be positive",
+        ));
+    }
+    let k = match num_n.to_u64() {
+        Some(k) => k,
+        None => {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "n is too large",
+            ))
+        }
+    };
+    let roots = match nthroot_mod(&num_a, k, &num_p, all_roots) {
+        Ok(roots) => roots,
+        Err(e) => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(e)),
+    };
+    if all_roots {
+        let values: PyResult<Vec<PyObject>> =
+            roots.iter().map(|r| from_rug_integer(py, r)).collect();
+        Ok(values?.into_py(py))
+    } else {
+        match roots.first() {
+            Some(root) => from_rug_integer(py, root),
+            None => Ok(py.None()),
+        }
+    }
+}
+
+#[pyfunction]
+fn polynomial_congruence_py(
+    py: Python<'_>,
+    coefficients: Vec<&PyAny>,
+    n: &PyAny,
+) -> PyResult<Vec<PyObject>> {
+    // roots of f(x) = 0 mod n, coefficients from the highest power down
+    let coefficients: Result<Vec<Integer>, _> =
+        coefficients.iter().map(|c| to_rug_integer(c)).collect();
+    let num_n = to_rug_integer(n)?;
+    match solve_poly_congruence(&coefficients?, &num_n) {
+        Ok(roots) => roots.iter().map(|r| from_rug_integer(py, r)).collect(),
+        Err(e) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(e)),
+    }
+}
+
 #[pymodule]
 fn manifold_rs(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(miller_rabin_bool, m)?)?;
@@ -78,6 +247,16 @@ fn manifold_rs(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(power_of_two_exponent_10n_py, m)?)?;
     m.add_function(wrap_pyfunction!(collatz_sequence, m)?)?;
     m.add_function(wrap_pyfunction!(chinese_remainder_theorem_py, m)?)?;
+    m.add_function(wrap_pyfunction!(is_perfect_power_py, m)?)?;
+    m.add_function(wrap_pyfunction!(iroot_py, m)?)?;
+    m.add_function(wrap_pyfunction!(batch_gcd_py, m)?)?;
+    m.add_function(wrap_pyfunction!(totient_py, m)?)?;
+    m.add_function(wrap_pyfunction!(reduced_totient_py, m)?)?;
+    m.add_function(wrap_pyfunction!(n_order_py, m)?)?;
+    m.add_function(wrap_pyfunction!(primitive_root_py, m)?)?;
+    m.add_function(wrap_pyfunction!(sqrt_mod_py, m)?)?;
+    m.add_function(wrap_pyfunction!(nthroot_mod_py, m)?)?;
+    m.add_function(wrap_pyfunction!(polynomial_congruence_py, m)?)?;
     m.add_class::<Collatz>()?;
     Ok(())
 }
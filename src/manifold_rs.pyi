@@ -1,5 +1,5 @@
 from typing import List, Any
-from typing import List, Union
+from typing import List, Optional, Tuple, Union
 Number = Union[int, str]
 
 class Collatz:
@@ -25,3 +25,19 @@ def chinese_remainder_theorem_py(
     a_list: List[Number],
     n_list: List[Number]
 ) -> str: ...
+
+
+def is_perfect_power_py(n: Number) -> Optional[Tuple[int, int]]: ...
+def iroot_py(n: Number, k: int) -> Tuple[int, int]: ...
+def batch_gcd_py(values: List[Number]) -> List[int]: ...
+def totient_py(n: Number) -> int: ...
+def reduced_totient_py(n: Number) -> int: ...
+def n_order_py(a: Number, n: Number) -> int: ...
+def primitive_root_py(p: Number, smallest: bool = True) -> Optional[int]: ...
+def sqrt_mod_py(
+    a: Number, p: Number, all_roots: bool = False
+) -> Union[Optional[int], List[int]]: ...
+def nthroot_mod_py(
+    a: Number, n: Number, p: Number, all_roots: bool = False
+) -> Union[Optional[int], List[int]]: ...
+def polynomial_congruence_py(coefficients: List[Number], n: Number) -> List[int]: ...
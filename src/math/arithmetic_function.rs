@@ -0,0 +1,375 @@
+/*
+arithmetic functions as values
+
+ArithmeticFunction is anything we can evaluate at n >= 1
+MultiplicativeFunction only has to say what happens on prime powers, the value at
+any n then comes from its factorization
+
+identities are checked on FunctionTable, the values at 1..=N
+(f * g)(n) = sum over d | n of f(d) g(n / d) is done by walking the multiples of
+every d, O(N log N), same for the inverse since f^-1 only needs f^-1 at divisors
+
+the built-ins are unit structs, Sigma and Id carry their k
+*/
+use std::ops::Mul;
+
+use rug::ops::Pow;
+use rug::{Complete, Integer};
+
+use super::factorization::{factorint, Factorization};
+use super::spf_sieve::factor_range;
+
+pub trait ArithmeticFunction {
+    fn eval(&self, n: &Integer) -> Integer;
+
+    fn table(&self, n: u64) -> FunctionTable {
+        FunctionTable::from_fn(n, |i| self.eval(&Integer::from(i)))
+    }
+}
+
+pub trait MultiplicativeFunction: ArithmeticFunction {
+    fn eval_prime_power(&self, p: &Integer, e: u32) -> Integer;
+
+    fn eval_factorization(&self, factorization: &Factorization) -> Integer {
+        factorization
+            .factors()
+            .iter()
+            .map(|(p, e)| self.eval_prime_power(p, *e))
+            .product()
+    }
+
+    fn multiplicative_table(&self, n: u64) -> FunctionTable {
+        // one segmented factorization of 1..=n instead of factoring every value
+        let mut values = vec![Integer::new()];
+        for factors in factor_range(1, n.max(1)).iter().take(n as usize) {
+            values.push(
+                factors
+                    .iter()
+                    .map(|(p, e)| self.eval_prime_power(&Integer::from(*p), *e))
+                    .product(),
+            );
+        }
+        FunctionTable { values }
+    }
+}
+
+fn _eval_multiplicative<F: MultiplicativeFunction + ?Sized>(f: &F, n: &Integer) -> Integer {
+    if n.cmp0() != std::cmp::Ordering::Greater {
+        panic!("arithmetic functions are defined for n >= 1, got {}", n);
+    }
+    f.eval_factorization(&factorint(n))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionTable {
+    // values[n] = f(n) for 1 <= n <= N, values[0] is unused and kept at 0
+    values: Vec<Integer>,
+}
+
+impl FunctionTable {
+    pub fn from_fn<F: Fn(u64) -> Integer>(n: u64, f: F) -> Self {
+        let mut values = vec![Integer::new()];
+        values.extend((1..=n).map(f));
+        FunctionTable { values }
+    }
+
+    pub fn from_values(values: Vec<Integer>) -> Self {
+        // values[0] is f(1)
+        let mut table = vec![Integer::new()];
+        table.extend(values);
+        FunctionTable { values: table }
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.values.len() as u64 - 1
+    }
+
+    pub fn get(&self, n: u64) -> &Integer {
+        if n == 0 || n > self.limit() {
+            panic!("{} is outside the table [1, {}]", n, self.limit());
+        }
+        &self.values[n as usize]
+    }
+
+    pub fn values(&self) -> &[Integer] {
+        // f(1), f(2), ...
+        &self.values[1..]
+    }
+}
+
+impl ArithmeticFunction for FunctionTable {
+    fn eval(&self, n: &Integer) -> Integer {
+        let n = n
+            .to_u64()
+            .unwrap_or_else(|| panic!("{} is outside the table [1, {}]", n, self.limit()));
+        self.get(n).clone()
+    }
+
+    fn table(&self, n: u64) -> FunctionTable {
+        FunctionTable::from_fn(n, |i| self.get(i).clone())
+    }
+}
+
+pub fn dirichlet_convolution(f: &FunctionTable, g: &FunctionTable) -> FunctionTable {
+    // defined up to the shorter of the two tables
+    let n = f.limit().min(g.limit()) as usize;
+    let mut values = vec![Integer::new(); n + 1];
+    for d in 1..=n {
+        if f.values[d] == 0 {
+            continue;
+        }
+        for (k, m) in (d..=n).step_by(d).enumerate() {
+            values[m] += (&f.values[d] * &g.values[k + 1]).complete();
+        }
+    }
+    FunctionTable { values }
+}
+
+pub fn dirichlet_inverse(f: &FunctionTable) -> Result<FunctionTable, String> {
+    /*
+    f^-1(1) = 1 / f(1), f^-1(n) = -1 / f(1) sum over d | n, d < n of f(n / d) f^-1(d)
+    over the integers that needs f(1) = +-1
+    the sums are pushed forward from every finished d to its multiples
+    */
+    let n = f.limit() as usize;
+    if n == 0 {
+        return Ok(f.clone());
+    }
+    let unit = f.values[1].clone();
+    if unit != 1 && unit != -1 {
+        return Err(format!(
+            "f(1) = {} is not invertible over the integers",
+            unit
+        ));
+    }
+    let mut sums = vec![Integer::new(); n + 1];
+    let mut values = vec![Integer::new(); n + 1];
+    for d in 1..=n {
+        values[d] = if d == 1 {
+            unit.clone()
+        } else {
+            -(&sums[d] * &unit).complete()
+        };
+        if values[d] == 0 {
+            continue;
+        }
+        for (k, m) in (2 * d..=n).step_by(d).enumerate() {
+            sums[m] += (&f.values[k + 2] * &values[d]).complete();
+        }
+    }
+    Ok(FunctionTable { values })
+}
+
+pub fn mobius_inversion(g: &FunctionTable) -> FunctionTable {
+    // g = f * 1  =>  f = g * mu
+    dirichlet_convolution(g, &Mobius.multiplicative_table(g.limit()))
+}
+
+pub fn is_multiplicative(f: &FunctionTable) -> bool {
+    // f(1) = 1 and f(n) is the product of f over the prime powers of n
+    let n = f.limit();
+    if n == 0 {
+        return true;
+    }
+    if f.values[1] != 1 {
+        return false;
+    }
+    factor_range(1, n).iter().enumerate().all(|(i, factors)| {
+        let product: Integer = factors
+            .iter()
+            .map(|(p, e)| f.values[p.pow(*e) as usize].clone())
+            .product();
+        i == 0 || product == f.values[i + 1]
+    })
+}
+
+impl Mul for &FunctionTable {
+    type Output = FunctionTable;
+
+    fn mul(self, other: &FunctionTable) -> FunctionTable {
+        dirichlet_convolution(self, other)
+    }
+}
+
+pub struct Mobius;
+pub struct Totient;
+// sum of the k-th powers of the divisors
+pub struct Sigma(pub u32);
+// number of divisors
+pub struct Tau;
+pub struct Liouville;
+// n^k
+pub struct Id(pub u32);
+// constant 1
+pub struct One;
+// the unit of the convolution, 1 at n = 1 and 0 elsewhere
+pub struct Epsilon;
+
+impl MultiplicativeFunction for Mobius {
+    fn eval_prime_power(&self, _p: &Integer, e: u32) -> Integer {
+        Integer::from(if e == 1 { -1 } else { 0 })
+    }
+}
+
+impl MultiplicativeFunction for Totient {
+    fn eval_prime_power(&self, p: &Integer, e: u32) -> Integer {
+        p.clone().pow(e - 1) * (p - 1u32).complete()
+    }
+}
+
+impl MultiplicativeFunction for Sigma {
+    fn eval_prime_power(&self, p: &Integer, e: u32) -> Integer {
+        let pk = p.clone().pow(self.0);
+        (0..e).fold(Integer::from(1), |acc, _| acc * &pk + 1u32)
+    }
+}
+
+impl MultiplicativeFunction for Tau {
+    fn eval_prime_power(&self, _p: &Integer, e: u32) -> Integer {
+        Integer::from(e + 1)
+    }
+}
+
+impl MultiplicativeFunction for Liouville {
+    fn eval_prime_power(&self, _p: &Integer, e: u32) -> Integer {
+        Integer::from(if e.is_multiple_of(2) { 1 } else { -1 })
+    }
+}
+
+impl MultiplicativeFunction for Id {
+    fn eval_prime_power(&self, p: &Integer, e: u32) -> Integer {
+        p.clone().pow(e * self.0)
+    }
+}
+
+impl MultiplicativeFunction for One {
+    fn eval_prime_power(&self, _p: &Integer, _e: u32) -> Integer {
+        Integer::from(1)
+    }
+}
+
+impl MultiplicativeFunction for Epsilon {
+    fn eval_prime_power(&self, _p: &Integer, _e: u32) -> Integer {
+        Integer::new()
+    }
+}
+
+macro_rules! multiplicative_eval {
+    // eval through the factorization for each multiplicative function
+    ($($function:ident),*) => {
+        $(
+            impl ArithmeticFunction for $function {
+                fn eval(&self, n: &Integer) -> Integer {
+                    _eval_multiplicative(self, n)
+                }
+            }
+        )*
+    };
+}
+
+multiplicative_eval!(Mobius, Totient, Sigma, Tau, Liouville, Id, One, Epsilon);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::linear_sieve::linear_sieve;
+
+    const N: u64 = 2000;
+
+    #[test]
+    fn test_builtins_match_sieve() {
+        let sieve = linear_sieve(N);
+        for n in 1..=N {
+            let i = n as usize;
+            let big = Integer::from(n);
+            assert_eq!(Mobius.eval(&big), sieve.mobius()[i]);
+            assert_eq!(Totient.eval(&big), sieve.totient()[i]);
+            assert_eq!(Tau.eval(&big), sieve.divisor_count()[i]);
+            assert_eq!(Sigma(1).eval(&big), sieve.divisor_sum()[i]);
+            assert_eq!(Sigma(0).eval(&big), sieve.divisor_count()[i]);
+            assert_eq!(Liouville.eval(&big), sieve.liouville()[i]);
+        }
+        assert_eq!(Mobius.multiplicative_table(N), Mobius.table(N));
+        assert_eq!(Sigma(2).multiplicative_table(N), Sigma(2).table(N));
+    }
+
+    #[test]
+    fn test_eval_from_factorization() {
+        let n = Integer::from(10).pow(30);
+        let factorization = factorint(&n);
+        assert_eq!(Tau.eval_factorization(&factorization), 31 * 31);
+        assert_eq!(Id(2).eval(&n), Integer::from(10).pow(60));
+        assert_eq!(Totient.eval(&n), factorization.totient());
+        assert_eq!(Sigma(3).eval(&n), factorization.sigma_k(3));
+    }
+
+    #[test]
+    fn test_dirichlet_identities() {
+        let one = One.multiplicative_table(N);
+        let mobius = Mobius.multiplicative_table(N);
+        // mu * 1 = e
+        assert_eq!(&mobius * &one, Epsilon.multiplicative_table(N));
+        // phi * 1 = id
+        assert_eq!(
+            &Totient.multiplicative_table(N) * &one,
+            Id(1).multiplicative_table(N)
+        );
+        // 1 * 1 = tau, id * 1 = sigma, id_2 * 1 = sigma_2
+        assert_eq!(&one * &one, Tau.multiplicative_table(N));
+        assert_eq!(
+            &Id(1).multiplicative_table(N) * &one,
+            Sigma(1).multiplicative_table(N)
+        );
+        assert_eq!(
+            &Id(2).multiplicative_table(N) * &one,
+            Sigma(2).multiplicative_table(N)
+        );
+        // lambda * 1 is the indicator of the squares
+        let squares = FunctionTable::from_fn(N, |n| {
+            let root = (n as f64).sqrt() as u64;
+            Integer::from(root * root == n)
+        });
+        assert_eq!(&Liouville.multiplicative_table(N) * &one, squares);
+    }
+
+    #[test]
+    fn test_dirichlet_inverse_and_mobius_inversion() {
+        let one = One.multiplicative_table(N);
+        assert_eq!(
+            dirichlet_inverse(&one).unwrap(),
+            Mobius.multiplicative_table(N)
+        );
+        let sigma = Sigma(1).multiplicative_table(N);
+        assert_eq!(mobius_inversion(&sigma), Id(1).multiplicative_table(N));
+
+        // the inverse of a non multiplicative table is still an inverse
+        let f = FunctionTable::from_fn(N, |n| {
+            Integer::from(if n == 1 { -1 } else { (n * n + 3) as i64 })
+        });
+        let inverse = dirichlet_inverse(&f).unwrap();
+        assert_eq!(&f * &inverse, Epsilon.multiplicative_table(N));
+
+        let f = FunctionTable::from_fn(N, |n| Integer::from(n + 1));
+        assert!(dirichlet_inverse(&f).is_err());
+    }
+
+    #[test]
+    fn test_is_multiplicative() {
+        for table in [
+            Mobius.multiplicative_table(N),
+            Totient.multiplicative_table(N),
+            Liouville.multiplicative_table(N),
+            &Totient.multiplicative_table(N) * &Sigma(2).multiplicative_table(N),
+        ] {
+            assert!(is_multiplicative(&table));
+        }
+        assert!(!is_multiplicative(&FunctionTable::from_fn(N, |n| {
+            Integer::from(n + 1)
+        })));
+        assert!(!is_multiplicative(&FunctionTable::from_fn(N, |n| {
+            Integer::from(n % 7)
+        })));
+        let values = FunctionTable::from_values(vec![Integer::from(1), Integer::from(2)]);
+        assert_eq!(values.get(2), &2);
+    }
+}
@@ -0,0 +1,151 @@
+/*
+Bernstein's batch gcd
+
+for moduli n_1 .. n_k we want gcd(n_i, prod of all the others) for every i
+doing that pairwise is k^2 gcds, the trees make it quasi linear
+
+product tree: leaves are the n_i, every node is the product of its two children
+remainder tree: the root P is reduced mod n^2 on the way down so every leaf ends
+with P mod n_i^2, then gcd(n_i, (P mod n_i^2) / n_i) is the answer
+reducing mod n_i^2 instead of n_i keeps the information of P / n_i
+
+every level of both trees is done in parallel
+*/
+use rayon::prelude::*;
+use rug::{Complete, Integer};
+
+use super::threading::get_large_pool;
+
+pub fn product_tree(values: &[Integer]) -> Vec<Vec<Integer>> {
+    /*
+    tree[0] are the values themselves, tree.last() is [product of everything]
+    an odd node at the end of a level is carried up as it is
+    */
+    let mut tree: Vec<Vec<Integer>> = vec![values.to_vec()];
+    let pool = get_large_pool();
+    while tree.last().unwrap().len() > 1 {
+        let level = tree.last().unwrap();
+        let next: Vec<Integer> = pool.install(|| {
+            level
+                .par_chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => (a * b).complete(),
+                    [a] => a.clone(),
+                    _ => unreachable!(),
+                })
+                .collect()
+        });
+        tree.push(next);
+    }
+    tree
+}
+
+pub fn remainder_tree(root: &Integer, tree: &[Vec<Integer>]) -> Vec<Integer> {
+    /*
+    root mod value^2 for every leaf of the product tree
+    each node only needs its parent's remainder, so a level is one parallel pass
+    */
+    let pool = get_large_pool();
+    let mut remainders = vec![root.clone()];
+    for level in tree.iter().rev() {
+        remainders = pool.install(|| {
+            level
+                .par_iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    let square = value.clone().square();
+                    (&remainders[i / 2] % &square).complete()
+                })
+                .collect()
+        });
+    }
+    remainders
+}
+
+pub fn batch_gcd(values: &[Integer]) -> Result<Vec<Integer>, String> {
+    /*
+    result[i] = gcd(values[i], product of values[j] for j != i)
+    negative values are taken by absolute value, zero has no meaningful answer
+    */
+    if values.is_empty() {
+        return Ok(Vec::new());
+    }
+    if let Some(i) = values.iter().position(|v| *v == 0) {
+        return Err(format!("value at index {} is zero", i));
+    }
+    let values: Vec<Integer> = values.iter().map(|v| v.clone().abs()).collect();
+
+    let tree = product_tree(&values);
+    let root = &tree.last().unwrap()[0];
+    let remainders = remainder_tree(root, &tree);
+
+    let pool = get_large_pool();
+    Ok(pool.install(|| {
+        values
+            .par_iter()
+            .zip(remainders.par_iter())
+            .map(|(value, remainder)| {
+                let quotient = (remainder / value).complete();
+                quotient.gcd(value)
+            })
+            .collect()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rug::ops::Pow;
+
+    fn naive_batch_gcd(values: &[Integer]) -> Vec<Integer> {
+        (0..values.len())
+            .map(|i| {
+                let others: Integer = values
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, v)| v.clone())
+                    .product();
+                others.gcd(&values[i])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_product_tree() {
+        let values: Vec<Integer> = (1..=5).map(Integer::from).collect();
+        let tree = product_tree(&values);
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree[1], vec![2, 12, 5]);
+        assert_eq!(tree[3], vec![120]);
+    }
+
+    #[test]
+    fn test_batch_gcd_shared_primes() {
+        // rsa style moduli, two of them share 1000003
+        let values = vec![
+            Integer::from(1_000_003u64 * 999_983),
+            Integer::from(1_000_033u64 * 999_979),
+            Integer::from(1_000_003u64 * 999_961),
+            Integer::from(1_000_037u64 * 999_953),
+        ];
+        assert_eq!(
+            batch_gcd(&values).unwrap(),
+            vec![1_000_003, 1, 1_000_003, 1]
+        );
+    }
+
+    #[test]
+    fn test_batch_gcd_matches_naive() {
+        let values: Vec<Integer> = (0..37u32)
+            .map(|i| Integer::from(3).pow(i % 5) * (Integer::from(7919) * i + 10_007))
+            .collect();
+        assert_eq!(batch_gcd(&values).unwrap(), naive_batch_gcd(&values));
+
+        let values: Vec<Integer> = vec![Integer::from(12), Integer::from(-18)];
+        assert_eq!(batch_gcd(&values).unwrap(), vec![6, 6]);
+        assert_eq!(batch_gcd(&[Integer::from(5)]).unwrap(), vec![1]);
+        assert!(batch_gcd(&[]).unwrap().is_empty());
+        assert!(batch_gcd(&[Integer::from(3), Integer::from(0)]).is_err());
+    }
+}
@@ -1,52 +1,202 @@
-use rug::{Complete, Integer};
+/*
+the chinese remainder theorem over ZZ
 
-pub fn extended_gcd(a: &Integer, b: &Integer) -> (Integer, Integer, Integer) {
-    if b == &0 {
-        return (a.clone(), Integer::from(1), Integer::from(0));
-    }
+chinese_remainder_theorem_impl is ring::crt on ZZ, the one generic version.
+two integer only variants stay next to it because they solve different problems:
+crt_general takes moduli that are not coprime and names the clashing pair, and
+CrtContext precomputes Garner's coefficients once for many residue vectors on the
+same moduli, which the generic one pass has no place to keep
+*/
+use std::sync::Arc;
 
-    let (gcd, x1, y1) = extended_gcd(b, &(a % b).complete());
-    let x = y1.clone();
-    let y = x1 - (a / b).complete() * &y1;
+use rug::{Complete, Integer};
 
-    (gcd, x, y)
-}
+pub use super::gcd::extended_gcd;
+use super::int_ring::get_zz;
+use super::modint::{ModContext, ModInt};
+use super::ring::crt;
 
 pub fn chinese_remainder_theorem_impl(
     a_list: &[Integer],
     n_list: &[Integer],
 ) -> Result<Integer, String> {
-    let k = a_list.len();
+    // x in [0, n_1 ... n_k) for pairwise coprime moduli, the generic crt over ZZ
+    if a_list.len() != n_list.len() {
+        return Err("Lists must have equal length".to_string());
+    }
+    crt(get_zz(), a_list, n_list).map_err(|_| {
+        /* name the first modulus sharing a factor with the product of the others */
+        let n: Integer = n_list.iter().product();
+        let (ni, ni_partial) = n_list
+            .iter()
+            .map(|ni| (ni, (&n / ni).complete()))
+            .find(|(ni, partial)| partial.gcd_ref(ni).complete() != 1)
+            .unwrap();
+        format!(
+            "Modulo {} and {} are not coprime, no solution exists.",
+            ni, ni_partial
+        )
+    })
+}
 
-    if k != n_list.len() {
+pub fn crt_general(a_list: &[Integer], n_list: &[Integer]) -> Result<(Integer, Integer), String> {
+    /*
+    (x, lcm) with x = a_i mod n_i for every i and 0 <= x < lcm, the moduli need
+    not be coprime
+    merging x = a mod m with y = b mod n: g = gcd(m, n) has to divide b - a, then
+    x + m t with t = (b - a) / g * (m / g)^-1 mod n / g solves both mod lcm(m, n)
+    */
+    if a_list.len() != n_list.len() {
         return Err("Lists must have equal length".to_string());
     }
+    if n_list.iter().any(|n| *n <= 0) {
+        return Err("Moduli must be positive".to_string());
+    }
 
-    let mut n = Integer::from(1);
-    for ni in n_list {
-        n *= ni;
+    let mut x = Integer::from(0);
+    let mut lcm = Integer::from(1);
+    for (i, (a, n)) in a_list.iter().zip(n_list.iter()).enumerate() {
+        let (g, inv, _) = extended_gcd(&lcm, n);
+        let diff = (a - &x).complete();
+        if !diff.is_divisible(&g) {
+            /* the system so far is consistent so some earlier congruence clashes with this one */
+            let j = (0..i)
+                .find(|j| {
+                    let g = n_list[*j].gcd_ref(n).complete();
+                    !(a - &a_list[*j]).complete().is_divisible(&g)
+                })
+                .unwrap();
+            return Err(format!(
+                "Congruences x = {} mod {} and x = {} mod {} are incompatible",
+                a_list[j], n_list[j], a, n
+            ));
+        }
+        let n_reduced = (n / &g).complete();
+        let t = ((diff / &g) * inv).modulo(&n_reduced);
+        x += &lcm * t;
+        lcm *= n_reduced;
+        x.modulo_mut(&lcm);
     }
+    Ok((x, lcm))
+}
 
-    let mut result = Integer::from(0);
+#[derive(Debug, Clone)]
+pub struct CrtContext {
+    /*
+    fixed pairwise coprime moduli, Garner's form
+        x = v_0 + v_1 m_0 + v_2 m_0 m_1 + ...
+    with v_i = (a_i - x_(i - 1)) c_i mod m_i, c_i = (m_0 ... m_(i - 1))^-1 mod m_i
+    the c_i and the partial products only depend on the moduli, each c_i is kept
+    as a ModInt in the context of its m_i
+    */
+    moduli: Vec<Integer>,
+    coefficients: Vec<ModInt>,
+    partial_products: Vec<Integer>,
+    modulus: Integer,
+}
 
-    for i in 0..k {
-        let ai = &a_list[i];
-        let ni = &n_list[i];
+impl CrtContext {
+    pub fn new(moduli: &[Integer]) -> Result<CrtContext, String> {
+        if moduli.iter().any(|n| *n <= 0) {
+            return Err("Moduli must be positive".to_string());
+        }
+        let mut coefficients = Vec::with_capacity(moduli.len());
+        let mut partial_products = Vec::with_capacity(moduli.len());
+        let mut product = Integer::from(1);
+        for (i, m) in moduli.iter().enumerate() {
+            let coefficient = match product.invert_ref(m) {
+                Some(inverse) => Integer::from(inverse),
+                /* mod 1 everything is 0, invert_ref gives None there */
+                None if *m == 1 => Integer::new(),
+                None => {
+                    let j = (0..i)
+                        .find(|j| moduli[*j].gcd_ref(m).complete() != 1)
+                        .unwrap();
+                    return Err(format!("Modulo {} and {} are not coprime", moduli[j], m));
+                }
+            };
+            let context = Arc::new(ModContext::new(m)?);
+            coefficients.push(ModInt::new(&coefficient, &context));
+            partial_products.push(product.clone());
+            product *= m;
+        }
+        Ok(CrtContext {
+            moduli: moduli.to_vec(),
+            coefficients,
+            partial_products,
+            modulus: product,
+        })
+    }
 
-        let ni_partial = (&n / ni).complete();
+    pub fn moduli(&self) -> &[Integer] {
+        &self.moduli
+    }
 
-        let (gcd, inv, _) = extended_gcd(&ni_partial, ni);
-        if gcd != 1 {
-            return Err(format!(
-                "Modulo {} and {} are not coprime, no solution exists.",
-                ni, ni_partial
-            ));
+    pub fn modulus(&self) -> &Integer {
+        // product of the moduli
+        &self.modulus
+    }
+
+    pub fn reconstruct(&self, residues: &[Integer]) -> Result<Integer, String> {
+        // the x in [0, modulus) with x = residues[i] mod moduli[i]
+        if residues.len() != self.moduli.len() {
+            return Err("Lists must have equal length".to_string());
+        }
+        let mut x = Integer::from(0);
+        for (i, a) in residues.iter().enumerate() {
+            let c = &self.coefficients[i];
+            let v = ModInt::new(&(a - &x).complete(), c.context()) * c;
+            x += v.value() * &self.partial_products[i];
         }
+        Ok(x)
+    }
 
-        result += (ai * &ni_partial).complete() * inv;
+    pub fn combinations(&self, residues: &[Vec<Integer>]) -> Result<Vec<Integer>, String> {
+        // x for every choice of one residue per modulus, sorted
+        if residues.len() != self.moduli.len() {
+            return Err("Lists must have equal length".to_string());
+        }
+        if residues.iter().any(|r| r.is_empty()) {
+            return Ok(Vec::new());
+        }
+        /* odometer over one residue per modulus */
+        let mut indices = vec![0usize; residues.len()];
+        let mut combined = Vec::new();
+        loop {
+            let chosen: Vec<Integer> = indices
+                .iter()
+                .zip(residues.iter())
+                .map(|(i, r)| r[*i].clone())
+                .collect();
+            combined.push(self.reconstruct(&chosen)?);
+            let mut position = 0;
+            while position < indices.len() {
+                indices[position] += 1;
+                if indices[position] < residues[position].len() {
+                    break;
+                }
+                indices[position] = 0;
+                position += 1;
+            }
+            if position == indices.len() {
+                break;
+            }
+        }
+        combined.sort();
+        Ok(combined)
     }
+}
 
-    Ok(result % n)
+pub fn expand_classes(classes: &[Integer], m: &Integer, n: &Integer) -> Vec<Integer> {
+    // every member in [0, n) of the classes mod m sorted, classes in [0, m) and m | n
+    let mut members = Vec::new();
+    let mut offset = Integer::new();
+    while offset < *n {
+        members.extend(classes.iter().map(|c| Integer::from(c + &offset)));
+        offset += m;
+    }
+    members.sort();
+    members
 }
 
 #[cfg(test)]
@@ -63,5 +213,122 @@ mod tests {
         for (a, n) in a_list.iter().zip(n_list.iter()) {
             assert_eq!((&result % n).complete(), (a % n).complete());
         }
+        // negative residues still land in [0, 105)
+        let a_list = vec![Integer::from(-1), Integer::from(-1), Integer::from(-1)];
+        assert_eq!(
+            chinese_remainder_theorem_impl(&a_list, &n_list).unwrap(),
+            104
+        );
+        let err = chinese_remainder_theorem_impl(
+            &a_list,
+            &[Integer::from(4), Integer::from(6), Integer::from(7)],
+        );
+        assert_eq!(
+            err.unwrap_err(),
+            "Modulo 4 and 42 are not coprime, no solution exists."
+        );
+    }
+
+    #[test]
+    fn test_crt_general() {
+        let ints = |v: &[i64]| v.iter().map(|x| Integer::from(*x)).collect::<Vec<_>>();
+        let (x, lcm) = crt_general(&ints(&[2, 3, 2]), &ints(&[3, 5, 7])).unwrap();
+        assert_eq!((x, lcm), (Integer::from(23), Integer::from(105)));
+        // not coprime but compatible
+        let (x, lcm) = crt_general(&ints(&[3, 5, 11]), &ints(&[4, 6, 10])).unwrap();
+        assert_eq!((x, lcm), (Integer::from(11), Integer::from(60)));
+        let (x, lcm) = crt_general(&ints(&[-1, 5]), &ints(&[12, 18])).unwrap();
+        assert_eq!((x, lcm), (Integer::from(23), Integer::from(36)));
+        assert_eq!(
+            crt_general(&[], &[]).unwrap(),
+            (Integer::from(0), Integer::from(1))
+        );
+        // the error names the clashing pair, not just the last congruence
+        let err = crt_general(&ints(&[1, 2, 0]), &ints(&[4, 3, 6])).unwrap_err();
+        assert_eq!(
+            err,
+            "Congruences x = 1 mod 4 and x = 0 mod 6 are incompatible"
+        );
+        assert!(crt_general(&ints(&[1]), &ints(&[0])).is_err());
+        assert!(crt_general(&ints(&[1, 2]), &ints(&[3])).is_err());
+
+        // brute force every system of two congruences with small moduli
+        for m in 1..13i64 {
+            for n in 1..13i64 {
+                for a in 0..m {
+                    for b in 0..n {
+                        let expected = (0..m * n).find(|x| x % m == a && x % n == b);
+                        match crt_general(&ints(&[a, b]), &ints(&[m, n])) {
+                            Ok((x, lcm)) => {
+                                assert_eq!(Some(x), expected.map(Integer::from));
+                                assert_eq!(lcm, m * n / Integer::from(m).gcd(&Integer::from(n)));
+                            }
+                            Err(_) => assert_eq!(expected, None),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_crt_context() {
+        let moduli: Vec<Integer> = [3u32, 5, 7, 11, 1, 13]
+            .iter()
+            .map(|m| Integer::from(*m))
+            .collect();
+        let context = CrtContext::new(&moduli).unwrap();
+        assert_eq!(*context.modulus(), 15_015);
+        assert_eq!(context.moduli().len(), 6);
+        for x in (0..15_015u32).step_by(7) {
+            let residues: Vec<Integer> = moduli.iter().map(|m| Integer::from(x) % m).collect();
+            assert_eq!(context.reconstruct(&residues).unwrap(), x);
+        }
+        // residues outside [0, m) and agreement with the one shot version
+        let residues: Vec<Integer> = [-1i32, 12, -20, 4, 9, 100]
+            .iter()
+            .map(|a| Integer::from(*a))
+            .collect();
+        let expected = chinese_remainder_theorem_impl(&residues, &moduli)
+            .unwrap()
+            .modulo(context.modulus());
+        assert_eq!(context.reconstruct(&residues).unwrap(), expected);
+        assert!(context.reconstruct(&residues[..2]).is_err());
+
+        // every combination, x^2 = 1 mod 3, 5 and 7
+        let context = CrtContext::new(&moduli[..3]).unwrap();
+        let ones: Vec<Vec<Integer>> = moduli[..3]
+            .iter()
+            .map(|m| vec![Integer::from(1), Integer::from(m - 1u32)])
+            .collect();
+        let combined = context.combinations(&ones).unwrap();
+        assert_eq!(combined, vec![1, 29, 34, 41, 64, 71, 76, 104]);
+        assert!(context
+            .combinations(&[vec![], ones[1].clone(), ones[2].clone()])
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            expand_classes(
+                &[Integer::from(1), Integer::from(4)],
+                &Integer::from(5),
+                &Integer::from(15)
+            ),
+            vec![1, 4, 6, 9, 11, 14]
+        );
+
+        // big coprime moduli
+        let moduli: Vec<Integer> = [(1u32 << 31) - 1, 1_000_000_007, 998_244_353]
+            .iter()
+            .map(|m| Integer::from(*m))
+            .collect();
+        let context = CrtContext::new(&moduli).unwrap();
+        let x = Integer::from(123_456_789_123_456_789u64) * 1_000u32 + 17u32;
+        let residues: Vec<Integer> = moduli.iter().map(|m| Integer::from(&x % m)).collect();
+        assert_eq!(context.reconstruct(&residues).unwrap(), x);
+
+        let err =
+            CrtContext::new(&[Integer::from(6), Integer::from(35), Integer::from(10)]).unwrap_err();
+        assert_eq!(err, "Modulo 6 and 10 are not coprime");
+        assert!(CrtContext::new(&[Integer::from(0)]).is_err());
     }
 }
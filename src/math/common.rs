@@ -1,10 +1,16 @@
 use std::collections::HashSet;
+use std::sync::{Arc, OnceLock};
 
 use super::{primes::sieve, traits::IntegerGenerator};
 use rug::{ops::Pow, Complete, Integer};
 
+use super::linear_sieve::mobius_table;
+use super::modint::{ModContext, ModInt};
+use super::quadratic_residue::residue_table;
 use super::{num_utils::pow_large, primes::miller_rabin_single};
 
+pub use super::gcd::{binary_gcd, euclidean_gcd};
+
 pub fn is_power_of_2(n: &Integer) -> bool {
     let significant_bits = n.significant_bits() - 1;
 
@@ -47,43 +53,6 @@ pub fn trailing_zeros(n: &Integer) -> u32 {
     significant_bits
 }
 
-pub fn binary_gcd(mut a: Integer, mut b: Integer) -> Integer {
-    if a == 0 {
-        return b;
-    }
-    if b == 0 {
-        return a;
-    }
-
-    let shift = trailing_zeros(&(&a | &b).complete());
-
-    a >>= trailing_zeros(&a);
-    b >>= trailing_zeros(&b);
-
-    while b != 0 {
-        while b.is_even() {
-            b >>= 1;
-        }
-
-        if a > b {
-            std::mem::swap(&mut a, &mut b);
-        }
-
-        b -= &a;
-    }
-
-    a << shift
-}
-
-pub fn euclidean_gcd(mut a: Integer, mut b: Integer) -> Integer {
-    while b != 0 {
-        let temp = a % &b;
-        a = b;
-        b = temp;
-    }
-    a
-}
-
 pub fn is_coprime(a: Integer, b: Integer) -> bool {
     return binary_gcd(a.clone(), b.clone()) == 1;
 }
@@ -94,14 +63,16 @@ pub fn lucas_lehmer_q(prime_q: &Integer) -> bool {
     }
 
     let m_q = pow_large(&Integer::from(2), &prime_q) - Integer::from(1);
+    let context = Arc::new(ModContext::new(&m_q).unwrap());
 
-    let mut s = Integer::from(4);
+    let mut s = ModInt::new(&Integer::from(4), &context);
+    let two = ModInt::new(&Integer::from(2), &context);
 
     let q_loop = prime_q - Integer::from(2);
     let mut cnt = Integer::from(0);
 
     while &cnt < &q_loop {
-        s = (s.square() - Integer::from(2)) % &m_q;
+        s = &s * &s - &two;
         cnt += 1;
     }
 
@@ -109,6 +80,15 @@ pub fn lucas_lehmer_q(prime_q: &Integer) -> bool {
 }
 
 pub fn quadratic_residues(p: &Integer) -> Vec<Integer> {
+    // sorted squares mod p, read off the sieve in quadratic_residue when p fits
+    if let Some(modulus) = p.to_u64().filter(|n| *n > 0) {
+        return residue_table(modulus)
+            .iter()
+            .enumerate()
+            .filter(|(_, residue)| **residue)
+            .map(|(a, _)| Integer::from(a))
+            .collect();
+    }
     let mut residues = HashSet::new();
 
     let half_p = p.clone() / Integer::from(2) + Integer::from(1);
@@ -122,10 +102,18 @@ pub fn quadratic_residues(p: &Integer) -> Vec<Integer> {
     residues_vec
 }
 
+// μ(n) for n below this comes from one shared table, 1 MiB built on first use
+const MOBIUS_TABLE_LIMIT: u64 = 1 << 20;
+static MOBIUS_TABLE: OnceLock<Vec<i8>> = OnceLock::new();
+
 pub fn mobius(n: &Integer) -> i32 {
     if n == &Integer::from(1) {
         return 1;
     }
+    if let Some(small) = n.to_u64().filter(|v| (1..=MOBIUS_TABLE_LIMIT).contains(v)) {
+        let table = MOBIUS_TABLE.get_or_init(|| mobius_table(MOBIUS_TABLE_LIMIT));
+        return table[small as usize] as i32;
+    }
     let n_u32 = n.to_u32();
     let primes = sieve(n_u32.unwrap() as usize);
     let mut n = n.clone();
@@ -257,6 +245,8 @@ mod tests {
         let residues = quadratic_residues(&Integer::from(7));
         let epxected = rug_int_vec![0, 1, 2, 4];
         assert_eq!(residues, epxected);
+        // moduli outside u64 take the old loop instead of panicking
+        assert!(quadratic_residues(&Integer::from(-7)).is_empty());
     }
 
     #[test]
@@ -0,0 +1,459 @@
+/*
+factoring b^n - 1 and b^n + 1 the way the Cunningham project does
+
+b^n - 1 = product of Phi_d(b) for d | n
+b^n + 1 = product of Phi_d(b) for d | 2n, d not dividing n
+so the first step is free, every cyclotomic value is computed exactly
+
+some of those pieces have an Aurifeuillian split on top, e.g.
+2^58 + 1 = 5 * (2^29 - 2^15 + 1) * ... Phi_116(2) = 107367629 * 536903681
+instead of bundling the L/M polynomials per base we find them from the roots:
+with b = s t^2 (s squarefree) Phi_d(b) = prod (t sqrt(s) - z) over the 2 phi(d)
+roots z of Phi_d(x^2). the galois group of Q(zeta, sqrt(s)) acts on those roots,
+when the orbit of one root is half of them the product over the orbit is an
+integer. it is computed numerically with enough precision, rounded and then
+checked exactly, nothing unverified gets reported
+
+only after that the remaining pieces go to factorint
+*/
+use std::collections::HashSet;
+use std::fmt;
+
+use rug::float::Constant;
+use rug::ops::Pow;
+use rug::{Complete, Complex, Float, Integer};
+
+use super::factor_u64::{factor_u64, gcd_u64};
+use super::factorization::{factorint, Factorization};
+use super::static_data::SMALL_PRIME_CACHE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CunninghamSign {
+    Minus,
+    Plus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PieceKind {
+    // the whole primitive part Phi_d(b)
+    Full,
+    // Aurifeuillian halves, L is the smaller one
+    L,
+    M,
+}
+
+#[derive(Debug, Clone)]
+pub struct CunninghamPiece {
+    // d in Phi_d(b)
+    pub index: u64,
+    pub kind: PieceKind,
+    pub value: Integer,
+    pub factors: Factorization,
+}
+
+#[derive(Debug, Clone)]
+pub struct CunninghamFactorization {
+    pub base: u64,
+    pub exponent: u64,
+    pub sign: CunninghamSign,
+    pub pieces: Vec<CunninghamPiece>,
+}
+
+fn _divisors_u64(n: u64) -> Vec<u64> {
+    let mut divisors = vec![1u64];
+    for (p, e) in factor_u64(n) {
+        let current = divisors.len();
+        let mut pk = 1;
+        for _ in 0..e {
+            pk *= p;
+            for i in 0..current {
+                divisors.push(divisors[i] * pk);
+            }
+        }
+    }
+    divisors.sort_unstable();
+    divisors
+}
+
+fn _mobius_u64(n: u64) -> i32 {
+    let factors = factor_u64(n);
+    if factors.iter().any(|(_, e)| *e > 1) {
+        0
+    } else if factors.len().is_multiple_of(2) {
+        1
+    } else {
+        -1
+    }
+}
+
+fn _totient_u64(n: u64) -> u64 {
+    factor_u64(n)
+        .iter()
+        .fold(n, |acc, (p, _)| acc / p * (p - 1))
+}
+
+pub fn cyclotomic_value(d: u64, b: &Integer) -> Integer {
+    /*
+    Phi_d(b) = prod over e | d of (b^e - 1)^mu(d / e)
+    the division at the end is exact
+    */
+    if d == 0 {
+        panic!("Phi_0 is undefined");
+    }
+    let mut numerator = Integer::from(1);
+    let mut denominator = Integer::from(1);
+    for e in _divisors_u64(d) {
+        let term = b.clone().pow(e as u32) - 1u32;
+        match _mobius_u64(d / e) {
+            1 => numerator *= term,
+            -1 => denominator *= term,
+            _ => {}
+        }
+    }
+    numerator / denominator
+}
+
+fn _squarefree_decomposition(b: u64) -> (u64, u64) {
+    // b = s * t^2 with s squarefree
+    let mut s = 1;
+    let mut t = 1;
+    for (p, e) in factor_u64(b) {
+        if e % 2 == 1 {
+            s *= p;
+        }
+        t *= p.pow(e / 2);
+    }
+    (s, t)
+}
+
+fn _root_orbit(d: u64, s: u64) -> HashSet<u64> {
+    /*
+    roots of Phi_d(x^2) are zeta_2d^c for c mod 2d with gcd(c, d) = 1
+    u in (Z/L)* sends sqrt(s) to kronecker(disc, u) sqrt(s) and zeta_2d^c to zeta_2d^(uc),
+    on the factors (t sqrt(s) - zeta_2d^c) that is c -> u c + d [kronecker = -1]
+    small primes generate (Z/L)*, the exact check afterwards covers us if they don't
+    */
+    let discriminant: i64 = if s % 4 == 1 { s as i64 } else { 4 * s as i64 };
+    let two_d = 2 * d;
+    let l = two_d / gcd_u64(two_d, discriminant.unsigned_abs()) * discriminant.unsigned_abs();
+    let disc = Integer::from(discriminant);
+
+    let mut generators: Vec<(u64, u64)> = Vec::new();
+    for &p in SMALL_PRIME_CACHE.iter() {
+        let p = p as u64;
+        if gcd_u64(p, l) != 1 {
+            continue;
+        }
+        if p > l && !generators.is_empty() {
+            break;
+        }
+        let shift = if disc.kronecker(&Integer::from(p)) == -1 {
+            d
+        } else {
+            0
+        };
+        generators.push((p % two_d, shift));
+    }
+    // -1 mod L
+    let shift = if disc.kronecker(&Integer::from(l - 1)) == -1 {
+        d
+    } else {
+        0
+    };
+    generators.push(((l - 1) % two_d, shift));
+
+    let mut orbit = HashSet::new();
+    let mut stack = vec![1u64];
+    orbit.insert(1u64);
+    while let Some(c) = stack.pop() {
+        for (u, shift) in generators.iter() {
+            let next = ((u * c) % two_d + shift) % two_d;
+            if orbit.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    orbit
+}
+
+pub fn aurifeuillian_split(d: u64, b: u64) -> Option<(Integer, Integer)> {
+    /*
+    returns (L, M) with L * M = Phi_d(b), 1 < L < M
+    None when Phi_d(b) has no Aurifeuillian factorization
+    */
+    if d < 3 || b < 2 {
+        return None;
+    }
+    let (s, t) = _squarefree_decomposition(b);
+    if s == 1 {
+        // b is a square, b^n - 1 already splits algebraically
+        return None;
+    }
+    let phi = _totient_u64(d);
+    let orbit = _root_orbit(d, s);
+    if orbit.len() as u64 != phi {
+        return None;
+    }
+
+    let value = cyclotomic_value(d, &Integer::from(b));
+    let precision = value.significant_bits() / 2 + 64 + 2 * (64 - phi.leading_zeros());
+    let sqrt_b = Float::with_val(precision, s).sqrt() * t;
+    let pi = Float::with_val(precision, Constant::Pi);
+
+    let mut product = Complex::with_val(precision, (1, 0));
+    for c in orbit.iter() {
+        let angle = (&pi * Float::with_val(precision, *c)) / Float::with_val(precision, d);
+        let (sin, cos) = angle.sin_cos(Float::new(precision));
+        let factor = Complex::with_val(precision, (&sqrt_b - cos, -sin));
+        product *= factor;
+    }
+    let (real, _) = product.into_real_imag();
+    let l = real.to_integer()?.abs();
+
+    if l <= 1 || !value.is_divisible(&l) {
+        return None;
+    }
+    let m = (&value / &l).complete();
+    if m <= 1 {
+        return None;
+    }
+    if l < m {
+        Some((l, m))
+    } else {
+        Some((m, l))
+    }
+}
+
+pub fn factor_b_n_pm1(b: u64, n: u64, sign: CunninghamSign) -> CunninghamFactorization {
+    if b < 2 || n == 0 {
+        panic!("need b >= 2 and n >= 1, got b = {}, n = {}", b, n);
+    }
+    let indices: Vec<u64> = match sign {
+        CunninghamSign::Minus => _divisors_u64(n),
+        CunninghamSign::Plus => _divisors_u64(2 * n)
+            .into_iter()
+            .filter(|d| !n.is_multiple_of(*d))
+            .collect(),
+    };
+
+    let base = Integer::from(b);
+    let mut pieces = Vec::new();
+    for d in indices {
+        let value = cyclotomic_value(d, &base);
+        if value == 1 {
+            continue;
+        }
+        match aurifeuillian_split(d, b) {
+            Some((l, m)) => {
+                for (kind, part) in [(PieceKind::L, l), (PieceKind::M, m)] {
+                    pieces.push(CunninghamPiece {
+                        index: d,
+                        kind,
+                        factors: factorint(&part),
+                        value: part,
+                    });
+                }
+            }
+            None => pieces.push(CunninghamPiece {
+                index: d,
+                kind: PieceKind::Full,
+                factors: factorint(&value),
+                value,
+            }),
+        }
+    }
+
+    CunninghamFactorization {
+        base: b,
+        exponent: n,
+        sign,
+        pieces,
+    }
+}
+
+fn _format_factors(factors: &Factorization) -> String {
+    factors
+        .factors()
+        .iter()
+        .map(|(p, e)| {
+            if *e == 1 {
+                p.to_string()
+            } else {
+                format!("{}^{}", p, e)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+impl CunninghamPiece {
+    pub fn label(&self, base: u64, sign: CunninghamSign) -> String {
+        /*
+        Phi_d(b) is the primitive part of b^d - 1 on the minus side
+        and of b^(d / 2) + 1 on the plus side, that exponent is what the tables show
+        Aurifeuillian halves are written as 58L / 58M
+        */
+        let (exponent, symbol) = match sign {
+            CunninghamSign::Minus => (self.index, "-"),
+            CunninghamSign::Plus => (self.index / 2, "+"),
+        };
+        match self.kind {
+            PieceKind::Full => format!("{},{}{}", base, exponent, symbol),
+            PieceKind::L => format!("{},{}L", base, exponent),
+            PieceKind::M => format!("{},{}M", base, exponent),
+        }
+    }
+}
+
+impl CunninghamFactorization {
+    pub fn label(&self) -> String {
+        let symbol = match self.sign {
+            CunninghamSign::Minus => "-",
+            CunninghamSign::Plus => "+",
+        };
+        format!("{},{}{}", self.base, self.exponent, symbol)
+    }
+
+    pub fn value(&self) -> Integer {
+        let power = Integer::from(self.base).pow(self.exponent as u32);
+        match self.sign {
+            CunninghamSign::Minus => power - 1u32,
+            CunninghamSign::Plus => power + 1u32,
+        }
+    }
+
+    pub fn factorization(&self) -> Factorization {
+        self.pieces
+            .iter()
+            .fold(Factorization::new(), |acc, piece| &acc * &piece.factors)
+    }
+}
+
+impl fmt::Display for CunninghamFactorization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /*
+        2,58+ = 5.107367629.536903681
+          2,2+  5
+          2,58L 107367629
+          2,58M 536903681
+        */
+        writeln!(
+            f,
+            "{} = {}",
+            self.label(),
+            _format_factors(&self.factorization())
+        )?;
+        for piece in self.pieces.iter() {
+            writeln!(
+                f,
+                "  {} {}",
+                piece.label(self.base, self.sign),
+                _format_factors(&piece.factors)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cyclotomic_value() {
+        let two = Integer::from(2);
+        assert_eq!(cyclotomic_value(1, &two), 1);
+        assert_eq!(cyclotomic_value(2, &two), 3);
+        assert_eq!(cyclotomic_value(4, &two), 5);
+        assert_eq!(cyclotomic_value(6, &two), 3);
+        assert_eq!(cyclotomic_value(12, &two), 13);
+        assert_eq!(cyclotomic_value(18, &Integer::from(3)), 703);
+        // product over d | n gives back b^n - 1
+        let product: Integer = _divisors_u64(60)
+            .iter()
+            .map(|d| cyclotomic_value(*d, &Integer::from(7)))
+            .product();
+        assert_eq!(product, Integer::from(7).pow(60) - 1u32);
+    }
+
+    #[test]
+    fn test_aurifeuillian_split() {
+        // 2^58 + 1 = 5 * 107367629 * 536903681
+        assert_eq!(
+            aurifeuillian_split(116, 2),
+            Some((Integer::from(107367629), Integer::from(536903681)))
+        );
+        // 3^9 + 1 = 4 * 7 * 19 * 37
+        assert_eq!(
+            aurifeuillian_split(18, 3),
+            Some((Integer::from(19), Integer::from(37)))
+        );
+        // 5^5 - 1 = 4 * 11 * 71
+        assert_eq!(
+            aurifeuillian_split(5, 5),
+            Some((Integer::from(11), Integer::from(71)))
+        );
+        assert_eq!(aurifeuillian_split(12, 2), None);
+        assert_eq!(aurifeuillian_split(7, 2), None);
+        assert_eq!(aurifeuillian_split(10, 4), None);
+    }
+
+    #[test]
+    fn test_aurifeuillian_split_other_bases() {
+        // s = 2, 3 mod 4 splits at d = 2s * odd, 12 = 3 * 2^2 behaves like base 3
+        for (d, b) in [
+            (12u64, 6u64),
+            (36, 6),
+            (14, 7),
+            (42, 7),
+            (20, 10),
+            (60, 10),
+            (6, 12),
+            (18, 12),
+        ] {
+            let (l, m) = aurifeuillian_split(d, b).unwrap();
+            assert_eq!(l * m, cyclotomic_value(d, &Integer::from(b)));
+        }
+    }
+
+    #[test]
+    fn test_factor_b_n_pm1() {
+        let result = factor_b_n_pm1(2, 58, CunninghamSign::Plus);
+        assert_eq!(result.factorization().value(), result.value());
+        assert_eq!(result.pieces.len(), 3);
+        assert_eq!(
+            result.to_string(),
+            "2,58+ = 5.107367629.536903681\n  2,2+ 5\n  2,58L 107367629\n  2,58M 536903681\n"
+        );
+
+        let result = factor_b_n_pm1(2, 12, CunninghamSign::Minus);
+        assert_eq!(result.label(), "2,12-");
+        assert_eq!(
+            result.factorization(),
+            Factorization::from(vec![(3, 2), (5, 1), (7, 1), (13, 1)])
+        );
+
+        let result = factor_b_n_pm1(10, 15, CunninghamSign::Minus);
+        assert_eq!(result.factorization().value(), result.value());
+        assert!(result
+            .factorization()
+            .primes()
+            .all(|p| p.is_probably_prime(30) != rug::integer::IsPrime::No));
+    }
+
+    #[test]
+    fn test_factor_b_n_pm1_aurifeuillian_minus() {
+        // 5^15 - 1, Phi_5(5) and Phi_15(5) both split
+        let result = factor_b_n_pm1(5, 15, CunninghamSign::Minus);
+        let labels: Vec<String> = result
+            .pieces
+            .iter()
+            .map(|piece| piece.label(5, CunninghamSign::Minus))
+            .collect();
+        assert_eq!(
+            labels,
+            vec!["5,1-", "5,3-", "5,5L", "5,5M", "5,15L", "5,15M"]
+        );
+        assert_eq!(result.factorization().value(), result.value());
+    }
+}
@@ -0,0 +1,353 @@
+/*
+discrete logarithms, the smallest x >= 0 with b^x = a mod n
+
+b coprime to n: Pohlig-Hellman over the order of b, for every q^e || order
+    x mod q^e one base q digit at a time, each digit is a log in the subgroup
+    of order q, found with baby-step giant-step when q is small and with
+    Pollard's rho otherwise, the x mod q^e are glued back with the CRT
+b not coprime to n: divide the common factor out of the congruence until it is,
+every division costs one power of b so the small x are checked on the way
+n prime with a subgroup too large for rho: index calculus, see index_calculus.rs
+*/
+use std::collections::HashMap;
+
+use rug::ops::Pow;
+use rug::{Complete, Integer};
+
+use super::chineese_remainder_theorem::chinese_remainder_theorem_impl;
+use super::factorization::{factorint, is_probable_prime, Factorization};
+use super::index_calculus::IndexCalculus;
+use super::num_utils::pow_mod;
+use super::primitive_root::multiplicative_order;
+
+// prime orders below this use baby-step giant-step, sqrt of it baby steps at most
+const BSGS_THRESHOLD: u64 = 1 << 32;
+// fresh starting points for rho before giving up on a subgroup
+const RHO_ATTEMPTS: u64 = 32;
+// prime moduli whose largest subgroup has more bits than this go to index calculus
+const INDEX_CALCULUS_BITS: u32 = 56;
+
+fn _no_log() -> String {
+    "Log does not exist".to_string()
+}
+
+pub fn baby_step_giant_step(
+    a: &Integer,
+    b: &Integer,
+    n: &Integer,
+    order: &Integer,
+) -> Option<Integer> {
+    /*
+    smallest x < order with b^x = a mod n, b invertible mod n
+    b^(i m + j) = a  <=>  b^j = a (b^-m)^i
+    */
+    let m = order.clone().sqrt() + 1u32;
+    let steps = m
+        .to_u64()
+        .expect("order too large for baby-step giant-step");
+    let mut baby: HashMap<Integer, u64> = HashMap::with_capacity(steps as usize);
+    let mut power = Integer::from(1).modulo(n);
+    for j in 0..steps {
+        baby.entry(power.clone()).or_insert(j);
+        power = (power * b).modulo(n);
+    }
+    let giant = pow_mod(b, &(-m.clone()), n);
+    let mut current = a.clone().modulo(n);
+    for i in 0..steps {
+        if let Some(j) = baby.get(&current) {
+            let x = (&m * i).complete() + j;
+            if x < *order {
+                return Some(x);
+            }
+        }
+        current = (current * &giant).modulo(n);
+    }
+    None
+}
+
+fn _rho_step(
+    state: &mut (Integer, Integer, Integer),
+    a: &Integer,
+    b: &Integer,
+    n: &Integer,
+    order: &Integer,
+) {
+    // x = b^alpha a^beta, split by x mod 3 into square, times b, times a
+    let (x, alpha, beta) = state;
+    match x.mod_u(3) {
+        0 => {
+            x.square_mut();
+            *alpha <<= 1;
+            *beta <<= 1;
+        }
+        1 => {
+            *x *= b;
+            *alpha += 1;
+        }
+        _ => {
+            *x *= a;
+            *beta += 1;
+        }
+    }
+    x.modulo_mut(n);
+    alpha.modulo_mut(order);
+    beta.modulo_mut(order);
+}
+
+pub fn pollard_rho_log(a: &Integer, b: &Integer, n: &Integer, order: &Integer) -> Option<Integer> {
+    /*
+    x with b^x = a mod n where b has prime order, Floyd's cycle finding on the walk
+    above, a collision b^alpha1 a^beta1 = b^alpha2 a^beta2 gives
+        (beta1 - beta2) x = alpha2 - alpha1 mod order
+    None when a is not a power of b
+    */
+    let a = a.clone().modulo(n);
+    let b = b.clone().modulo(n);
+    if a == 1 {
+        return Some(Integer::new());
+    }
+    for seed in 1..=RHO_ATTEMPTS {
+        let alpha = Integer::from(seed).modulo(order);
+        let beta = Integer::from(seed * 7 + 1).modulo(order);
+        let x = (pow_mod(&b, &alpha, n) * pow_mod(&a, &beta, n)).modulo(n);
+        let mut tortoise = (x, alpha, beta);
+        let mut hare = tortoise.clone();
+        loop {
+            _rho_step(&mut tortoise, &a, &b, n, order);
+            _rho_step(&mut hare, &a, &b, n, order);
+            _rho_step(&mut hare, &a, &b, n, order);
+            if tortoise.0 == hare.0 {
+                break;
+            }
+        }
+        let denominator = (&tortoise.2 - &hare.2).complete().modulo(order);
+        if denominator == 0 {
+            continue;
+        }
+        let inverse = match denominator.invert(order) {
+            Ok(inverse) => inverse,
+            Err(_) => continue,
+        };
+        let x = ((&hare.1 - &tortoise.1).complete() * inverse).modulo(order);
+        /* a outside the subgroup still collides, the check weeds that out */
+        return if pow_mod(&b, &x, n) == a {
+            Some(x)
+        } else {
+            None
+        };
+    }
+    None
+}
+
+fn _log_prime_order(a: &Integer, b: &Integer, n: &Integer, q: &Integer) -> Option<Integer> {
+    if *q < BSGS_THRESHOLD {
+        baby_step_giant_step(a, b, n, q)
+    } else {
+        pollard_rho_log(a, b, n, q)
+    }
+}
+
+pub fn pohlig_hellman(
+    a: &Integer,
+    b: &Integer,
+    n: &Integer,
+    order: &Integer,
+    order_factorization: &Factorization,
+) -> Option<Integer> {
+    // x mod order with b^x = a mod n, order is the order of b mod n
+    let mut residues = Vec::new();
+    let mut moduli = Vec::new();
+    for (q, e) in order_factorization.factors().iter() {
+        let qe = q.clone().pow(*e);
+        let cofactor = (order / &qe).complete();
+        // b_q has order q^e, gamma = b_q^(q^(e - 1)) has order q
+        let b_q = pow_mod(b, &cofactor, n);
+        let a_q = pow_mod(a, &cofactor, n);
+        let gamma = pow_mod(&b_q, &q.clone().pow(e - 1), n);
+        let b_q_inverse = b_q.clone().invert(n).ok()?;
+
+        let mut x = Integer::new();
+        let mut q_k = Integer::from(1);
+        for k in 0..*e {
+            /* strip the digits found so far, what is left lies in the order q subgroup */
+            let shifted = (pow_mod(&b_q_inverse, &x, n) * &a_q).modulo(n);
+            let h = pow_mod(&shifted, &q.clone().pow(e - 1 - k), n);
+            let digit = _log_prime_order(&h, &gamma, n, q)?;
+            x += digit * &q_k;
+            q_k *= q;
+        }
+        residues.push(x);
+        moduli.push(qe);
+    }
+    if moduli.is_empty() {
+        return Some(Integer::new());
+    }
+    /* the CRT result carries the sign of the bezout coefficients */
+    chinese_remainder_theorem_impl(&residues, &moduli)
+        .ok()
+        .map(|x| x.modulo(order))
+}
+
+pub fn discrete_log(a: &Integer, b: &Integer, n: &Integer) -> Result<Integer, String> {
+    // smallest x >= 0 with b^x = a mod n
+    if n.cmp0() != std::cmp::Ordering::Greater {
+        return Err("n should be positive".to_string());
+    }
+    let mut n = n.clone();
+    let mut a = a.clone().modulo(&n);
+    let b = b.clone().modulo(&n);
+
+    /*
+    b^x = a with g = gcd(b, n) > 1 and x >= 1 means g | a and
+    (b / g) b^(x - 1) = a / g mod n / g, keep the b / g factors in scale
+    */
+    let mut shift = 0u32;
+    let mut scale = Integer::from(1).modulo(&n);
+    loop {
+        if a == scale {
+            return Ok(Integer::from(shift));
+        }
+        let g = b.gcd_ref(&n).complete();
+        if g == 1 {
+            break;
+        }
+        if !a.is_divisible(&g) {
+            return Err(_no_log());
+        }
+        a /= &g;
+        n /= &g;
+        scale = (scale * (&b / &g).complete()).modulo(&n);
+        a.modulo_mut(&n);
+        shift += 1;
+    }
+    if n == 1 {
+        return Ok(Integer::from(shift));
+    }
+    /* scale is a unit mod n now, b^y = a / scale */
+    let scale_inverse = scale.invert(&n).map_err(|_| _no_log())?;
+    let target = (a * scale_inverse).modulo(&n);
+    if target.gcd_ref(&n).complete() != 1 {
+        return Err(_no_log());
+    }
+    let b = b.modulo(&n);
+    let order = multiplicative_order(&b, &n)?;
+    let order_factorization = factorint(&order);
+    let largest_bits = order_factorization
+        .factors()
+        .keys()
+        .next_back()
+        .map_or(0, |q| q.significant_bits());
+    if largest_bits > INDEX_CALCULUS_BITS && is_probable_prime(&n) {
+        if let Ok(calculus) = IndexCalculus::new(&n, &b) {
+            return calculus.log(&target).map(|y| y + shift);
+        }
+    }
+    let y = pohlig_hellman(&target, &b, &n, &order, &order_factorization).ok_or_else(_no_log)?;
+    if pow_mod(&b, &y, &n) != target {
+        return Err(_no_log());
+    }
+    Ok(y + shift)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_log(a: u64, b: u64, n: u64) -> Option<u64> {
+        let mut x = 1 % n;
+        for k in 0..=2 * n {
+            if x == a % n {
+                return Some(k);
+            }
+            x = x * b % n;
+        }
+        None
+    }
+
+    #[test]
+    fn test_discrete_log_small() {
+        let log = |a: u64, b: u64, n: u64| {
+            discrete_log(&Integer::from(a), &Integer::from(b), &Integer::from(n))
+        };
+        assert_eq!(log(15, 7, 41).unwrap(), 3);
+        assert_eq!(log(1, 7, 2).unwrap(), 0);
+        assert_eq!(log(3, 7, 5).unwrap(), 3);
+        assert!(log(3, 2, 0).is_err());
+        for n in 1..60u64 {
+            for b in 0..n {
+                for a in 0..n {
+                    let expected = brute_log(a, b, n);
+                    match log(a, b, n) {
+                        Ok(x) => {
+                            assert_eq!(Some(x.to_u64().unwrap()), expected, "{} {} {}", a, b, n)
+                        }
+                        Err(_) => assert_eq!(expected, None, "{} {} {}", a, b, n),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_baby_step_giant_step() {
+        let n = Integer::from(1_000_003u64);
+        let b = Integer::from(2);
+        let order = multiplicative_order(&b, &n).unwrap();
+        for x in [0u64, 1, 17, 999, 500_000] {
+            let a = pow_mod(&b, &Integer::from(x), &n);
+            let found = baby_step_giant_step(&a, &b, &n, &order).unwrap();
+            assert_eq!(found, Integer::from(x).modulo(&order));
+        }
+    }
+
+    #[test]
+    fn test_pollard_rho_log() {
+        // p = 2 q + 1 with q prime, 4 generates the subgroup of order q
+        let p = Integer::from(2_000_000_579u64);
+        let q = Integer::from(1_000_000_289u64);
+        assert!(crate::math::factorization::is_probable_prime(&p));
+        assert!(crate::math::factorization::is_probable_prime(&q));
+        let b = Integer::from(4);
+        for x in [5u64, 123_456_789, 999_999_999] {
+            let a = pow_mod(&b, &Integer::from(x), &p);
+            assert_eq!(pollard_rho_log(&a, &b, &p, &q).unwrap(), x);
+        }
+        // -1 has order 2 so it is not a power of 4
+        assert_eq!(pollard_rho_log(&(&p - Integer::from(1)), &b, &p, &q), None);
+    }
+
+    #[test]
+    fn test_discrete_log_64_bit() {
+        // p - 1 = 4 * 43 * 67 * 193 * 809383 * 10247197, 2 * 3^4 * 17 * 23 * 319279 * 456065899
+        // and the order 2^60 of 3 mod 2^62
+        let cases = [
+            (
+                Integer::from(18_446_744_073_709_551_533u64),
+                Integer::from(2),
+            ),
+            (
+                Integer::from(9_223_372_036_854_775_783u64),
+                Integer::from(5),
+            ),
+            (Integer::from(1u64 << 62), Integer::from(3)),
+        ];
+        for (n, b) in cases.iter() {
+            let order = multiplicative_order(b, n).unwrap();
+            for x in [0u64, 1, 65_537, 1 << 40, 0x0123_4567_89ab_cdef] {
+                let x = Integer::from(x).modulo(&order);
+                let a = pow_mod(b, &x, n);
+                assert_eq!(discrete_log(&a, b, n).unwrap(), x, "n = {}", n);
+            }
+        }
+        // a safe prime, the subgroup of order (p - 1) / 2 goes to index calculus
+        let n = Integer::from(18_446_744_073_709_550_147u64);
+        let b = Integer::from(5);
+        let x = Integer::from(0x0123_4567_89ab_cdefu64);
+        let a = pow_mod(&b, &x, &n);
+        assert_eq!(discrete_log(&a, &b, &n).unwrap(), x);
+        // p = 3 mod 4, so -1 is not a square and never a power of 4
+        let n = Integer::from(1_000_000_007u64);
+        let minus_one = Integer::from(&n - 1u32);
+        assert!(discrete_log(&minus_one, &Integer::from(4), &n).is_err());
+    }
+}
@@ -0,0 +1,574 @@
+/*
+u64 native factoring
+
+most of the numbers we factor fit in a machine word
+for those going through rug is wasteful, every step allocates
+everything here works on u64 with u128 for the intermediate products
+*/
+use super::miller_rabin_bases::get_miller_rabin_bases_u64;
+use super::static_data::SMALL_PRIME_CACHE;
+
+// square free products of small odd primes, Gower & Wagstaff
+const SQUFOF_MULTIPLIERS: [u64; 16] = [
+    1,
+    3,
+    5,
+    7,
+    11,
+    3 * 5,
+    3 * 7,
+    3 * 11,
+    5 * 7,
+    5 * 11,
+    7 * 11,
+    3 * 5 * 7,
+    3 * 5 * 11,
+    3 * 7 * 11,
+    5 * 7 * 11,
+    3 * 5 * 7 * 11,
+];
+
+// below this the methods based on squares (lehman, hart) beat rho
+const LEHMAN_LIMIT: u64 = 1 << 40;
+const HART_LIMIT: u64 = 1 << 42;
+const SQUFOF_LIMIT: u64 = 1 << 62;
+
+#[inline]
+pub fn mul_mod_u64(a: u64, b: u64, m: u64) -> u64 {
+    ((a as u128 * b as u128) % m as u128) as u64
+}
+
+pub fn pow_mod_u64(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    if m == 1 {
+        return 0;
+    }
+    let mut result = 1u64;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod_u64(result, base, m);
+        }
+        base = mul_mod_u64(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+pub fn gcd_u64(mut a: u64, mut b: u64) -> u64 {
+    if a == 0 {
+        return b;
+    }
+    if b == 0 {
+        return a;
+    }
+    let shift = (a | b).trailing_zeros();
+    a >>= a.trailing_zeros();
+    while b != 0 {
+        b >>= b.trailing_zeros();
+        if a > b {
+            std::mem::swap(&mut a, &mut b);
+        }
+        b -= a;
+    }
+    a << shift
+}
+
+pub fn isqrt_u64(n: u64) -> u64 {
+    // f64 gets us within one of the answer, fix it up with integer checks
+    let mut r = (n as f64).sqrt() as u64;
+    while r as u128 * r as u128 > n as u128 {
+        r -= 1;
+    }
+    while (r + 1) as u128 * (r + 1) as u128 <= n as u128 {
+        r += 1;
+    }
+    r
+}
+
+pub fn isqrt_u128(n: u128) -> u128 {
+    let mut r = (n as f64).sqrt() as u128;
+    while r * r > n {
+        r -= 1;
+    }
+    while (r + 1) * (r + 1) <= n {
+        r += 1;
+    }
+    r
+}
+
+pub fn is_square_u64(n: u64) -> Option<u64> {
+    // squares mod 16 are 0, 1, 4, 9
+    if (0x213_u64 >> (n & 15)) & 1 == 0 {
+        return None;
+    }
+    let r = isqrt_u64(n);
+    if r * r == n {
+        Some(r)
+    } else {
+        None
+    }
+}
+
+fn _miller_rabin_test_u64(n: u64, base: u64, s: u32, t: u64) -> bool {
+    let mut b = pow_mod_u64(base, t, n);
+    if b == 1 || b == n - 1 {
+        return true;
+    }
+    for _ in 1..s {
+        b = mul_mod_u64(b, b, n);
+        if b == n - 1 {
+            return true;
+        }
+        if b == 1 {
+            return false;
+        }
+    }
+    false
+}
+
+pub fn is_prime_u64(n: u64) -> bool {
+    /*
+    deterministic for the whole u64 range
+    uses the same base table as miller_rabin_single
+    */
+    if n < 2 {
+        return false;
+    }
+    for &p in SMALL_PRIME_CACHE[..16].iter() {
+        let p = p as u64;
+        if n == p {
+            return true;
+        }
+        if n.is_multiple_of(p) {
+            return false;
+        }
+    }
+    if n < 53 * 53 {
+        return true;
+    }
+
+    let s = (n - 1).trailing_zeros();
+    let t = (n - 1) >> s;
+    for &base in get_miller_rabin_bases_u64(n) {
+        let base = base as u64 % n;
+        if base >= 2 && !_miller_rabin_test_u64(n, base, s, t) {
+            return false;
+        }
+    }
+    true
+}
+
+pub fn squfof(n: u64) -> Option<u64> {
+    /*
+    Shanks' square forms factorization
+    https://en.wikipedia.org/wiki/Shanks%27s_square_forms_factorization
+    the continued fraction expansion of sqrt(k * n) is walked until
+    a square form shows up, then the reverse cycle finds the factor
+    */
+    if n < 4 {
+        return None;
+    }
+    if n.is_multiple_of(2) {
+        return Some(2);
+    }
+    if let Some(root) = is_square_u64(n) {
+        return Some(root);
+    }
+
+    let s = isqrt_u64(n);
+    let bound = 3 * 2 * isqrt_u64(2 * s) as i128;
+
+    for &k in SQUFOF_MULTIPLIERS.iter() {
+        let d = k as i128 * n as i128;
+        let p0 = isqrt_u128(d as u128) as i128;
+        let mut p_prev = p0;
+        let mut p = p0;
+        let mut q_prev = 1i128;
+        let mut q = d - p0 * p0;
+        if q == 0 {
+            // k * n is a square, the multiplier shares a factor with n
+            let g = gcd_u64(n, k);
+            if g != 1 && g != n {
+                return Some(g);
+            }
+            continue;
+        }
+
+        let mut i = 2;
+        let mut r = 0;
+        let mut found = false;
+        while i < bound {
+            let b = (p0 + p) / q;
+            p = b * q - p;
+            let q_tmp = q;
+            q = q_prev + b * (p_prev - p);
+            if i & 1 == 0 {
+                let root = isqrt_u128(q as u128) as i128;
+                if root * root == q {
+                    r = root;
+                    found = true;
+                    break;
+                }
+            }
+            q_prev = q_tmp;
+            p_prev = p;
+            i += 1;
+        }
+        if !found {
+            continue;
+        }
+
+        let b = (p0 - p) / r;
+        p += b * r;
+        p_prev = p;
+        q_prev = r;
+        q = (d - p_prev * p_prev) / q_prev;
+        loop {
+            let b = (p0 + p) / q;
+            p_prev = p;
+            p = b * q - p;
+            let q_tmp = q;
+            q = q_prev + b * (p_prev - p);
+            q_prev = q_tmp;
+            if p == p_prev {
+                break;
+            }
+        }
+        let g = gcd_u64(n, q_prev as u64);
+        if g != 1 && g != n {
+            return Some(g);
+        }
+    }
+    None
+}
+
+pub fn hart_olf(n: u64, max_iterations: u64) -> Option<u64> {
+    /*
+    Hart's one line factoring
+    s = ceil(sqrt(n * i)), if s^2 mod n is a square t^2 then gcd(s - t, n) splits n
+    the 480 multiplier makes s^2 - n * i more likely to be a square
+    */
+    if n < 4 {
+        return None;
+    }
+    if n.is_multiple_of(2) {
+        return Some(2);
+    }
+    if let Some(root) = is_square_u64(n) {
+        return Some(root);
+    }
+    let n_big = n as u128;
+    for i in 1..=max_iterations {
+        let ni = n_big * 480 * i as u128;
+        let mut s = isqrt_u128(ni);
+        if s * s != ni {
+            s += 1;
+        }
+        let m = ((s * s) % n_big) as u64;
+        if let Some(t) = is_square_u64(m) {
+            let s_mod = (s % n_big) as u64;
+            let g = gcd_u64(s_mod.abs_diff(t), n);
+            if g != 1 && g != n {
+                return Some(g);
+            }
+        }
+    }
+    None
+}
+
+pub fn lehman(n: u64) -> Option<u64> {
+    /*
+    Lehman's method, O(n^(1/3))
+    trial division up to n^(1/3), then look for a^2 - 4kn = b^2
+    with k <= n^(1/3) and a in a short window above sqrt(4kn)
+    */
+    if n < 4 {
+        return None;
+    }
+    if n.is_multiple_of(2) {
+        return Some(2);
+    }
+    let mut cube_root = (n as f64).cbrt() as u64;
+    while ((cube_root + 1) as u128).pow(3) <= n as u128 {
+        cube_root += 1;
+    }
+
+    let mut d = 3u64;
+    while d <= cube_root {
+        if n.is_multiple_of(d) {
+            return Some(d);
+        }
+        d += 2;
+    }
+
+    let sixth_root = (n as f64).powf(1.0 / 6.0);
+    let n_big = n as u128;
+    for k in 1..=cube_root.max(1) as u128 {
+        let four_kn = 4 * k * n_big;
+        let mut a = isqrt_u128(four_kn);
+        if a * a < four_kn {
+            a += 1;
+        }
+        let a_max = isqrt_u128(four_kn) + (sixth_root / (4.0 * (k as f64).sqrt())) as u128 + 1;
+        while a <= a_max {
+            let b_squared = a * a - four_kn;
+            let b = isqrt_u128(b_squared);
+            if b * b == b_squared {
+                let g = gcd_u64(((a + b) % n_big) as u64, n);
+                if g != 1 && g != n {
+                    return Some(g);
+                }
+            }
+            a += 1;
+        }
+    }
+    None
+}
+
+pub fn pollard_rho_u64(n: u64, seed: u64) -> Option<u64> {
+    /*
+    Brent's variant of pollard rho
+    gcd is taken on a product of 128 differences at a time
+    and we backtrack one step at a time if the batch hits n
+    */
+    if n < 4 {
+        return None;
+    }
+    if n.is_multiple_of(2) {
+        return Some(2);
+    }
+    let c = seed % (n - 1) + 1;
+    let f = |x: u64| ((mul_mod_u64(x, x, n) as u128 + c as u128) % n as u128) as u64;
+
+    let batch = 128;
+    let mut y = seed % n;
+    let mut r = 1u64;
+    let mut q = 1u64;
+    let mut g = 1u64;
+    let mut x = y;
+    let mut ys = y;
+
+    while g == 1 {
+        x = y;
+        for _ in 0..r {
+            y = f(y);
+        }
+        let mut k = 0;
+        while k < r && g == 1 {
+            ys = y;
+            for _ in 0..batch.min(r - k) {
+                y = f(y);
+                q = mul_mod_u64(q, x.abs_diff(y), n);
+            }
+            g = gcd_u64(q, n);
+            k += batch;
+        }
+        r <<= 1;
+    }
+
+    if g == n {
+        loop {
+            ys = f(ys);
+            g = gcd_u64(x.abs_diff(ys), n);
+            if g > 1 {
+                break;
+            }
+        }
+    }
+
+    if g == n {
+        None
+    } else {
+        Some(g)
+    }
+}
+
+pub fn find_factor_u64(n: u64) -> Option<u64> {
+    /*
+    returns a non trivial factor of a composite n
+    picks the method by size, rho is the fallback since it always gets there
+    */
+    if n < 4 || is_prime_u64(n) {
+        return None;
+    }
+    if n.is_multiple_of(2) {
+        return Some(2);
+    }
+    if let Some(root) = is_square_u64(n) {
+        return Some(root);
+    }
+
+    let found = if n < LEHMAN_LIMIT {
+        lehman(n)
+    } else if n < HART_LIMIT {
+        hart_olf(n, isqrt_u64(isqrt_u64(n)) * 4)
+    } else if n < SQUFOF_LIMIT {
+        squfof(n)
+    } else {
+        None
+    };
+    if found.is_some() {
+        return found;
+    }
+
+    (1..).find_map(|seed| pollard_rho_u64(n, seed))
+}
+
+pub fn factor_u64(mut n: u64) -> Vec<(u64, u32)> {
+    /*
+    full factorization as sorted (prime, exponent) pairs
+    small primes are stripped with trial division first,
+    what is left is split with find_factor_u64
+    */
+    let mut factors: Vec<(u64, u32)> = Vec::new();
+    if n < 2 {
+        return factors;
+    }
+
+    for &p in SMALL_PRIME_CACHE.iter() {
+        let p = p as u64;
+        if p * p > n {
+            break;
+        }
+        if n.is_multiple_of(p) {
+            let mut exp = 0;
+            while n.is_multiple_of(p) {
+                n /= p;
+                exp += 1;
+            }
+            factors.push((p, exp));
+        }
+    }
+
+    let mut stack = vec![n];
+    while let Some(m) = stack.pop() {
+        if m == 1 {
+            continue;
+        }
+        if is_prime_u64(m) {
+            match factors.iter_mut().find(|(p, _)| *p == m) {
+                Some((_, exp)) => *exp += 1,
+                None => factors.push((m, 1)),
+            }
+            continue;
+        }
+        let d = find_factor_u64(m).expect("composite numbers always split");
+        stack.push(d);
+        stack.push(m / d);
+    }
+
+    factors.sort_unstable();
+    factors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn product(factors: &[(u64, u32)]) -> u64 {
+        factors.iter().map(|(p, e)| p.pow(*e)).product()
+    }
+
+    #[test]
+    fn test_is_prime_u64() {
+        assert!(!is_prime_u64(0));
+        assert!(!is_prime_u64(1));
+        assert!(is_prime_u64(2));
+        assert!(is_prime_u64(9973));
+        assert!(!is_prime_u64(9975));
+        assert!(is_prime_u64(1_000_000_007));
+        assert!(!is_prime_u64(3_215_031_751));
+        assert!(is_prime_u64(18446744073709551557));
+        assert!(!is_prime_u64(18446744073709551555));
+    }
+
+    #[test]
+    fn test_is_prime_u64_matches_sieve() {
+        for n in 0..10_000u64 {
+            let expected = SMALL_PRIME_CACHE.binary_search(&(n as u32)).is_ok();
+            assert_eq!(is_prime_u64(n), expected, "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn test_squfof() {
+        let n = 600851475143u64;
+        let d = squfof(n).unwrap();
+        assert!(d > 1 && d < n && n.is_multiple_of(d));
+
+        let n = 1_000_000_007u64 * 998_244_353;
+        let d = squfof(n).unwrap();
+        assert!(d == 1_000_000_007 || d == 998_244_353);
+    }
+
+    #[test]
+    fn test_hart_olf() {
+        let n = 1_000_003u64 * 1_000_033;
+        let d = hart_olf(n, 100_000).unwrap();
+        assert!(d == 1_000_003 || d == 1_000_033);
+    }
+
+    #[test]
+    fn test_lehman() {
+        let n = 10_007u64 * 1_000_003;
+        let d = lehman(n).unwrap();
+        assert!(d == 10_007 || d == 1_000_003);
+        assert!(matches!(lehman(101 * 103), Some(101) | Some(103)));
+        // the cube root check must not overflow near u64::MAX
+        assert_eq!(lehman(u64::MAX - 58), None);
+        let d = lehman(4_294_967_291 * 4_294_967_279).unwrap();
+        assert!(d == 4_294_967_291 || d == 4_294_967_279);
+    }
+
+    #[test]
+    fn test_pollard_rho_u64() {
+        let n = 4_294_967_291u64 * 4_294_967_279;
+        let d = (1..).find_map(|seed| pollard_rho_u64(n, seed)).unwrap();
+        assert!(d == 4_294_967_291 || d == 4_294_967_279);
+        // no modular arithmetic below 4, rho used to divide by n - 1 = 0
+        for n in 0..4 {
+            assert_eq!(pollard_rho_u64(n, 1), None);
+        }
+        assert_eq!(pollard_rho_u64(4, 1), Some(2));
+    }
+
+    #[test]
+    fn test_factor_u64() {
+        assert!(factor_u64(1).is_empty());
+        assert_eq!(factor_u64(2), vec![(2, 1)]);
+        assert_eq!(factor_u64(360), vec![(2, 3), (3, 2), (5, 1)]);
+        assert_eq!(
+            factor_u64(600851475143),
+            vec![(71, 1), (839, 1), (1471, 1), (6857, 1)]
+        );
+        assert_eq!(
+            factor_u64(u64::MAX),
+            vec![
+                (3, 1),
+                (5, 1),
+                (17, 1),
+                (257, 1),
+                (641, 1),
+                (65537, 1),
+                (6700417, 1)
+            ]
+        );
+        assert_eq!(factor_u64(1 << 63), vec![(2, 63)]);
+    }
+
+    #[test]
+    fn test_factor_u64_products() {
+        let numbers = [
+            999_999_999_989u64 * 17,
+            4_294_967_291u64 * 4_294_967_279,
+            1_000_003u64 * 1_000_003 * 1_000_003,
+            18446744073709551557,
+            9_999_999_967u64 * 1_000_003,
+        ];
+        for n in numbers {
+            let factors = factor_u64(n);
+            assert_eq!(product(&factors), n);
+            assert!(factors.iter().all(|(p, _)| is_prime_u64(*p)));
+        }
+    }
+}
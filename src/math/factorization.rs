@@ -0,0 +1,474 @@
+/*
+prime -> exponent map and everything we derive from it
+
+factorint is the general entry point for any positive Integer
+anything that fits a u64 goes to factor_u64, bigger numbers get
+trial division, perfect power detection and pollard rho on rug Integers
+rho is O(sqrt(p)) in the smallest factor p, so numbers whose two smallest
+prime factors both have more than ~20 digits are out of reach for now
+
+note: above 3317044064679887385961981 the prime checks are probabilistic
+(rug is_probably_prime with 30 rounds) so the factorization is not certified
+*/
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::ops::Mul;
+
+use rug::integer::IsPrime;
+use rug::ops::Pow;
+use rug::{Complete, Integer};
+
+use super::factor_u64::factor_u64;
+use super::perfect_power::is_perfect_power;
+use super::static_data::SMALL_PRIME_CACHE;
+
+const PRIME_TEST_ROUNDS: u32 = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Factorization {
+    factors: BTreeMap<Integer, u32>,
+}
+
+impl Factorization {
+    pub fn new() -> Self {
+        Self {
+            factors: BTreeMap::new(),
+        }
+    }
+
+    pub fn from_pairs(pairs: &[(Integer, u32)]) -> Self {
+        let mut result = Self::new();
+        for (p, e) in pairs {
+            result.insert(p.clone(), *e);
+        }
+        result
+    }
+
+    pub fn insert(&mut self, prime: Integer, exponent: u32) {
+        if exponent == 0 {
+            return;
+        }
+        *self.factors.entry(prime).or_insert(0) += exponent;
+    }
+
+    pub fn factors(&self) -> &BTreeMap<Integer, u32> {
+        &self.factors
+    }
+
+    pub fn primes(&self) -> impl Iterator<Item = &Integer> {
+        self.factors.keys()
+    }
+
+    pub fn exponent(&self, prime: &Integer) -> u32 {
+        *self.factors.get(prime).unwrap_or(&0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.factors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.factors.is_empty()
+    }
+
+    pub fn value(&self) -> Integer {
+        self.factors
+            .iter()
+            .map(|(p, e)| p.clone().pow(*e))
+            .product()
+    }
+
+    pub fn divisors(&self) -> Divisors {
+        Divisors::new(self)
+    }
+
+    pub fn num_divisors(&self) -> Integer {
+        self.factors
+            .values()
+            .map(|e| Integer::from(*e + 1))
+            .product()
+    }
+
+    pub fn sigma_k(&self, k: u32) -> Integer {
+        /*
+        sum of the k-th powers of the divisors
+        multiplicative, and for a prime power
+        sigma_k(p^e) = (p^(k(e + 1)) - 1) / (p^k - 1)
+        */
+        if k == 0 {
+            return self.num_divisors();
+        }
+        self.factors
+            .iter()
+            .map(|(p, e)| {
+                let pk = p.clone().pow(k);
+                let numerator = pk.clone().pow(e + 1) - 1u32;
+                numerator / (pk - 1u32)
+            })
+            .product()
+    }
+
+    pub fn totient(&self) -> Integer {
+        // phi(p^e) = p^(e - 1) (p - 1)
+        self.factors
+            .iter()
+            .map(|(p, e)| p.clone().pow(e - 1) * (p - 1u32).complete())
+            .product()
+    }
+
+    pub fn carmichael_lambda(&self) -> Integer {
+        /*
+        exponent of the group (Z/nZ)*
+        lcm of lambda(p^e), which is phi(p^e) except
+        lambda(2^e) = 2^(e - 2) for e >= 3
+        */
+        let mut result = Integer::from(1);
+        for (p, e) in self.factors.iter() {
+            let lambda = if *p == 2 && *e >= 3 {
+                Integer::from(1) << (e - 2)
+            } else {
+                p.clone().pow(e - 1) * (p - 1u32).complete()
+            };
+            result.lcm_mut(&lambda);
+        }
+        result
+    }
+
+    pub fn radical(&self) -> Integer {
+        self.factors.keys().product()
+    }
+
+    pub fn is_squarefree(&self) -> bool {
+        self.factors.values().all(|e| *e == 1)
+    }
+
+    pub fn mobius(&self) -> i32 {
+        if !self.is_squarefree() {
+            return 0;
+        }
+        if self.factors.len().is_multiple_of(2) {
+            1
+        } else {
+            -1
+        }
+    }
+
+    pub fn gcd(&self, other: &Self) -> Self {
+        let mut result = Self::new();
+        for (p, e) in self.factors.iter() {
+            let common = (*e).min(other.exponent(p));
+            result.insert(p.clone(), common);
+        }
+        result
+    }
+
+    pub fn lcm(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        for (p, e) in other.factors.iter() {
+            let current = result.exponent(p);
+            if *e > current {
+                result.insert(p.clone(), e - current);
+            }
+        }
+        result
+    }
+}
+
+impl From<Vec<(u64, u32)>> for Factorization {
+    fn from(pairs: Vec<(u64, u32)>) -> Self {
+        let mut result = Self::new();
+        for (p, e) in pairs {
+            result.insert(Integer::from(p), e);
+        }
+        result
+    }
+}
+
+impl Mul for &Factorization {
+    type Output = Factorization;
+
+    fn mul(self, other: &Factorization) -> Factorization {
+        let mut result = self.clone();
+        for (p, e) in other.factors.iter() {
+            result.insert(p.clone(), *e);
+        }
+        result
+    }
+}
+
+impl Mul for Factorization {
+    type Output = Factorization;
+
+    fn mul(self, other: Factorization) -> Factorization {
+        &self * &other
+    }
+}
+
+pub struct Divisors {
+    /*
+    sorted and lazy, nothing is generated before it is asked for
+    every divisor is built by multiplying primes in non decreasing order,
+    that gives each one a single parent so a min heap never sees duplicates
+    heap entries are (divisor, index of its largest prime, exponent of that prime)
+    */
+    primes: Vec<(Integer, u32)>,
+    heap: BinaryHeap<Reverse<(Integer, usize, u32)>>,
+}
+
+impl Divisors {
+    fn new(factorization: &Factorization) -> Self {
+        let primes: Vec<(Integer, u32)> = factorization
+            .factors
+            .iter()
+            .map(|(p, e)| (p.clone(), *e))
+            .collect();
+        let mut heap = BinaryHeap::new();
+        heap.push(Reverse((Integer::from(1), 0, 0)));
+        Self { primes, heap }
+    }
+}
+
+impl Iterator for Divisors {
+    type Item = Integer;
+
+    fn next(&mut self) -> Option<Integer> {
+        let Reverse((divisor, index, exponent)) = self.heap.pop()?;
+
+        // raise the exponent of the largest prime we already have
+        if exponent > 0 && exponent < self.primes[index].1 {
+            let child = (&divisor * &self.primes[index].0).complete();
+            self.heap.push(Reverse((child, index, exponent + 1)));
+        }
+        // or bring in a larger prime
+        let start = if exponent == 0 { 0 } else { index + 1 };
+        for (i, (p, _)) in self.primes.iter().enumerate().skip(start) {
+            let child = (&divisor * p).complete();
+            self.heap.push(Reverse((child, i, 1)));
+        }
+        Some(divisor)
+    }
+}
+
+pub fn pollard_rho(n: &Integer, seed: u64) -> Option<Integer> {
+    /*
+    Brent's pollard rho on rug Integers, same shape as pollard_rho_u64
+    returns None when the cycle closes without a split, try another seed
+    */
+    if n.is_even() {
+        return Some(Integer::from(2));
+    }
+    let c = Integer::from(seed) % (n - 1u32).complete() + 1u32;
+    let f = |x: &Integer| -> Integer { (x.clone().square() + &c) % n };
+
+    let batch = 128u64;
+    let mut y = Integer::from(seed) % n;
+    let mut x = y.clone();
+    let mut ys = y.clone();
+    let mut q = Integer::from(1);
+    let mut g = Integer::from(1);
+    let mut r = 1u64;
+
+    while g == 1 {
+        x = y.clone();
+        for _ in 0..r {
+            y = f(&y);
+        }
+        let mut k = 0;
+        while k < r && g == 1 {
+            ys = y.clone();
+            for _ in 0..batch.min(r - k) {
+                y = f(&y);
+                q *= (&x - &y).complete().abs();
+                q %= n;
+            }
+            g = q.clone().gcd(n);
+            k += batch;
+        }
+        r <<= 1;
+    }
+
+    if &g == n {
+        loop {
+            ys = f(&ys);
+            g = (&x - &ys).complete().abs().gcd(n);
+            if g > 1 {
+                break;
+            }
+        }
+    }
+
+    if &g == n {
+        None
+    } else {
+        Some(g)
+    }
+}
+
+pub fn is_probable_prime(n: &Integer) -> bool {
+    n.is_probably_prime(PRIME_TEST_ROUNDS) != IsPrime::No
+}
+
+pub fn factorint(n: &Integer) -> Factorization {
+    if n.cmp0() != std::cmp::Ordering::Greater {
+        panic!("factorint expects a positive integer, got {}", n);
+    }
+    if let Some(small) = n.to_u64() {
+        return Factorization::from(factor_u64(small));
+    }
+
+    let mut result = Factorization::new();
+    let mut rest = n.clone();
+    for &p in SMALL_PRIME_CACHE.iter() {
+        if rest.is_divisible_u(p) {
+            let exponent = rest.remove_factor_mut(&Integer::from(p));
+            result.insert(Integer::from(p), exponent);
+        }
+    }
+
+    let mut stack: Vec<(Integer, u32)> = vec![(rest, 1)];
+    while let Some((m, multiplicity)) = stack.pop() {
+        if m == 1 {
+            continue;
+        }
+        if let Some(small) = m.to_u64() {
+            for (p, e) in factor_u64(small) {
+                result.insert(Integer::from(p), e * multiplicity);
+            }
+            continue;
+        }
+        if is_probable_prime(&m) {
+            result.insert(m, multiplicity);
+            continue;
+        }
+        if let Some((base, exponent)) = is_perfect_power(&m) {
+            stack.push((base, exponent * multiplicity));
+            continue;
+        }
+        let d = (1..)
+            .find_map(|seed| pollard_rho(&m, seed))
+            .expect("composite numbers always split");
+        let cofactor = (&m / &d).complete();
+        stack.push((d, multiplicity));
+        stack.push((cofactor, multiplicity));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::spf_sieve::{divisor_count_range, divisor_sum_range, totient_range};
+    use std::str::FromStr;
+
+    fn fact(n: u64) -> Factorization {
+        factorint(&Integer::from(n))
+    }
+
+    #[test]
+    fn test_factorint_small() {
+        assert_eq!(fact(360), Factorization::from(vec![(2, 3), (3, 2), (5, 1)]));
+        assert!(fact(1).is_empty());
+        assert_eq!(fact(360).value(), 360);
+    }
+
+    #[test]
+    fn test_factorint_large() {
+        // (2^31 - 1)(2^89 - 1) * 3^5
+        let m31: Integer = (Integer::from(1) << 31) - 1u32;
+        let m89: Integer = (Integer::from(1) << 89) - 1u32;
+        let n: Integer = (&m31 * &m89).complete() * 243u32;
+        let expected =
+            Factorization::from_pairs(&[(Integer::from(3), 5), (m31.clone(), 1), (m89.clone(), 1)]);
+        assert_eq!(factorint(&n), expected);
+
+        let n = Integer::from_str("1000000000000000000000000000057").unwrap()
+            * Integer::from(1_000_003u32).pow(3);
+        let f = factorint(&n);
+        assert_eq!(f.value(), n);
+        assert_eq!(f.exponent(&Integer::from(1_000_003)), 3);
+
+        // past u64, rho on the rug side pulls out the 10 digit prime
+        let p = Integer::from(1_000_000_007u32);
+        let q = Integer::from_str("10000000000000000087").unwrap();
+        let f = factorint(&(&p * &q).complete());
+        assert_eq!(f, Factorization::from_pairs(&[(p, 1), (q, 1)]));
+    }
+
+    #[test]
+    fn test_divisors() {
+        let divisors: Vec<Integer> = fact(60).divisors().collect();
+        let expected: Vec<Integer> = [1, 2, 3, 4, 5, 6, 10, 12, 15, 20, 30, 60]
+            .iter()
+            .map(|d| Integer::from(*d))
+            .collect();
+        assert_eq!(divisors, expected);
+        assert_eq!(
+            fact(1).divisors().collect::<Vec<_>>(),
+            vec![Integer::from(1)]
+        );
+
+        let f = fact(720720);
+        let divisors: Vec<Integer> = f.divisors().collect();
+        assert_eq!(Integer::from(divisors.len()), f.num_divisors());
+        assert!(divisors.windows(2).all(|w| w[0] < w[1]));
+        assert!(divisors
+            .iter()
+            .all(|d| Integer::from(720720).is_divisible(d)));
+    }
+
+    #[test]
+    fn test_divisors_are_lazy() {
+        let n = Integer::from(2).pow(64) - 1u32;
+        let first: Vec<Integer> = factorint(&n).divisors().take(4).collect();
+        let expected: Vec<Integer> = [1, 3, 5, 15].iter().map(|d| Integer::from(*d)).collect();
+        assert_eq!(first, expected);
+    }
+
+    #[test]
+    fn test_arithmetic_functions() {
+        let totients = totient_range(1, 500);
+        let tau = divisor_count_range(1, 500);
+        let sigma = divisor_sum_range(1, 500);
+        for n in 1..=500u64 {
+            let f = fact(n);
+            let i = (n - 1) as usize;
+            assert_eq!(f.totient(), totients[i]);
+            assert_eq!(f.num_divisors(), tau[i]);
+            assert_eq!(f.sigma_k(0), tau[i]);
+            assert_eq!(f.sigma_k(1), sigma[i] as u64);
+        }
+        assert_eq!(fact(12).sigma_k(2), 1 + 4 + 9 + 16 + 36 + 144);
+    }
+
+    #[test]
+    fn test_carmichael_lambda() {
+        let expected = [
+            1, 1, 2, 2, 4, 2, 6, 2, 6, 4, 10, 2, 12, 6, 4, 4, 16, 6, 18, 4,
+        ];
+        for (i, lambda) in expected.iter().enumerate() {
+            assert_eq!(fact(i as u64 + 1).carmichael_lambda(), *lambda);
+        }
+        assert_eq!(fact(561).carmichael_lambda(), 80);
+    }
+
+    #[test]
+    fn test_radical_mobius_squarefree() {
+        assert_eq!(fact(360).radical(), 30);
+        assert!(!fact(360).is_squarefree());
+        assert!(fact(30).is_squarefree());
+        assert_eq!(fact(30).mobius(), -1);
+        assert_eq!(fact(6).mobius(), 1);
+        assert_eq!(fact(12).mobius(), 0);
+        assert_eq!(fact(1).mobius(), 1);
+    }
+
+    #[test]
+    fn test_mul_gcd_lcm() {
+        let a = fact(360);
+        let b = fact(84);
+        assert_eq!((&a * &b).value(), 360 * 84);
+        assert_eq!(a.gcd(&b).value(), 12);
+        assert_eq!(a.lcm(&b).value(), 2520);
+        assert_eq!((a * b).value(), 30240);
+    }
+}
@@ -0,0 +1,604 @@
+/*
+finite fields GF(p^k) = F_p[x] / f with f monic irreducible of degree k
+
+elements are their coordinates in the basis 1, x, ..., x^(k - 1) as integers,
+the arithmetic on them and on candidate moduli runs in PolyRing<PrimeField>
+    frobenius is a -> a^p, a^(p^i) its i-th power
+    trace(a) = a + a^p + ... + a^(p^(k - 1)) and norm(a) = a^((q - 1) / (p - 1))
+    both land in F_p
+    order of a: start from q - 1 and drop every prime factor that is not needed
+
+f is irreducible iff x^(p^k) = x mod f and gcd(x^(p^(k / r)) - x, f) = 1 for
+every prime r | k (rabin). f of degree k is primitive iff x has order q - 1 mod
+f, which already makes F_p[x] / f a field
+
+conway polynomials C(p, k): the first primitive polynomial in the order below
+whose root x maps to a root of C(p, d) under x -> x^((p^k - 1) / (p^d - 1)) for
+every maximal proper divisor d of k, so the fields nest. the order writes
+    x^k - a_(k-1) x^(k-1) + a_(k-2) x^(k-2) - ... + (-1)^k a_0
+and compares (a_(k-1), ..., a_0) lexicographically. they are searched for, not
+bundled, up to CONWAY_LIMIT elements, bigger fields take the first irreducible
+polynomial reading the coefficients from x^(k - 1) down as a base p number
+*/
+use rug::ops::Pow;
+use rug::{Complete, Integer};
+
+use super::factorization::factorint;
+use super::modint::ModInt;
+use super::poly::{Poly, PolyRing};
+use super::ring::{EuclideanDomain, Field, PrimeField, Ring};
+
+const CONWAY_LIMIT: u32 = 1 << 20;
+
+fn _polynomials(p: &Integer) -> Result<PolyRing<PrimeField>, String> {
+    // F_p[x], p checked prime
+    Ok(PolyRing::new(PrimeField::new(p)?))
+}
+
+fn _integers(f: &Poly<ModInt>) -> Vec<Integer> {
+    f.coefficients().iter().map(ModInt::value).collect()
+}
+
+fn _is_irreducible(ring: &PolyRing<PrimeField>, f: &Poly<ModInt>) -> bool {
+    // rabin's test
+    let k = match f.degree() {
+        Some(k) if k >= 1 => k as u32,
+        _ => return false,
+    };
+    let p = ring.base().characteristic();
+    let x = ring.x();
+    let power = ring.pow_rem(&x, &p.clone().pow(k), f);
+    if !ring.rem(&ring.sub(&power, &x), f).is_zero() {
+        return false;
+    }
+    factorint(&Integer::from(k)).factors().keys().all(|r| {
+        let d = k / r.to_u32().unwrap();
+        let power = ring.pow_rem(&x, &p.clone().pow(d), f);
+        ring.gcd(f, &ring.sub(&power, &x)).degree() == Some(0)
+    })
+}
+
+pub fn is_irreducible(f: &[Integer], p: &Integer) -> Result<bool, String> {
+    // f mod p, f low to high
+    let ring = _polynomials(p)?;
+    Ok(_is_irreducible(&ring, &ring.from_integers(f)))
+}
+
+fn _is_primitive(
+    ring: &PolyRing<PrimeField>,
+    f: &Poly<ModInt>,
+    q_minus_1: &Integer,
+    primes: &[Integer],
+) -> bool {
+    // x has order q - 1 mod f
+    if f.coefficients()[0].is_zero() {
+        return false;
+    }
+    let (x, one) = (ring.x(), ring.one());
+    if ring.pow_rem(&x, q_minus_1, f) != one {
+        return false;
+    }
+    primes.iter().all(|r| {
+        let exponent = (q_minus_1 / r).complete();
+        ring.pow_rem(&x, &exponent, f) != one
+    })
+}
+
+fn _evaluate_at(
+    ring: &PolyRing<PrimeField>,
+    g: &Poly<ModInt>,
+    y: &Poly<ModInt>,
+    f: &Poly<ModInt>,
+) -> Poly<ModInt> {
+    // g(y) mod f, horner from the top
+    g.coefficients().iter().rev().fold(ring.zero(), |value, c| {
+        ring.add(&ring.mul_rem(&value, y, f), &ring.poly(vec![c.clone()]))
+    })
+}
+
+fn _digits(mut m: Integer, p: &Integer, k: u32) -> Vec<Integer> {
+    // base p digits of m, least significant first
+    (0..k)
+        .map(|_| {
+            let (q, r) = m.clone().div_rem(p.clone());
+            m = q;
+            r
+        })
+        .collect()
+}
+
+fn _conway_polynomial(ring: &PolyRing<PrimeField>, k: u32) -> Result<Poly<ModInt>, String> {
+    if k == 0 {
+        return Err("k should be positive".to_string());
+    }
+    let p = ring.base().characteristic();
+    let q = p.clone().pow(k);
+    if q > CONWAY_LIMIT {
+        return Err(format!("p^k should be at most {}", CONWAY_LIMIT));
+    }
+    let q_minus_1 = Integer::from(&q - 1u32);
+    let primes: Vec<Integer> = factorint(&q_minus_1).factors().keys().cloned().collect();
+    let mut subfields = Vec::new();
+    for (r, _) in factorint(&Integer::from(k)).factors().iter() {
+        let d = k / r.to_u32().unwrap();
+        let exponent = q_minus_1.clone() / (p.clone().pow(d) - 1u32);
+        subfields.push((_conway_polynomial(ring, d)?, exponent));
+    }
+
+    /* a_i is the most significant digit for i = k - 1 and sits on x^i with sign (-1)^(k - i) */
+    let mut m = Integer::new();
+    while m < q {
+        let alphas = _digits(m.clone(), p, k);
+        m += 1;
+        let mut f: Vec<Integer> = alphas
+            .iter()
+            .enumerate()
+            .map(|(i, a)| {
+                if (k as usize - i) % 2 == 1 {
+                    -a.clone()
+                } else {
+                    a.clone()
+                }
+            })
+            .collect();
+        f.push(Integer::from(1));
+        let f = ring.from_integers(&f);
+        if !_is_primitive(ring, &f, &q_minus_1, &primes) {
+            continue;
+        }
+        let x = ring.x();
+        let compatible = subfields.iter().all(|(g, exponent)| {
+            let y = ring.pow_rem(&x, exponent, &f);
+            _evaluate_at(ring, g, &y, &f).is_zero()
+        });
+        if compatible {
+            return Ok(f);
+        }
+    }
+    Err("no Conway polynomial found".to_string())
+}
+
+pub fn conway_polynomial(p: &Integer, k: u32) -> Result<Vec<Integer>, String> {
+    // C(p, k) low to high
+    _conway_polynomial(&_polynomials(p)?, k).map(|f| _integers(&f))
+}
+
+fn _irreducible_polynomial(ring: &PolyRing<PrimeField>, k: u32) -> Result<Poly<ModInt>, String> {
+    if k == 0 {
+        return Err("k should be positive".to_string());
+    }
+    let p = ring.base().characteristic();
+    let q = p.clone().pow(k);
+    let mut m = Integer::from(1);
+    while m < q {
+        let mut f = _digits(m.clone(), p, k);
+        m += 1;
+        f.push(Integer::from(1));
+        let f = ring.from_integers(&f);
+        if !f.coefficients()[0].is_zero() && _is_irreducible(ring, &f) {
+            return Ok(f);
+        }
+    }
+    Err("no irreducible polynomial found".to_string())
+}
+
+pub fn irreducible_polynomial(p: &Integer, k: u32) -> Result<Vec<Integer>, String> {
+    // the first monic irreducible of degree k, low to high
+    _irreducible_polynomial(&_polynomials(p)?, k).map(|f| _integers(&f))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FieldElement {
+    // coordinates in 1, x, ..., x^(k - 1), always k of them
+    coefficients: Vec<Integer>,
+}
+
+impl FieldElement {
+    pub fn coefficients(&self) -> &[Integer] {
+        &self.coefficients
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FiniteField {
+    ring: PolyRing<PrimeField>,
+    degree: u32,
+    // monic
+    modulus: Poly<ModInt>,
+    order: Integer,
+}
+
+impl FiniteField {
+    pub fn new(p: &Integer, k: u32) -> Result<FiniteField, String> {
+        // conway modulus when it is small enough to search for
+        let ring = _polynomials(p)?;
+        if k == 0 {
+            return Err("k should be positive".to_string());
+        }
+        let modulus = if p.clone().pow(k) <= CONWAY_LIMIT {
+            _conway_polynomial(&ring, k)?
+        } else {
+            _irreducible_polynomial(&ring, k)?
+        };
+        Ok(FiniteField::_from_modulus(ring, modulus))
+    }
+
+    pub fn with_modulus(p: &Integer, modulus: &[Integer]) -> Result<FiniteField, String> {
+        let ring = _polynomials(p)?;
+        let f = ring.from_integers(modulus);
+        if !_is_irreducible(&ring, &f) {
+            return Err("modulus should be irreducible mod p".to_string());
+        }
+        let f = ring.mul(&ring.normal_unit(&f), &f);
+        Ok(FiniteField::_from_modulus(ring, f))
+    }
+
+    fn _from_modulus(ring: PolyRing<PrimeField>, modulus: Poly<ModInt>) -> FiniteField {
+        let degree = modulus.degree().unwrap() as u32;
+        FiniteField {
+            order: ring.base().characteristic().clone().pow(degree),
+            ring,
+            degree,
+            modulus,
+        }
+    }
+
+    pub fn characteristic(&self) -> &Integer {
+        self.ring.base().characteristic()
+    }
+
+    pub fn degree(&self) -> u32 {
+        self.degree
+    }
+
+    pub fn order(&self) -> &Integer {
+        &self.order
+    }
+
+    pub fn modulus(&self) -> Vec<Integer> {
+        // monic, low to high
+        _integers(&self.modulus)
+    }
+
+    fn _element(&self, mut coefficients: Vec<Integer>) -> FieldElement {
+        coefficients.resize(self.degree as usize, Integer::new());
+        FieldElement { coefficients }
+    }
+
+    fn _lift(&self, a: &FieldElement) -> Poly<ModInt> {
+        self.ring.from_integers(&a.coefficients)
+    }
+
+    fn _reduce(&self, f: &Poly<ModInt>) -> FieldElement {
+        self._element(_integers(f))
+    }
+
+    pub fn element(&self, coefficients: &[Integer]) -> FieldElement {
+        // the class of c_0 + c_1 x + ..., any length
+        let f = self.ring.from_integers(coefficients);
+        self._reduce(&self.ring.rem(&f, &self.modulus))
+    }
+
+    pub fn generator(&self) -> FieldElement {
+        // the class of x
+        self.element(&[Integer::new(), Integer::from(1)])
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = FieldElement> + '_ {
+        // every element in the order of their coordinates as base p numbers
+        let mut m = Integer::new();
+        std::iter::from_fn(move || {
+            if m >= self.order {
+                return None;
+            }
+            let digits = _digits(m.clone(), self.characteristic(), self.degree);
+            m += 1;
+            Some(self._element(digits))
+        })
+    }
+
+    pub fn power(&self, a: &FieldElement, exponent: &Integer) -> FieldElement {
+        // a^e, a negative exponent needs a nonzero a
+        if *exponent < 0 {
+            let inverse = self.inverse(a).expect("zero is not invertible");
+            return self.power(&inverse, &(-exponent.clone()));
+        }
+        self._reduce(&self.ring.pow_rem(&self._lift(a), exponent, &self.modulus))
+    }
+
+    pub fn frobenius(&self, a: &FieldElement, i: u32) -> FieldElement {
+        // a^(p^i)
+        self.power(a, &self.characteristic().clone().pow(i % self.degree))
+    }
+
+    fn _in_prime_field(&self, a: &FieldElement) -> Integer {
+        debug_assert!(a.coefficients[1..].iter().all(|c| *c == 0));
+        a.coefficients[0].clone()
+    }
+
+    pub fn trace(&self, a: &FieldElement) -> Integer {
+        let mut sum = self.zero();
+        let mut conjugate = a.clone();
+        for _ in 0..self.degree {
+            sum = self.add(&sum, &conjugate);
+            conjugate = self.frobenius(&conjugate, 1);
+        }
+        self._in_prime_field(&sum)
+    }
+
+    pub fn norm(&self, a: &FieldElement) -> Integer {
+        let exponent =
+            Integer::from(&self.order - 1u32) / Integer::from(self.characteristic() - 1u32);
+        self._in_prime_field(&self.power(a, &exponent))
+    }
+
+    pub fn element_order(&self, a: &FieldElement) -> Result<Integer, String> {
+        // multiplicative order
+        if self.is_zero(a) {
+            return Err("zero has no multiplicative order".to_string());
+        }
+        let mut order = Integer::from(&self.order - 1u32);
+        for (r, e) in factorint(&order.clone()).factors().iter() {
+            for _ in 0..*e {
+                let candidate = (&order / r).complete();
+                if self.power(a, &candidate) != self.one() {
+                    break;
+                }
+                order = candidate;
+            }
+        }
+        Ok(order)
+    }
+
+    pub fn is_primitive(&self, a: &FieldElement) -> bool {
+        // a generates the multiplicative group
+        !self.is_zero(a) && self.element_order(a).unwrap() == Integer::from(&self.order - 1u32)
+    }
+
+    pub fn primitive_element(&self) -> FieldElement {
+        // x when the modulus is primitive, conway ones are, else the first one found
+        let x = self.generator();
+        if self.is_primitive(&x) {
+            return x;
+        }
+        self.elements()
+            .find(|a| self.is_primitive(a))
+            .expect("the multiplicative group is cyclic")
+    }
+}
+
+impl Ring for FiniteField {
+    type Element = FieldElement;
+
+    fn zero(&self) -> FieldElement {
+        self._element(Vec::new())
+    }
+
+    fn one(&self) -> FieldElement {
+        self._element(vec![Integer::from(1)])
+    }
+
+    fn convert(&self, n: &Integer) -> FieldElement {
+        self._element(vec![n.clone().modulo(self.characteristic())])
+    }
+
+    fn add(&self, a: &FieldElement, b: &FieldElement) -> FieldElement {
+        let coefficients = a
+            .coefficients
+            .iter()
+            .zip(b.coefficients.iter())
+            .map(|(x, y)| (x + y).complete().modulo(self.characteristic()))
+            .collect();
+        FieldElement { coefficients }
+    }
+
+    fn sub(&self, a: &FieldElement, b: &FieldElement) -> FieldElement {
+        let coefficients = a
+            .coefficients
+            .iter()
+            .zip(b.coefficients.iter())
+            .map(|(x, y)| (x - y).complete().modulo(self.characteristic()))
+            .collect();
+        FieldElement { coefficients }
+    }
+
+    fn mul(&self, a: &FieldElement, b: &FieldElement) -> FieldElement {
+        self._reduce(
+            &self
+                .ring
+                .mul_rem(&self._lift(a), &self._lift(b), &self.modulus),
+        )
+    }
+
+    fn is_zero(&self, a: &FieldElement) -> bool {
+        a.coefficients.iter().all(|c| *c == 0)
+    }
+
+    fn pow(&self, a: &FieldElement, exponent: u64) -> FieldElement {
+        self.power(a, &Integer::from(exponent))
+    }
+}
+
+impl EuclideanDomain for FiniteField {
+    fn div_rem(&self, a: &FieldElement, b: &FieldElement) -> (FieldElement, FieldElement) {
+        let inverse = self.inverse(b).expect("division by zero");
+        (self.mul(a, &inverse), self.zero())
+    }
+
+    fn normal_unit(&self, a: &FieldElement) -> FieldElement {
+        self.inverse(a).unwrap_or_else(|| self.one())
+    }
+}
+
+impl Field for FiniteField {
+    fn inverse(&self, a: &FieldElement) -> Option<FieldElement> {
+        // a^(q - 2)
+        if self.is_zero(a) {
+            return None;
+        }
+        Some(self.power(a, &Integer::from(&self.order - 2u32)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::ring::determinant;
+
+    fn poly(coefficients: &[u32]) -> Vec<Integer> {
+        coefficients.iter().map(|c| Integer::from(*c)).collect()
+    }
+
+    #[test]
+    fn test_conway_polynomial() {
+        // the published values, low to high
+        let known: [(u32, u32, &[u32]); 12] = [
+            (2, 1, &[1, 1]),
+            (3, 1, &[1, 1]),
+            (7, 1, &[4, 1]),
+            (2, 2, &[1, 1, 1]),
+            (2, 3, &[1, 1, 0, 1]),
+            (2, 4, &[1, 1, 0, 0, 1]),
+            (2, 5, &[1, 0, 1, 0, 0, 1]),
+            (2, 6, &[1, 1, 0, 1, 1, 0, 1]),
+            (3, 2, &[2, 2, 1]),
+            (3, 3, &[1, 2, 0, 1]),
+            (5, 2, &[2, 4, 1]),
+            (7, 2, &[3, 6, 1]),
+        ];
+        for (p, k, expected) in known {
+            assert_eq!(
+                conway_polynomial(&Integer::from(p), k).unwrap(),
+                poly(expected),
+                "C({}, {})",
+                p,
+                k
+            );
+        }
+        assert!(conway_polynomial(&Integer::from(4), 2).is_err());
+        assert!(conway_polynomial(&Integer::from(2), 0).is_err());
+        assert!(conway_polynomial(&Integer::from(2), 40).is_err());
+    }
+
+    #[test]
+    fn test_irreducible() {
+        // monic irreducibles over F_2 by degree: 2, 1, 2, 3, 6, 9
+        let two = Integer::from(2);
+        for (k, count) in [(1u32, 2usize), (2, 1), (3, 2), (4, 3), (5, 6), (6, 9)] {
+            let found = (0..1u32 << k)
+                .filter(|m| {
+                    let mut f = _digits(Integer::from(*m), &two, k);
+                    f.push(Integer::from(1));
+                    is_irreducible(&f, &two).unwrap()
+                })
+                .count();
+            assert_eq!(found, count, "k = {}", k);
+        }
+        assert!(!is_irreducible(&poly(&[1, 0, 1]), &two).unwrap());
+        assert!(is_irreducible(&poly(&[1, 0, 1]), &Integer::from(3)).unwrap());
+        assert_eq!(
+            irreducible_polynomial(&two, 8).unwrap(),
+            poly(&[1, 1, 0, 1, 1, 0, 0, 0, 1])
+        );
+        let big = irreducible_polynomial(&Integer::from(1_000_003), 3).unwrap();
+        assert!(is_irreducible(&big, &Integer::from(1_000_003)).unwrap());
+    }
+
+    #[test]
+    fn test_field_arithmetic() {
+        for (p, k) in [(2u32, 4u32), (3, 3), (5, 2), (7, 1), (2, 1)] {
+            let field = FiniteField::new(&Integer::from(p), k).unwrap();
+            let elements: Vec<FieldElement> = field.elements().collect();
+            assert_eq!(Integer::from(elements.len()), *field.order());
+            let g = field.primitive_element();
+            // conway moduli are primitive
+            assert_eq!(g, field.generator());
+            assert_eq!(
+                field.element_order(&g).unwrap(),
+                Integer::from(field.order() - 1u32)
+            );
+            for a in elements.iter() {
+                // a^q = a, frobenius is additive and multiplicative
+                assert_eq!(field.power(a, field.order()), *a);
+                if let Some(inverse) = field.inverse(a) {
+                    assert_eq!(field.mul(a, &inverse), field.one());
+                    let order = field.element_order(a).unwrap();
+                    assert!(Integer::from(field.order() - 1u32).is_divisible(&order));
+                    assert_eq!(field.power(a, &order), field.one());
+                }
+                for b in elements.iter().step_by(3) {
+                    let sum = field.add(a, b);
+                    let product = field.mul(a, b);
+                    assert_eq!(
+                        field.frobenius(&sum, 1),
+                        field.add(&field.frobenius(a, 1), &field.frobenius(b, 1))
+                    );
+                    assert_eq!(
+                        field.frobenius(&product, 1),
+                        field.mul(&field.frobenius(a, 1), &field.frobenius(b, 1))
+                    );
+                    assert_eq!(
+                        field.trace(&sum),
+                        (field.trace(a) + field.trace(b)).modulo(&Integer::from(p))
+                    );
+                    assert_eq!(
+                        field.norm(&product),
+                        (field.norm(a) * field.norm(b)).modulo(&Integer::from(p))
+                    );
+                    assert_eq!(field.sub(&sum, b), *a);
+                }
+            }
+            // every value of F_p is a trace, and exactly q / p elements have each trace
+            let zero_trace = elements.iter().filter(|a| field.trace(a) == 0).count();
+            assert_eq!(Integer::from(zero_trace * p as usize), *field.order());
+        }
+    }
+
+    #[test]
+    fn test_with_modulus() {
+        // GF(9) as F_3[x] / (x^2 + 1), given scaled by 2
+        let p = Integer::from(3);
+        assert!(FiniteField::with_modulus(&p, &poly(&[2, 0, 1])).is_err());
+        let field = FiniteField::with_modulus(&p, &poly(&[2, 0, 2])).unwrap();
+        assert_eq!(field.modulus(), poly(&[1, 0, 1]));
+        // x^2 = -1, x has order 4 so x is not primitive in GF(9)
+        let x = field.generator();
+        assert_eq!(field.mul(&x, &x), field.convert(&Integer::from(-1)));
+        assert!(!field.is_primitive(&x));
+        let g = field.primitive_element();
+        assert_eq!(field.element_order(&g).unwrap(), 8);
+        assert_eq!(
+            field.element(&poly(&[1, 2, 1])),
+            field.element(&poly(&[0, 2]))
+        );
+        assert!(field.element_order(&field.zero()).is_err());
+
+        // a big field falls back to an irreducible modulus
+        let big = FiniteField::new(&Integer::from(1_000_003), 2).unwrap();
+        let a = big.element(&poly(&[17, 5]));
+        let inverse = big.inverse(&a).unwrap();
+        assert_eq!(big.mul(&a, &inverse), big.one());
+        assert_eq!(
+            big.power(&a, &Integer::from(-2)),
+            big.mul(&inverse, &inverse)
+        );
+        assert!(FiniteField::new(&Integer::from(6), 2).is_err());
+    }
+
+    #[test]
+    fn test_field_traits() {
+        // generic algorithms over GF(8)
+        let field = FiniteField::new(&Integer::from(2), 3).unwrap();
+        let x = field.generator();
+        let matrix = vec![
+            vec![field.one(), x.clone()],
+            vec![field.mul(&x, &x), field.one()],
+        ];
+        // 1 - x^3 = 1 + x + 1 = x mod x^3 + x + 1
+        assert_eq!(determinant(&field, &matrix).unwrap(), x);
+        let (g, s, t) = field.gcdex(&x, &field.one());
+        assert_eq!(g, field.one());
+        assert_eq!(field.add(&field.mul(&x, &s), &t), field.one());
+        assert_eq!(field.div(&field.one(), &x), field.inverse(&x));
+    }
+}
@@ -0,0 +1,543 @@
+/*
+every gcd we have in one place
+
+euclidean_gcd / binary_gcd / extended_gcd are the textbook ones, fine up to a few
+hundred digits
+lehmer works on the leading 62 bits with machine words and only touches the big
+numbers once per ~30 quotients
+half gcd (Schonhage / Thull-Yap) reduces the top half of the numbers recursively,
+the matrices are applied with gmp multiplication so it is subquadratic and the one
+to use for million digit inputs
+
+the reductions are tracked as matrices U with (a', b') = U (a, b)
+every U is unimodular so gcd(a', b') = gcd(a, b) no matter what, if a quotient
+guessed from the leading bits is off the numbers just come out negative or
+swapped, that gets normalized and the next step continues from there.
+the cofactors of the extended versions are read from the first row of U
+*/
+use rug::{Complete, Integer};
+
+// below this many bits lehmer wins over half gcd
+const HALF_GCD_THRESHOLD: u32 = 1 << 14;
+// below this many bits plain euclid wins over lehmer
+const LEHMER_THRESHOLD: u32 = 256;
+
+pub fn euclidean_gcd(mut a: Integer, mut b: Integer) -> Integer {
+    while b != 0 {
+        let temp = a % &b;
+        a = b;
+        b = temp;
+    }
+    a.abs()
+}
+
+pub fn binary_gcd(a: Integer, b: Integer) -> Integer {
+    let mut a = a.abs();
+    let mut b = b.abs();
+    if a == 0 {
+        return b;
+    }
+    if b == 0 {
+        return a;
+    }
+
+    let shift = (&a | &b).complete().find_one(0).unwrap();
+
+    a >>= a.find_one(0).unwrap();
+    b >>= b.find_one(0).unwrap();
+
+    while b != 0 {
+        b >>= b.find_one(0).unwrap();
+
+        if a > b {
+            std::mem::swap(&mut a, &mut b);
+        }
+
+        b -= &a;
+    }
+
+    a << shift
+}
+
+fn _normalize_cofactors(
+    a: &Integer,
+    b: &Integer,
+    g: Integer,
+    x: Integer,
+) -> (Integer, Integer, Integer) {
+    /*
+    any x with a x = g mod b is turned into the smallest one, |x| <= |b| / 2g
+    and y is recomputed from it, that way every variant returns the same triple
+    a and b are the absolute values here
+    */
+    if *b == 0 {
+        return (g, Integer::from(1), Integer::from(0));
+    }
+    if *a == 0 {
+        return (g, Integer::from(0), Integer::from(1));
+    }
+    let step = (b / &g).complete();
+    let mut x = x % &step;
+    if x < 0 {
+        x += &step;
+    }
+    if Integer::from(&x << 1) > step {
+        x -= &step;
+    }
+    let y = (&g - (a * &x).complete()) / b;
+    (g, x, y)
+}
+
+fn _with_signs(
+    a: &Integer,
+    b: &Integer,
+    (g, x, y): (Integer, Integer, Integer),
+) -> (Integer, Integer, Integer) {
+    // the algorithms work on |a|, |b|, flip the cofactors back
+    let x = if *a < 0 { -x } else { x };
+    let y = if *b < 0 { -y } else { y };
+    (g, x, y)
+}
+
+pub fn extended_gcd(a: &Integer, b: &Integer) -> (Integer, Integer, Integer) {
+    /*
+    (g, x, y) with a x + b y = g, g >= 0 and |x| <= |b| / 2g
+    iterative, only the x cofactor is tracked and y is recovered at the end
+    */
+    let abs_a = a.clone().abs();
+    let abs_b = b.clone().abs();
+    let mut r0 = abs_a.clone();
+    let mut r1 = abs_b.clone();
+    let mut x0 = Integer::from(1);
+    let mut x1 = Integer::from(0);
+
+    while r1 != 0 {
+        let (q, r) = r0.div_rem_ref(&r1).complete();
+        r0 = std::mem::replace(&mut r1, r);
+        let next = &x0 - (&q * &x1).complete();
+        x0 = std::mem::replace(&mut x1, next);
+    }
+
+    _with_signs(a, b, _normalize_cofactors(&abs_a, &abs_b, r0, x0))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Matrix {
+    // (a', b') = [[m00, m01], [m10, m11]] (a, b)
+    m00: Integer,
+    m01: Integer,
+    m10: Integer,
+    m11: Integer,
+}
+
+impl Matrix {
+    fn identity() -> Self {
+        Matrix {
+            m00: Integer::from(1),
+            m01: Integer::from(0),
+            m10: Integer::from(0),
+            m11: Integer::from(1),
+        }
+    }
+
+    fn is_identity(&self) -> bool {
+        self.m00 == 1 && self.m01 == 0 && self.m10 == 0 && self.m11 == 1
+    }
+
+    fn apply(&self, a: &Integer, b: &Integer) -> (Integer, Integer) {
+        let new_a = (&self.m00 * a).complete() + (&self.m01 * b).complete();
+        let new_b = (&self.m10 * a).complete() + (&self.m11 * b).complete();
+        (new_a, new_b)
+    }
+
+    fn then(&self, next: &Matrix) -> Matrix {
+        // next * self, first self then next
+        Matrix {
+            m00: (&next.m00 * &self.m00).complete() + (&next.m01 * &self.m10).complete(),
+            m01: (&next.m00 * &self.m01).complete() + (&next.m01 * &self.m11).complete(),
+            m10: (&next.m10 * &self.m00).complete() + (&next.m11 * &self.m10).complete(),
+            m11: (&next.m10 * &self.m01).complete() + (&next.m11 * &self.m11).complete(),
+        }
+    }
+
+    fn then_small(&mut self, (a, b, c, d): (i128, i128, i128, i128)) {
+        // same as then() for a matrix of machine words
+        let m00 = (&self.m00 * a).complete() + (&self.m10 * b).complete();
+        let m01 = (&self.m01 * a).complete() + (&self.m11 * b).complete();
+        let m10 = (&self.m00 * c).complete() + (&self.m10 * d).complete();
+        let m11 = (&self.m01 * c).complete() + (&self.m11 * d).complete();
+        self.m00 = m00;
+        self.m01 = m01;
+        self.m10 = m10;
+        self.m11 = m11;
+    }
+
+    fn euclid_step(&mut self, a: &mut Integer, b: &mut Integer) {
+        // (a, b) -> (b, a mod b)
+        let (q, r) = a.div_rem_ref(b).complete();
+        *a = std::mem::replace(b, r);
+        let m10 = &self.m00 - (&q * &self.m10).complete();
+        let m11 = &self.m01 - (&q * &self.m11).complete();
+        self.m00 = std::mem::replace(&mut self.m10, m10);
+        self.m01 = std::mem::replace(&mut self.m11, m11);
+    }
+
+    fn normalize(&mut self, a: &mut Integer, b: &mut Integer) {
+        // a >= b >= 0 again after a reduction that went too far
+        if *a < 0 {
+            *a = -std::mem::take(a);
+            self.m00 = -std::mem::take(&mut self.m00);
+            self.m01 = -std::mem::take(&mut self.m01);
+        }
+        if *b < 0 {
+            *b = -std::mem::take(b);
+            self.m10 = -std::mem::take(&mut self.m10);
+            self.m11 = -std::mem::take(&mut self.m11);
+        }
+        if *a < *b {
+            std::mem::swap(a, b);
+            std::mem::swap(&mut self.m00, &mut self.m10);
+            std::mem::swap(&mut self.m01, &mut self.m11);
+        }
+    }
+}
+
+fn _lehmer_quotients(a: &Integer, b: &Integer) -> Option<(i128, i128, i128, i128)> {
+    /*
+    Knuth's algorithm L on the leading 62 bits of a (and the same bits of b)
+    returns (A, B, C, D) with a' = A a + B b, b' = C a + D b
+    the quotient is only taken when both bounds of the true quotient agree,
+    None if not even the first one is certain
+    */
+    let shift = a.significant_bits().saturating_sub(62);
+    let mut x = (a >> shift).complete().to_i128().unwrap();
+    let mut y = (b >> shift).complete().to_i128().unwrap();
+    let (mut ca, mut cb, mut cc, mut cd) = (1i128, 0i128, 0i128, 1i128);
+
+    loop {
+        if y + cc == 0 || y + cd == 0 {
+            break;
+        }
+        let q = (x + ca) / (y + cc);
+        if q != (x + cb) / (y + cd) {
+            break;
+        }
+        (ca, cc) = (cc, ca - q * cc);
+        (cb, cd) = (cd, cb - q * cd);
+        (x, y) = (y, x - q * y);
+    }
+
+    if cb == 0 {
+        None
+    } else {
+        Some((ca, cb, cc, cd))
+    }
+}
+
+fn _lehmer_reduce(a: &mut Integer, b: &mut Integer, stop_bits: u32, matrix: &mut Matrix) {
+    // lehmer steps until b has at most stop_bits bits
+    while *b != 0 && b.significant_bits() > stop_bits {
+        if a.significant_bits() <= 64 || a.significant_bits() - b.significant_bits() > 30 {
+            matrix.euclid_step(a, b);
+            continue;
+        }
+        match _lehmer_quotients(a, b) {
+            Some(step) => {
+                let (ca, cb, cc, cd) = step;
+                let new_a = (&*a * ca).complete() + (&*b * cb).complete();
+                let new_b = (&*a * cc).complete() + (&*b * cd).complete();
+                *a = new_a;
+                *b = new_b;
+                matrix.then_small(step);
+                matrix.normalize(a, b);
+            }
+            None => matrix.euclid_step(a, b),
+        }
+    }
+}
+
+fn _half_gcd(a: &Integer, b: &Integer) -> Matrix {
+    /*
+    a >= b >= 0 with n bits, returns U such that U (a, b) has b' of about n / 2 bits
+    the first recursion reduces the top half of (a, b), which takes the full numbers
+    from n to ~3n/4 bits, one euclid step, then the second one takes them to n/2
+    */
+    let n = a.significant_bits();
+    let m = n / 2 + 1;
+    if b.significant_bits() <= m {
+        return Matrix::identity();
+    }
+    let mut matrix = Matrix::identity();
+    let mut a = a.clone();
+    let mut b = b.clone();
+    if n <= HALF_GCD_THRESHOLD {
+        _lehmer_reduce(&mut a, &mut b, m, &mut matrix);
+        return matrix;
+    }
+
+    let first = _half_gcd(&(&a >> m).complete(), &(&b >> m).complete());
+    if !first.is_identity() {
+        (a, b) = first.apply(&a, &b);
+        matrix = first;
+        matrix.normalize(&mut a, &mut b);
+    }
+    if b.significant_bits() <= m {
+        return matrix;
+    }
+    matrix.euclid_step(&mut a, &mut b);
+    if b.significant_bits() <= m {
+        return matrix;
+    }
+
+    let k = (2 * m).saturating_sub(a.significant_bits());
+    let second = _half_gcd(&(&a >> k).complete(), &(&b >> k).complete());
+    if !second.is_identity() {
+        (a, b) = second.apply(&a, &b);
+        matrix = matrix.then(&second);
+        matrix.normalize(&mut a, &mut b);
+    }
+    _lehmer_reduce(&mut a, &mut b, m, &mut matrix);
+    matrix
+}
+
+fn _reduce_to_gcd(a: &Integer, b: &Integer, use_half_gcd: bool) -> (Integer, Matrix) {
+    // runs the chosen reduction down to (g, 0) and returns g with the full matrix
+    let mut matrix = Matrix::identity();
+    let mut a = a.clone();
+    let mut b = b.clone();
+    matrix.normalize(&mut a, &mut b);
+
+    while b != 0 {
+        if use_half_gcd && a.significant_bits() > HALF_GCD_THRESHOLD {
+            let step = _half_gcd(&a, &b);
+            if !step.is_identity() {
+                (a, b) = step.apply(&a, &b);
+                matrix = matrix.then(&step);
+                matrix.normalize(&mut a, &mut b);
+            }
+            if b != 0 {
+                matrix.euclid_step(&mut a, &mut b);
+            }
+        } else {
+            _lehmer_reduce(&mut a, &mut b, 0, &mut matrix);
+        }
+    }
+    (a, matrix)
+}
+
+fn _reduce_without_matrix(a: &Integer, b: &Integer, use_half_gcd: bool) -> Integer {
+    /*
+    same reduction when only g is wanted, the matrices of the single steps are
+    thrown away and the big numbers never get multiplied by accumulated cofactors
+    */
+    let mut a = a.clone().abs();
+    let mut b = b.clone().abs();
+    if a < b {
+        std::mem::swap(&mut a, &mut b);
+    }
+    while b != 0 {
+        if use_half_gcd && a.significant_bits() > HALF_GCD_THRESHOLD {
+            let mut matrix = _half_gcd(&a, &b);
+            if !matrix.is_identity() {
+                (a, b) = matrix.apply(&a, &b);
+                matrix.normalize(&mut a, &mut b);
+            }
+            if b != 0 {
+                matrix.euclid_step(&mut a, &mut b);
+            }
+        } else if a.significant_bits() <= LEHMER_THRESHOLD {
+            return euclidean_gcd(a, b);
+        } else {
+            let mut matrix = Matrix::identity();
+            let stop = b.significant_bits().saturating_sub(62 * 8);
+            _lehmer_reduce(&mut a, &mut b, stop, &mut matrix);
+        }
+    }
+    a
+}
+
+fn _extended_from_reduction(
+    a: &Integer,
+    b: &Integer,
+    use_half_gcd: bool,
+) -> (Integer, Integer, Integer) {
+    let abs_a = a.clone().abs();
+    let abs_b = b.clone().abs();
+    let (g, matrix) = _reduce_to_gcd(&abs_a, &abs_b, use_half_gcd);
+    // g = m00 |a| + m01 |b|
+    _with_signs(a, b, _normalize_cofactors(&abs_a, &abs_b, g, matrix.m00))
+}
+
+pub fn lehmer_gcd(a: &Integer, b: &Integer) -> Integer {
+    _reduce_without_matrix(a, b, false)
+}
+
+pub fn lehmer_extended_gcd(a: &Integer, b: &Integer) -> (Integer, Integer, Integer) {
+    _extended_from_reduction(a, b, false)
+}
+
+pub fn half_gcd(a: &Integer, b: &Integer) -> Integer {
+    _reduce_without_matrix(a, b, true)
+}
+
+pub fn half_extended_gcd(a: &Integer, b: &Integer) -> (Integer, Integer, Integer) {
+    _extended_from_reduction(a, b, true)
+}
+
+pub fn gcd(a: &Integer, b: &Integer) -> Integer {
+    // picks the algorithm from the size of the larger input
+    let bits = a.significant_bits().max(b.significant_bits());
+    if bits <= LEHMER_THRESHOLD {
+        euclidean_gcd(a.clone(), b.clone())
+    } else if bits <= HALF_GCD_THRESHOLD {
+        lehmer_gcd(a, b)
+    } else {
+        half_gcd(a, b)
+    }
+}
+
+pub fn xgcd(a: &Integer, b: &Integer) -> (Integer, Integer, Integer) {
+    let bits = a.significant_bits().max(b.significant_bits());
+    if bits <= LEHMER_THRESHOLD {
+        extended_gcd(a, b)
+    } else if bits <= HALF_GCD_THRESHOLD {
+        lehmer_extended_gcd(a, b)
+    } else {
+        half_extended_gcd(a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rug::ops::Pow;
+    use rug::rand::RandState;
+
+    fn fibonacci_pair(n: u32) -> (Integer, Integer) {
+        // consecutive fibonacci numbers, the worst case for euclid
+        let (mut a, mut b) = (Integer::from(0), Integer::from(1));
+        for _ in 0..n {
+            let next = (&a + &b).complete();
+            a = std::mem::replace(&mut b, next);
+        }
+        (b, a)
+    }
+
+    fn check_extended(a: &Integer, b: &Integer, result: (Integer, Integer, Integer)) {
+        let (g, x, y) = result;
+        assert_eq!(g, a.clone().gcd(b));
+        assert_eq!((a * &x).complete() + (b * &y).complete(), g);
+        // same triple as the iterative reference
+        assert_eq!((g, x, y), extended_gcd(a, b));
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        let (g, x, y) = extended_gcd(&Integer::from(240), &Integer::from(46));
+        assert_eq!(
+            (g, x, y),
+            (Integer::from(2), Integer::from(-9), Integer::from(47))
+        );
+        assert_eq!(
+            extended_gcd(&Integer::from(0), &Integer::from(-5)),
+            (Integer::from(5), Integer::from(0), Integer::from(-1))
+        );
+        assert_eq!(
+            extended_gcd(&Integer::from(-7), &Integer::from(0)),
+            (Integer::from(7), Integer::from(-1), Integer::from(0))
+        );
+        for (a, b) in [(17, 5), (-17, 5), (17, -5), (-12, -18), (1, 1), (6, 3)] {
+            let (a, b) = (Integer::from(a), Integer::from(b));
+            let (g, x, y) = extended_gcd(&a, &b);
+            assert_eq!(g, a.clone().gcd(&b));
+            assert_eq!(a * x + b * y, g);
+        }
+    }
+
+    #[test]
+    fn test_extended_gcd_matches_gmp_cofactor_bounds() {
+        let mut rand = RandState::new();
+        for bits in [10u32, 100, 1000] {
+            for _ in 0..20 {
+                let a = Integer::from(Integer::random_bits(bits, &mut rand));
+                let b = Integer::from(Integer::random_bits(bits, &mut rand)) + 1u32;
+                let (g, x, y) = extended_gcd(&a, &b);
+                let (gmp_g, _, _) = a.clone().extended_gcd(b.clone(), Integer::new());
+                assert_eq!(g, gmp_g);
+                assert!((x.clone().abs() * 2u32 * &g) <= b);
+                assert_eq!(a * x + b * y, g);
+            }
+        }
+    }
+
+    #[test]
+    fn test_extended_gcd_fibonacci_no_recursion() {
+        let (a, b) = fibonacci_pair(20_000);
+        let (g, x, y) = extended_gcd(&a, &b);
+        assert_eq!(g, 1);
+        assert_eq!(a * x + b * y, 1);
+    }
+
+    #[test]
+    fn test_lehmer_against_gmp() {
+        let mut rand = RandState::new();
+        for bits in [64u32, 300, 2000, 10_000] {
+            for _ in 0..10 {
+                let common = Integer::from(Integer::random_bits(bits / 4, &mut rand));
+                let a = Integer::from(Integer::random_bits(bits, &mut rand)) * &common;
+                let b = Integer::from(Integer::random_bits(bits - 7, &mut rand)) * &common;
+                assert_eq!(lehmer_gcd(&a, &b), a.clone().gcd(&b));
+                check_extended(&a, &b, lehmer_extended_gcd(&a, &b));
+                assert_eq!(lehmer_gcd(&-a.clone(), &b), a.clone().gcd(&b));
+            }
+        }
+        let (a, b) = fibonacci_pair(5000);
+        check_extended(&a, &b, lehmer_extended_gcd(&a, &b));
+    }
+
+    #[test]
+    fn test_half_gcd_against_gmp() {
+        let mut rand = RandState::new();
+        for bits in [20_000u32, 100_000] {
+            for _ in 0..3 {
+                let common = Integer::from(Integer::random_bits(bits / 3, &mut rand));
+                let a = Integer::from(Integer::random_bits(bits, &mut rand)) * &common;
+                let b = Integer::from(Integer::random_bits(bits, &mut rand)) * &common;
+                assert_eq!(half_gcd(&a, &b), a.clone().gcd(&b));
+                check_extended(&a, &b, half_extended_gcd(&a, &b));
+            }
+        }
+        let (a, b) = fibonacci_pair(60_000);
+        assert_eq!(half_gcd(&a, &b), 1);
+        let (g, x, y) = half_extended_gcd(&a, &b);
+        assert_eq!(a * x + b * y, g);
+    }
+
+    #[test]
+    fn test_half_gcd_large() {
+        // 10^5 digits each, a common factor of 3 * 10^4 digits
+        let mut rand = RandState::new();
+        let common = Integer::from(Integer::random_bits(100_000, &mut rand)) | 1u32;
+        let a = Integer::from(Integer::random_bits(232_000, &mut rand)) * &common;
+        let b = Integer::from(Integer::random_bits(232_000, &mut rand)) * &common;
+        assert_eq!(gcd(&a, &b), a.clone().gcd(&b));
+        let (g, x, y) = xgcd(&a, &b);
+        assert_eq!(g, a.clone().gcd(&b));
+        assert_eq!(a * x + b * y, g);
+    }
+
+    #[test]
+    fn test_small_gcds() {
+        assert_eq!(binary_gcd(Integer::from(-12), Integer::from(18)), 6);
+        assert_eq!(euclidean_gcd(Integer::from(12), Integer::from(-18)), 6);
+        assert_eq!(gcd(&Integer::from(0), &Integer::from(0)), 0);
+        assert_eq!(
+            gcd(&Integer::from(2).pow(300), &Integer::from(6).pow(100)),
+            Integer::from(2).pow(100)
+        );
+        assert_eq!(
+            xgcd(&Integer::from(0), &Integer::from(0)),
+            (Integer::from(0), Integer::from(1), Integer::from(0))
+        );
+    }
+}
@@ -0,0 +1,587 @@
+/*
+index calculus for discrete logs in F_p^* when the order of the base has a prime q
+too large for rho, logs are found mod q here and mod the smooth rest of the order
+with Pohlig-Hellman
+
+everything is projected into the subgroup of order q by psi(x) = x^((p - 1) / q),
+log_g(x) mod q is log_psi(g) psi(x) and psi(-1) = 1 since (p - 1) / q is even,
+so signs never matter
+
+relations: g^k = t mod p, write t = a / b with |a|, b ~ sqrt(p) by stopping the
+euclidean algorithm on (p, t) halfway, both smooth over the primes of the factor
+base gives
+    k = sum of e_i log(p_i) - sum of f_i log(p_i) mod q
+two numbers of half the size are far more likely to be smooth than t itself
+
+linear algebra: structured gaussian elimination, always pivot on the lightest
+column and the lightest row in it so the fill-in stays small, then back substitute
+
+individual logs by descent: h g^k = a / b with a, b smooth except for at most one
+medium prime each (below B^2), every medium prime l gets its own log from a
+relation l g^k' = a' / b' over the factor base
+*/
+use std::collections::HashSet;
+
+use rayon::prelude::*;
+use rug::{Complete, Integer};
+
+use super::chineese_remainder_theorem::chinese_remainder_theorem_impl;
+use super::discrete_log::pohlig_hellman;
+use super::factorization::{factorint, is_probable_prime};
+use super::num_utils::pow_mod;
+use super::primitive_root::multiplicative_order;
+use super::static_data::SMALL_PRIME_CACHE;
+use super::threading::get_large_pool;
+
+// the halves from the euclidean split have to fit in a u64
+const MAX_BITS: u32 = 126;
+// below this the large prime is better left to rho
+const MIN_SUBGROUP_BITS: u32 = 16;
+// relations beyond the size of the factor base
+const EXTRA_RELATIONS: usize = 40;
+// candidates walked per parallel task
+const BATCH: u64 = 1 << 12;
+// tries for a single log before giving up
+const DESCENT_ATTEMPTS: u64 = 1 << 22;
+
+fn _splitmix(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn _random_below(seed: u64, bound: &Integer) -> Integer {
+    // seed spread over bound, good enough to pick starting exponents
+    let mut value = Integer::new();
+    for i in 0..bound.significant_bits().div_ceil(64) + 1 {
+        value <<= 64;
+        value += _splitmix(seed.wrapping_mul(31).wrapping_add(i as u64));
+    }
+    value.modulo(bound)
+}
+
+fn _split(t: u128, p: u128, root: u128) -> (u64, u64) {
+    /*
+    |a|, |b| with t b = a mod p and both about sqrt(p), root is isqrt(p)
+    r_i = s_i p + t_i t along the euclidean algorithm, |t_i| <= p / r_(i - 1)
+    */
+    let (mut r0, mut r1) = (p, t);
+    let (mut t0, mut t1) = (0i128, 1i128);
+    while r1 > root {
+        let quotient = r0 / r1;
+        (r0, r1) = (r1, r0 - quotient * r1);
+        (t0, t1) = (t1, t0 - quotient as i128 * t1);
+    }
+    (r1 as u64, t1.unsigned_abs() as u64)
+}
+
+struct Smooth {
+    // (index into the factor base, exponent)
+    exponents: Vec<(usize, u32)>,
+    // 1 or a single prime between B and B^2
+    cofactor: u64,
+}
+
+fn _trial_factor(mut value: u64, factor_base: &[u64]) -> Option<Smooth> {
+    // None when what is left after the factor base is not a prime below B^2
+    if value == 0 {
+        return None;
+    }
+    let mut exponents = Vec::new();
+    for (i, &p) in factor_base.iter().enumerate() {
+        if p * p > value {
+            break;
+        }
+        if value.is_multiple_of(p) {
+            let mut e = 0;
+            while value.is_multiple_of(p) {
+                value /= p;
+                e += 1;
+            }
+            exponents.push((i, e));
+        }
+    }
+    let bound = *factor_base.last().unwrap();
+    if value > 1 && value <= bound {
+        /* a prime of the factor base itself, the loop stopped before reaching it */
+        let i = factor_base.binary_search(&value).ok()?;
+        exponents.push((i, 1));
+        exponents.sort_unstable();
+        value = 1;
+    }
+    if value > 1 && value as u128 >= bound as u128 * bound as u128 {
+        return None;
+    }
+    Some(Smooth {
+        exponents,
+        cofactor: value,
+    })
+}
+
+#[derive(Clone)]
+struct SparseRow {
+    // sorted by column, no zero entries
+    entries: Vec<(usize, Integer)>,
+    rhs: Integer,
+}
+
+impl SparseRow {
+    fn get(&self, column: usize) -> Option<&Integer> {
+        self.entries
+            .binary_search_by_key(&column, |(c, _)| *c)
+            .ok()
+            .map(|i| &self.entries[i].1)
+    }
+
+    fn minus_multiple(&self, factor: &Integer, other: &SparseRow, q: &Integer) -> SparseRow {
+        // self - factor * other mod q
+        let mut entries = Vec::with_capacity(self.entries.len() + other.entries.len());
+        let (mut i, mut j) = (0, 0);
+        while i < self.entries.len() || j < other.entries.len() {
+            let left = self.entries.get(i).map(|(c, _)| *c).unwrap_or(usize::MAX);
+            let right = other.entries.get(j).map(|(c, _)| *c).unwrap_or(usize::MAX);
+            let (column, value) = if left < right {
+                i += 1;
+                (left, self.entries[i - 1].1.clone())
+            } else if right < left {
+                j += 1;
+                (
+                    right,
+                    (-(factor * &other.entries[j - 1].1).complete()).modulo(q),
+                )
+            } else {
+                i += 1;
+                j += 1;
+                let product = (factor * &other.entries[j - 1].1).complete();
+                (left, (&self.entries[i - 1].1 - product).modulo(q))
+            };
+            if value != 0 {
+                entries.push((column, value));
+            }
+        }
+        let rhs = (&self.rhs - (factor * &other.rhs).complete()).modulo(q);
+        SparseRow { entries, rhs }
+    }
+}
+
+fn _solve_mod_q(rows: Vec<SparseRow>, columns: usize, q: &Integer) -> Vec<Option<Integer>> {
+    /*
+    structured gaussian elimination, x with every row . x = rhs mod q
+    unknowns that the relations do not pin down come back as None
+    */
+    let mut active: Vec<Option<SparseRow>> = rows.into_iter().map(Some).collect();
+    let mut column_rows: Vec<HashSet<usize>> = vec![HashSet::new(); columns];
+    for (r, row) in active.iter().enumerate() {
+        for (c, _) in row.as_ref().unwrap().entries.iter() {
+            column_rows[*c].insert(r);
+        }
+    }
+    let mut done = vec![false; columns];
+    let mut pivots: Vec<(usize, SparseRow)> = Vec::new();
+
+    loop {
+        let column = (0..columns)
+            .filter(|c| !done[*c] && !column_rows[*c].is_empty())
+            .min_by_key(|c| column_rows[*c].len());
+        let column = match column {
+            Some(column) => column,
+            None => break,
+        };
+        let r = *column_rows[column]
+            .iter()
+            .min_by_key(|r| active[**r].as_ref().unwrap().entries.len())
+            .unwrap();
+        let mut pivot = active[r].take().unwrap();
+        for (c, _) in pivot.entries.iter() {
+            column_rows[*c].remove(&r);
+        }
+        /* scale the pivot row so the pivot is 1 */
+        let inverse = pivot.get(column).unwrap().clone().invert(q).unwrap();
+        for (_, value) in pivot.entries.iter_mut() {
+            *value = (&*value * &inverse).complete().modulo(q);
+        }
+        pivot.rhs = (&pivot.rhs * &inverse).complete().modulo(q);
+
+        let others: Vec<usize> = column_rows[column].iter().copied().collect();
+        for s in others {
+            let row = active[s].take().unwrap();
+            let factor = row.get(column).unwrap().clone();
+            let reduced = row.minus_multiple(&factor, &pivot, q);
+            for (c, _) in row.entries.iter() {
+                column_rows[*c].remove(&s);
+            }
+            if reduced.entries.is_empty() {
+                /* a dependent relation, rhs is 0 unless a relation was wrong */
+                continue;
+            }
+            for (c, _) in reduced.entries.iter() {
+                column_rows[*c].insert(s);
+            }
+            active[s] = Some(reduced);
+        }
+        done[column] = true;
+        pivots.push((column, pivot));
+    }
+
+    let mut solution: Vec<Option<Integer>> = vec![None; columns];
+    for (column, row) in pivots.iter().rev() {
+        let mut value = row.rhs.clone();
+        let mut known = true;
+        for (c, coefficient) in row.entries.iter() {
+            if c == column {
+                continue;
+            }
+            match &solution[*c] {
+                Some(x) => value -= (coefficient * x).complete(),
+                None => {
+                    known = false;
+                    break;
+                }
+            }
+        }
+        if known {
+            solution[*column] = Some(value.modulo(q));
+        }
+    }
+    solution
+}
+
+fn _smoothness_bound(p: &Integer) -> u64 {
+    // exp(c sqrt(ln p ln ln p)), capped by the small prime cache
+    let ln_p = p.significant_bits() as f64 * std::f64::consts::LN_2;
+    let bound = (0.6 * (ln_p * ln_p.ln()).sqrt()).exp() as u64;
+    bound.clamp(300, *SMALL_PRIME_CACHE.last().unwrap() as u64)
+}
+
+pub struct IndexCalculus {
+    p: Integer,
+    base: Integer,
+    order: Integer,
+    root: u128,
+    // largest prime of the order of the base, factor base logs are known mod q
+    q: Integer,
+    factor_base: Vec<u64>,
+    logs: Vec<Option<Integer>>,
+}
+
+impl IndexCalculus {
+    pub fn new(p: &Integer, base: &Integer) -> Result<Self, String> {
+        if !is_probable_prime(p) || *p < 3 {
+            return Err("p should be an odd prime".to_string());
+        }
+        if p.significant_bits() > MAX_BITS {
+            return Err(format!("p should have at most {} bits", MAX_BITS));
+        }
+        let base = base.clone().modulo(p);
+        if base == 0 {
+            return Err("base should be a unit mod p".to_string());
+        }
+        let order = multiplicative_order(&base, p)?;
+        let (q, e) = factorint(&order)
+            .factors()
+            .iter()
+            .next_back()
+            .map(|(q, e)| (q.clone(), *e))
+            .ok_or("the base should not be 1".to_string())?;
+        if q.significant_bits() < MIN_SUBGROUP_BITS {
+            return Err("the order of the base has no large prime factor".to_string());
+        }
+        if e > 1 {
+            return Err("the largest prime of the order should divide it once".to_string());
+        }
+
+        let bound = _smoothness_bound(p);
+        let factor_base: Vec<u64> = SMALL_PRIME_CACHE
+            .iter()
+            .map(|p| *p as u64)
+            .take_while(|p| *p <= bound)
+            .collect();
+        let mut calculus = IndexCalculus {
+            p: p.clone(),
+            base,
+            order,
+            root: Integer::from(p.sqrt_ref()).to_u128().unwrap(),
+            q,
+            factor_base,
+            logs: Vec::new(),
+        };
+        let relations = calculus._collect_relations();
+        calculus.logs = _solve_mod_q(relations, calculus.factor_base.len(), &calculus.q);
+        calculus._check_logs();
+        Ok(calculus)
+    }
+
+    pub fn factor_base(&self) -> &[u64] {
+        &self.factor_base
+    }
+
+    pub fn subgroup_order(&self) -> &Integer {
+        &self.q
+    }
+
+    pub fn factor_base_log(&self, prime: u64) -> Option<&Integer> {
+        // log of a factor base prime mod q
+        let i = self.factor_base.binary_search(&prime).ok()?;
+        self.logs[i].as_ref()
+    }
+
+    fn _project(&self, x: &Integer) -> Integer {
+        let cofactor = Integer::from(&self.p - 1u32) / &self.q;
+        pow_mod(x, &cofactor, &self.p)
+    }
+
+    fn _random_step(&self, seed: u64) -> (Integer, Integer) {
+        /*
+        the walk multiplies by g^s for a random s, stepping by g itself would turn
+        a smooth a / b into the next one with just one more factor g and the
+        relations would all be the same one
+        */
+        let step = _random_below(!seed, &self.order);
+        let multiplier = pow_mod(&self.base, &step, &self.p);
+        (step, multiplier)
+    }
+
+    fn _collect_relations(&self) -> Vec<SparseRow> {
+        let wanted = self.factor_base.len() + EXTRA_RELATIONS;
+        let p = self.p.to_u128().unwrap();
+        let root = self.root;
+        let pool = get_large_pool();
+        let tasks = pool.current_num_threads() as u64;
+        let mut relations = Vec::new();
+        let mut round = 0u64;
+        while relations.len() < wanted {
+            let found: Vec<SparseRow> = pool.install(|| {
+                (0..tasks)
+                    .into_par_iter()
+                    .flat_map_iter(|task| {
+                        let seed = round * tasks + task;
+                        let mut k = _random_below(seed, &self.order);
+                        let mut t = pow_mod(&self.base, &k, &self.p);
+                        let (step, multiplier) = self._random_step(seed);
+                        let mut found = Vec::new();
+                        for _ in 0..BATCH {
+                            if let Some(row) = self._relation(t.to_u128().unwrap(), p, root, &k) {
+                                found.push(row);
+                            }
+                            t = (t * &multiplier).modulo(&self.p);
+                            k += &step;
+                        }
+                        found
+                    })
+                    .collect()
+            });
+            relations.extend(found);
+            round += 1;
+        }
+        relations
+    }
+
+    fn _relation(&self, t: u128, p: u128, root: u128, k: &Integer) -> Option<SparseRow> {
+        // g^k = t = a / b with a, b smooth over the factor base
+        let (a, b) = _split(t, p, root);
+        let a = _trial_factor(a, &self.factor_base).filter(|s| s.cofactor == 1)?;
+        let b = _trial_factor(b, &self.factor_base).filter(|s| s.cofactor == 1)?;
+        let mut entries: Vec<(usize, Integer)> = Vec::new();
+        for (i, e) in a.exponents {
+            entries.push((i, Integer::from(e)));
+        }
+        for (i, e) in b.exponents {
+            match entries.binary_search_by_key(&i, |(c, _)| *c) {
+                Ok(j) => entries[j].1 -= e,
+                Err(j) => entries.insert(j, (i, -Integer::from(e))),
+            }
+        }
+        let entries = entries
+            .into_iter()
+            .map(|(c, v)| (c, v.modulo(&self.q)))
+            .filter(|(_, v)| *v != 0)
+            .collect();
+        Some(SparseRow {
+            entries,
+            rhs: k.clone().modulo(&self.q),
+        })
+    }
+
+    fn _check_logs(&mut self) {
+        // psi(g)^log(p_i) = psi(p_i), anything that fails is dropped
+        let generator = self._project(&self.base);
+        for i in 0..self.factor_base.len() {
+            if let Some(log) = &self.logs[i] {
+                let prime = Integer::from(self.factor_base[i]);
+                if pow_mod(&generator, log, &self.p) != self._project(&prime) {
+                    self.logs[i] = None;
+                }
+            }
+        }
+    }
+
+    fn _smooth_log(&self, smooth: &Smooth) -> Option<Integer> {
+        // sum of e_i log(p_i) mod q, None if a log is missing
+        let mut total = Integer::new();
+        for (i, e) in smooth.exponents.iter() {
+            total += (self.logs[*i].as_ref()? * *e).complete();
+        }
+        Some(total)
+    }
+
+    fn _descent_step(
+        &self,
+        t: &Integer,
+        k: &Integer,
+        seed: u64,
+        allow_medium: bool,
+    ) -> Option<Integer> {
+        // log h from h g^k = t = a / b, log h = log a - log b - k mod q
+        let (a, b) = _split(t.to_u128().unwrap(), self.p.to_u128().unwrap(), self.root);
+        let a = _trial_factor(a, &self.factor_base)?;
+        let b = _trial_factor(b, &self.factor_base)?;
+        let mut log = self._smooth_log(&a)? - self._smooth_log(&b)? - k;
+        for (cofactor, sign) in [(a.cofactor, 1), (b.cofactor, -1)] {
+            if cofactor == 1 {
+                continue;
+            }
+            if !allow_medium {
+                return None;
+            }
+            let medium = self._log_mod_q(&Integer::from(cofactor), seed ^ cofactor, false)?;
+            log += medium * sign;
+        }
+        Some(log.modulo(&self.q))
+    }
+
+    fn _log_mod_q(&self, h: &Integer, seed: u64, allow_medium: bool) -> Option<Integer> {
+        // with allow_medium a leftover prime in a or b is descended on its own
+        let mut k = _random_below(seed, &self.order);
+        let mut t = (pow_mod(&self.base, &k, &self.p) * h).modulo(&self.p);
+        let (step, multiplier) = self._random_step(seed);
+        for _ in 0..DESCENT_ATTEMPTS {
+            if let Some(log) = self._descent_step(&t, &k, seed, allow_medium) {
+                return Some(log);
+            }
+            t = (t * &multiplier).modulo(&self.p);
+            k += &step;
+        }
+        None
+    }
+
+    pub fn log(&self, h: &Integer) -> Result<Integer, String> {
+        // smallest x >= 0 with base^x = h mod p
+        let h = h.clone().modulo(&self.p);
+        if h == 0 {
+            return Err("Log does not exist".to_string());
+        }
+        let x_q = self
+            ._log_mod_q(&h, 1, true)
+            .ok_or("descent failed to find a smooth relation".to_string())?;
+
+        /* the rest of the order is smooth, Pohlig-Hellman on g^q and h^q */
+        let rest = (&self.order / &self.q).complete();
+        let x_rest = if rest == 1 {
+            Integer::new()
+        } else {
+            let base_rest = pow_mod(&self.base, &self.q, &self.p);
+            let h_rest = pow_mod(&h, &self.q, &self.p);
+            pohlig_hellman(&h_rest, &base_rest, &self.p, &rest, &factorint(&rest))
+                .ok_or("Log does not exist".to_string())?
+        };
+        let x = chinese_remainder_theorem_impl(&[x_q, x_rest], &[self.q.clone(), rest])?
+            .modulo(&self.order);
+        if pow_mod(&self.base, &x, &self.p) != h {
+            return Err("Log does not exist".to_string());
+        }
+        Ok(x)
+    }
+}
+
+pub fn index_calculus_log(a: &Integer, b: &Integer, p: &Integer) -> Result<Integer, String> {
+    // b^x = a mod p with a single use of the factor base
+    IndexCalculus::new(p, b)?.log(a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let p = 1_000_000_007u128;
+        for t in [1u128, 2, 12345, 999_999_999, 500_000_004] {
+            let (a, b) = _split(t, p, 31_622);
+            assert!((a as u128) * (a as u128) < p);
+            assert!((b as u128) * (b as u128) <= 4 * p);
+            let lhs = t * b as u128 % p;
+            assert!(lhs == a as u128 || lhs == p - a as u128);
+        }
+    }
+
+    #[test]
+    fn test_solve_mod_q() {
+        // a random sparse system with a planted solution
+        let q = Integer::from(1_000_003u32);
+        let columns = 30;
+        let planted: Vec<Integer> = (0..columns).map(|i| Integer::from(i * i + 7)).collect();
+        let mut rows = Vec::new();
+        for r in 0..60u64 {
+            let mut entries = Vec::new();
+            for c in 0..columns {
+                let x = _splitmix(r * 1000 + c as u64);
+                if x.is_multiple_of(5) {
+                    entries.push((c, Integer::from(x % 4 + 1)));
+                }
+            }
+            let rhs = entries
+                .iter()
+                .map(|(c, v)| (v * &planted[*c]).complete())
+                .sum::<Integer>()
+                .modulo(&q);
+            rows.push(SparseRow { entries, rhs });
+        }
+        let solution = _solve_mod_q(rows, columns, &q);
+        for (x, expected) in solution.iter().zip(planted.iter()) {
+            if let Some(x) = x {
+                assert_eq!(x, expected);
+            }
+        }
+        assert!(solution.iter().filter(|x| x.is_some()).count() > columns - 3);
+    }
+
+    #[test]
+    fn test_index_calculus_safe_prime() {
+        // p = 2 q + 1, rho would need about 2^30 steps
+        let p = Integer::from(18_446_744_073_709_550_147u64);
+        let calculus = IndexCalculus::new(&p, &Integer::from(5)).unwrap();
+        assert_eq!(Integer::from(calculus.subgroup_order() * 2u32) + 1u32, p);
+        for x in [0u64, 1, 123_456_789_012_345, 9_000_000_000_000_000_000] {
+            let x = Integer::from(x).modulo(&calculus.order);
+            let h = pow_mod(&Integer::from(5), &x, &p);
+            assert_eq!(calculus.log(&h).unwrap(), x);
+        }
+    }
+
+    #[test]
+    #[ignore = "about 3 s in release, far longer in debug"]
+    fn test_index_calculus_80_bits() {
+        // an 80 bit safe prime, the top of the range this is meant for
+        let p = Integer::from_str_radix("604462909807314587353439", 10).unwrap();
+        let b = Integer::from(11);
+        let calculus = IndexCalculus::new(&p, &b).unwrap();
+        assert_eq!(Integer::from(calculus.subgroup_order() * 2u32) + 1u32, p);
+        let x = Integer::from_str_radix("123456789012345678901234", 10)
+            .unwrap()
+            .modulo(&calculus.order);
+        let h = pow_mod(&b, &x, &p);
+        assert_eq!(calculus.log(&h).unwrap(), x);
+    }
+
+    #[test]
+    fn test_index_calculus_log_errors() {
+        let p = Integer::from(1_000_000_007u64);
+        assert!(
+            index_calculus_log(&Integer::from(3), &Integer::from(5), &Integer::from(91)).is_err()
+        );
+        assert!(index_calculus_log(&Integer::from(0), &Integer::from(5), &p).is_err());
+        // the order of -1 has no large prime
+        assert!(IndexCalculus::new(&p, &Integer::from(&p - 1u32)).is_err());
+    }
+}
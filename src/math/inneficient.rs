@@ -82,6 +82,7 @@ pub fn sum_of_factors_from_pentagonal_numbers(target: Integer) -> Vec<Integer> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::math::factorization::factorint;
 
     #[test]
     fn test_simpple_partition_number() {
@@ -93,4 +94,13 @@ mod tests {
         assert!(result[5] == 6);
         assert!(result[13] == 14);
     }
+
+    #[test]
+    fn test_sum_of_factors_matches_sigma_1() {
+        let result = sum_of_factors_from_pentagonal_numbers(Integer::from(300));
+        for n in 1..result.len() {
+            let sigma = factorint(&Integer::from(n)).sigma_k(1);
+            assert_eq!(result[n], sigma, "n = {}", n);
+        }
+    }
 }
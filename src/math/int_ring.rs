@@ -1,5 +1,12 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::sync::OnceLock;
+
 use rug::Integer;
 
+use super::gcd::extended_gcd;
+use super::ring::{EuclideanDomain, Ring};
+
 #[derive(Debug)]
 pub struct IntegerRing {
     zero: Integer,
@@ -14,51 +21,6 @@ impl IntegerRing {
         }
     }
 
-    pub fn zero(&self) -> Integer {
-        self.zero.clone()
-    }
-
-    pub fn one(&self) -> Integer {
-        self.one.clone()
-    }
-
-    pub fn gcd(&self, a: &Integer, b: &Integer) -> Integer {
-        a.clone().gcd(&b)
-    }
-
-    fn gcdex(&self, a: &Integer, b: &Integer) -> (Integer, Integer, Integer) {
-        // todo double check logic and unit test
-        let mut x0 = Integer::from(1);
-        let mut x1 = Integer::from(0);
-        let mut y0 = Integer::from(0);
-        let mut y1 = Integer::from(1);
-
-        let mut a = a.clone();
-        let mut b = b.clone();
-
-        while b != 0 {
-            let (q, r) = a.div_rem(b.clone());
-            a = b.clone();
-            b = r.clone();
-
-            let temp_x = x0 - &q * &x1;
-            let temp_y = y0 - &q * &y1;
-            x0 = x1;
-            x1 = temp_x;
-            y0 = y1;
-            y1 = temp_y;
-        }
-
-        (a, x0, y0)
-    }
-
-    fn lcm(a: &Integer, b: &Integer) -> Integer {
-        let gcd = a.clone().gcd(b);
-        let product = a.clone() * b.clone();
-
-        product / gcd
-    }
-
     pub fn sqrt(&self, a: &Integer) -> Integer {
         a.clone().sqrt()
     }
@@ -89,7 +51,7 @@ impl IntegerRing {
     }
 
     pub fn log(&self, a: &Integer, b: &Integer) -> Option<Integer> {
-        if a.cmp0() <= Ordering::Equal || b.cmp0() <= Ordering::Equal || *b == Integer::from(1) {
+        if a.cmp0() <= Ordering::Equal || b.cmp0() <= Ordering::Equal || *b == 1 {
             return None;
         }
 
@@ -102,6 +64,69 @@ impl IntegerRing {
     }
 }
 
+impl Default for IntegerRing {
+    fn default() -> Self {
+        IntegerRing::new()
+    }
+}
+
+impl Ring for IntegerRing {
+    type Element = Integer;
+
+    fn zero(&self) -> Integer {
+        self.zero.clone()
+    }
+
+    fn one(&self) -> Integer {
+        self.one.clone()
+    }
+
+    fn convert(&self, n: &Integer) -> Integer {
+        n.clone()
+    }
+
+    fn add(&self, a: &Integer, b: &Integer) -> Integer {
+        Integer::from(a + b)
+    }
+
+    fn sub(&self, a: &Integer, b: &Integer) -> Integer {
+        Integer::from(a - b)
+    }
+
+    fn mul(&self, a: &Integer, b: &Integer) -> Integer {
+        Integer::from(a * b)
+    }
+
+    fn neg(&self, a: &Integer) -> Integer {
+        Integer::from(-a)
+    }
+}
+
+impl EuclideanDomain for IntegerRing {
+    fn div_rem(&self, a: &Integer, b: &Integer) -> (Integer, Integer) {
+        // euclidean division, 0 <= r < |b|
+        a.clone().div_rem_euc(b.clone())
+    }
+
+    fn normal_unit(&self, a: &Integer) -> Integer {
+        Integer::from(if *a < 0 { -1 } else { 1 })
+    }
+
+    /* gmp backed versions of the defaults */
+
+    fn gcdex(&self, a: &Integer, b: &Integer) -> (Integer, Integer, Integer) {
+        extended_gcd(a, b)
+    }
+
+    fn gcd(&self, a: &Integer, b: &Integer) -> Integer {
+        a.clone().gcd(b)
+    }
+
+    fn lcm(&self, a: &Integer, b: &Integer) -> Integer {
+        a.clone().lcm(b)
+    }
+}
+
 impl PartialEq for IntegerRing {
     fn eq(&self, _other: &Self) -> bool {
         true // There's only one integer ring
@@ -118,5 +143,5 @@ impl Hash for IntegerRing {
 
 static ZZ: OnceLock<IntegerRing> = OnceLock::new();
 pub fn get_zz() -> &'static IntegerRing {
-    ZZ.get_or_init(|| IntegerRing::new())
+    ZZ.get_or_init(IntegerRing::new)
 }
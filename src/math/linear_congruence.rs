@@ -0,0 +1,482 @@
+/*
+linear congruences and linear diophantine systems
+
+a x = b mod n: with g = gcd(a, n) there is a solution iff g | b, and then it is
+a single class x0 mod n / g, x0 = (b / g) (a / g)^-1
+
+A x = b over Z goes through the column hermite normal form A U = H, U unimodular:
+    H is lower triangular in echelon form so H y = b is forward substitution
+    where every pivot has to divide exactly
+    the columns of U past the last pivot span the kernel of A
+    x = U y
+A x = b mod n is the same as A x + n z = b over Z, solved on [A | n I] and cut
+back to the x coordinates
+
+solutions come back as a particular point plus the lattice spanned by the
+generators, those are put in hermite form too and the particular point is reduced
+by them, so the answer does not depend on the path the elimination took
+*/
+use rug::ops::DivRounding;
+use rug::{Complete, Integer};
+
+use super::int_ring::get_zz;
+use super::ring::EuclideanDomain;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatticeSolution {
+    // every solution is particular + t_1 g_1 + ... + t_r g_r for integers t_i
+    pub particular: Vec<Integer>,
+    pub generators: Vec<Vec<Integer>>,
+}
+
+impl LatticeSolution {
+    pub fn point(&self, parameters: &[Integer]) -> Vec<Integer> {
+        // the solution for the given t_i, missing parameters are 0
+        let mut x = self.particular.clone();
+        for (generator, t) in self.generators.iter().zip(parameters.iter()) {
+            for (xi, gi) in x.iter_mut().zip(generator.iter()) {
+                *xi += (gi * t).complete();
+            }
+        }
+        x
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.generators.len()
+    }
+}
+
+pub fn solve_linear_congruence(
+    a: &Integer,
+    b: &Integer,
+    n: &Integer,
+) -> Result<Option<(Integer, Integer)>, String> {
+    /*
+    (x0, m) with a x = b mod n exactly when x = x0 mod m, 0 <= x0 < m
+    the solutions below n are x0 + i m for i < n / m
+    */
+    if *n <= 0 {
+        return Err("n must be > 0".to_string());
+    }
+    let (g, inv, _) = get_zz().gcdex(a, n);
+    if !b.is_divisible(&g) {
+        return Ok(None);
+    }
+    let m = (n / &g).complete();
+    let x0 = ((b / &g).complete() * inv).modulo(&m);
+    Ok(Some((x0, m)))
+}
+
+fn _column_operation(
+    matrix: &mut [Vec<Integer>],
+    p: usize,
+    j: usize,
+    (x, y, u, v): (&Integer, &Integer, &Integer, &Integer),
+) {
+    // (col_p, col_j) <- (x col_p + y col_j, u col_p + v col_j)
+    for row in matrix.iter_mut() {
+        let new_p = (x * &row[p]).complete() + (y * &row[j]).complete();
+        let new_j = (u * &row[p]).complete() + (v * &row[j]).complete();
+        row[p] = new_p;
+        row[j] = new_j;
+    }
+}
+
+pub fn hermite_normal_form(matrix: &[Vec<Integer>]) -> (Vec<Vec<Integer>>, Vec<Vec<Integer>>) {
+    /*
+    (H, U) with matrix U = H, U unimodular and H the column hermite normal form:
+    pivots move right going down, are positive, and the entries left of a pivot
+    are reduced into [0, pivot)
+    */
+    let rows = matrix.len();
+    let columns = matrix.first().map_or(0, |row| row.len());
+    let mut h = matrix.to_vec();
+    let mut u: Vec<Vec<Integer>> = (0..columns)
+        .map(|i| {
+            (0..columns)
+                .map(|j| Integer::from(u32::from(i == j)))
+                .collect()
+        })
+        .collect();
+
+    let mut pivot = 0;
+    for row in 0..rows {
+        if pivot == columns {
+            break;
+        }
+        for j in pivot + 1..columns {
+            if h[row][j] == 0 {
+                continue;
+            }
+            /* unimodular since x a / g + y b / g = 1 */
+            let a = h[row][pivot].clone();
+            let b = h[row][j].clone();
+            let (g, x, y) = get_zz().gcdex(&a, &b);
+            let u_coefficient = -(b / &g);
+            let v_coefficient = a / &g;
+            let step = (&x, &y, &u_coefficient, &v_coefficient);
+            _column_operation(&mut h, pivot, j, step);
+            _column_operation(&mut u, pivot, j, step);
+        }
+        if h[row][pivot] == 0 {
+            continue;
+        }
+        if h[row][pivot] < 0 {
+            for r in h.iter_mut().chain(u.iter_mut()) {
+                r[pivot] = -r[pivot].clone();
+            }
+        }
+        for k in 0..pivot {
+            let q = h[row][k].clone().div_floor(&h[row][pivot]);
+            if q == 0 {
+                continue;
+            }
+            let minus_q = -q;
+            let one = Integer::from(1);
+            let zero = Integer::new();
+            /* col_k -= q col_pivot, written as the same 2x2 step */
+            let step = (&one, &zero, &minus_q, &one);
+            _column_operation(&mut h, pivot, k, step);
+            _column_operation(&mut u, pivot, k, step);
+        }
+        pivot += 1;
+    }
+    (h, u)
+}
+
+fn _canonical(particular: Vec<Integer>, generators: Vec<Vec<Integer>>) -> LatticeSolution {
+    // generators in hermite form and the particular point reduced by them
+    let dimension = particular.len();
+    if generators.is_empty() {
+        return LatticeSolution {
+            particular,
+            generators,
+        };
+    }
+    let as_columns: Vec<Vec<Integer>> = (0..dimension)
+        .map(|i| generators.iter().map(|g| g[i].clone()).collect())
+        .collect();
+    let (h, _) = hermite_normal_form(&as_columns);
+    let basis: Vec<Vec<Integer>> = (0..generators.len())
+        .map(|j| h.iter().map(|row| row[j].clone()).collect::<Vec<Integer>>())
+        .filter(|column| column.iter().any(|entry| *entry != 0))
+        .collect();
+
+    /* the pivot rows increase so reducing by one column leaves the earlier rows alone */
+    let mut x = particular;
+    for column in basis.iter() {
+        let p = column.iter().position(|entry| *entry != 0).unwrap();
+        let q = x[p].clone().div_floor(&column[p]);
+        for (xi, ci) in x.iter_mut().zip(column.iter()) {
+            *xi -= (&q * ci).complete();
+        }
+    }
+    LatticeSolution {
+        particular: x,
+        generators: basis,
+    }
+}
+
+fn _check_dimensions(a: &[Vec<Integer>], b: &[Integer]) -> Result<usize, String> {
+    // the number of unknowns
+    if a.len() != b.len() {
+        return Err("Matrix and vector dimensions do not match".to_string());
+    }
+    let columns = a.first().map_or(0, |row| row.len());
+    if a.iter().any(|row| row.len() != columns) {
+        return Err("Matrix rows must have equal length".to_string());
+    }
+    Ok(columns)
+}
+
+pub fn solve_linear_diophantine(
+    a: &[Vec<Integer>],
+    b: &[Integer],
+) -> Result<Option<LatticeSolution>, String> {
+    // every integer x with A x = b, None when there is none
+    let columns = _check_dimensions(a, b)?;
+    let (h, u) = hermite_normal_form(a);
+
+    let mut y = vec![Integer::new(); columns];
+    let mut pivot = 0;
+    for (row, target) in h.iter().zip(b.iter()) {
+        let mut residual = target.clone();
+        for (entry, yj) in row.iter().zip(y.iter()).take(pivot) {
+            residual -= (entry * yj).complete();
+        }
+        if pivot < columns && row[pivot] != 0 {
+            if !residual.is_divisible(&row[pivot]) {
+                return Ok(None);
+            }
+            y[pivot] = residual / &row[pivot];
+            pivot += 1;
+        } else if residual != 0 {
+            /* the row is zero from the pivot on */
+            return Ok(None);
+        }
+    }
+
+    let particular: Vec<Integer> = u
+        .iter()
+        .map(|row| {
+            row.iter()
+                .zip(y.iter())
+                .map(|(entry, yj)| (entry * yj).complete())
+                .sum()
+        })
+        .collect();
+    let kernel: Vec<Vec<Integer>> = (pivot..columns)
+        .map(|j| u.iter().map(|row| row[j].clone()).collect())
+        .collect();
+    Ok(Some(_canonical(particular, kernel)))
+}
+
+pub fn solve_linear_system_mod(
+    a: &[Vec<Integer>],
+    b: &[Integer],
+    n: &Integer,
+) -> Result<Option<LatticeSolution>, String> {
+    /*
+    every x with A x = b mod n, the lattice always contains n Z^k so there are
+    k generators and the particular point has entries in [0, n)
+    */
+    let columns = _check_dimensions(a, b)?;
+    if *n <= 0 {
+        return Err("n must be > 0".to_string());
+    }
+    let rows = a.len();
+    let extended: Vec<Vec<Integer>> = a
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let mut row = row.clone();
+            row.extend((0..rows).map(|j| if i == j { n.clone() } else { Integer::new() }));
+            row
+        })
+        .collect();
+    let solution = match solve_linear_diophantine(&extended, b)? {
+        Some(solution) => solution,
+        None => return Ok(None),
+    };
+
+    /* n e_i is always a solution direction, adding them keeps the rank full with no rows */
+    let mut generators: Vec<Vec<Integer>> = solution
+        .generators
+        .iter()
+        .map(|g| g[..columns].to_vec())
+        .collect();
+    generators.extend((0..columns).map(|i| {
+        (0..columns)
+            .map(|j| if i == j { n.clone() } else { Integer::new() })
+            .collect()
+    }));
+    let particular = solution.particular[..columns].to_vec();
+    Ok(Some(_canonical(particular, generators)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(rows: &[&[i64]]) -> Vec<Vec<Integer>> {
+        rows.iter()
+            .map(|row| row.iter().map(|x| Integer::from(*x)).collect())
+            .collect()
+    }
+
+    fn vector(values: &[i64]) -> Vec<Integer> {
+        values.iter().map(|x| Integer::from(*x)).collect()
+    }
+
+    fn apply(a: &[Vec<Integer>], x: &[Integer]) -> Vec<Integer> {
+        a.iter()
+            .map(|row| {
+                row.iter()
+                    .zip(x.iter())
+                    .map(|(entry, xi)| (entry * xi).complete())
+                    .sum()
+            })
+            .collect()
+    }
+
+    fn in_lattice(solution: &LatticeSolution, x: &[Integer]) -> bool {
+        // x - particular in the span of the triangular generators
+        let mut rest: Vec<Integer> = x
+            .iter()
+            .zip(solution.particular.iter())
+            .map(|(xi, pi)| (xi - pi).complete())
+            .collect();
+        for column in solution.generators.iter() {
+            let p = column.iter().position(|entry| *entry != 0).unwrap();
+            if !rest[p].is_divisible(&column[p]) {
+                return false;
+            }
+            let q = (&rest[p] / &column[p]).complete();
+            for (ri, ci) in rest.iter_mut().zip(column.iter()) {
+                *ri -= (&q * ci).complete();
+            }
+        }
+        rest.iter().all(|ri| *ri == 0)
+    }
+
+    #[test]
+    fn test_solve_linear_congruence() {
+        let solve = |a: i64, b: i64, n: i64| {
+            solve_linear_congruence(&Integer::from(a), &Integer::from(b), &Integer::from(n))
+                .unwrap()
+        };
+        assert_eq!(solve(3, 2, 7), Some((Integer::from(3), Integer::from(7))));
+        assert_eq!(solve(6, 4, 10), Some((Integer::from(4), Integer::from(5))));
+        assert_eq!(solve(6, 3, 10), None);
+        assert_eq!(solve(0, 0, 4), Some((Integer::from(0), Integer::from(1))));
+        assert!(
+            solve_linear_congruence(&Integer::from(1), &Integer::from(1), &Integer::from(0))
+                .is_err()
+        );
+        for n in 1..40i64 {
+            for a in -5..n {
+                for b in 0..n {
+                    let expected: Vec<i64> =
+                        (0..n).filter(|x| (a * x - b).rem_euclid(n) == 0).collect();
+                    let got: Vec<i64> = match solve(a, b, n) {
+                        Some((x0, m)) => {
+                            let (x0, m) = (x0.to_i64().unwrap(), m.to_i64().unwrap());
+                            assert!(0 <= x0 && x0 < m);
+                            (0..n / m).map(|i| x0 + i * m).collect()
+                        }
+                        None => Vec::new(),
+                    };
+                    assert_eq!(got, expected, "a = {}, b = {}, n = {}", a, b, n);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hermite_normal_form() {
+        let a = matrix(&[&[2, 3, 6, 2], &[5, 6, 1, 6], &[8, 3, 1, 1]]);
+        let (h, u) = hermite_normal_form(&a);
+        // A U = H, H lower echelon with reduced positive pivots
+        for (row, h_row) in a.iter().zip(h.iter()) {
+            for (j, entry) in h_row.iter().enumerate() {
+                let column: Vec<Integer> = u.iter().map(|r| r[j].clone()).collect();
+                assert_eq!(apply(std::slice::from_ref(row), &column)[0], *entry);
+            }
+        }
+        assert_eq!(h[0][1], 0);
+        assert_eq!(h[0][2], 0);
+        assert_eq!(h[1][2], 0);
+        for (i, h_row) in h.iter().enumerate() {
+            assert!(h_row[i] > 0);
+            for entry in h_row[..i].iter() {
+                assert!(*entry >= 0 && *entry < h_row[i]);
+            }
+        }
+        // the last column is the kernel
+        let kernel: Vec<Integer> = u.iter().map(|r| r[3].clone()).collect();
+        assert!(apply(&a, &kernel).iter().all(|entry| *entry == 0));
+        assert!(h.iter().all(|row| row[3] == 0));
+    }
+
+    #[test]
+    fn test_solve_linear_diophantine() {
+        // 6x + 10y + 15z = 1
+        let a = matrix(&[&[6, 10, 15]]);
+        let solution = solve_linear_diophantine(&a, &vector(&[1]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(solution.dimension(), 2);
+        for t in -3..4i64 {
+            for s in -3..4i64 {
+                let x = solution.point(&vector(&[t, s]));
+                assert_eq!(apply(&a, &x), vector(&[1]));
+            }
+        }
+        for x in -6..7i64 {
+            for y in -6..7i64 {
+                for z in -6..7i64 {
+                    let point = vector(&[x, y, z]);
+                    assert_eq!(in_lattice(&solution, &point), 6 * x + 10 * y + 15 * z == 1);
+                }
+            }
+        }
+
+        // full rank has the single solution
+        let a = matrix(&[&[2, 1], &[1, 3]]);
+        let solution = solve_linear_diophantine(&a, &vector(&[4, 7]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(solution.particular, vector(&[1, 2]));
+        assert_eq!(solution.dimension(), 0);
+        assert_eq!(
+            solve_linear_diophantine(&a, &vector(&[1, 0])).unwrap(),
+            None
+        );
+
+        // dependent rows, consistent and not
+        let a = matrix(&[&[1, 2, 3], &[2, 4, 6]]);
+        let solution = solve_linear_diophantine(&a, &vector(&[5, 10]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(solution.dimension(), 2);
+        assert_eq!(
+            apply(&a, &solution.point(&vector(&[7, -2]))),
+            vector(&[5, 10])
+        );
+        assert_eq!(
+            solve_linear_diophantine(&a, &vector(&[5, 11])).unwrap(),
+            None
+        );
+
+        assert!(solve_linear_diophantine(&a, &vector(&[5])).is_err());
+        assert!(solve_linear_diophantine(&matrix(&[&[1, 2], &[3]]), &vector(&[1, 2])).is_err());
+    }
+
+    #[test]
+    fn test_solve_linear_system_mod() {
+        type System<'a> = (&'a [&'a [i64]], &'a [i64], i64);
+        let systems: [System; 6] = [
+            (&[&[1, 1], &[1, 2]], &[3, 5], 7),
+            (&[&[2, 4], &[3, 3]], &[2, 3], 6),
+            (&[&[2, 4], &[3, 3]], &[1, 3], 6),
+            (&[&[4, 6]], &[2], 12),
+            (&[&[0, 0]], &[0], 5),
+            (&[&[3, 6], &[6, 3], &[1, 1]], &[3, 6, 1], 9),
+        ];
+        for (rows, b, n) in systems {
+            let a = matrix(rows);
+            let b = vector(b);
+            let solution = solve_linear_system_mod(&a, &b, &Integer::from(n)).unwrap();
+            let mut count = 0;
+            for x in 0..n {
+                for y in 0..n {
+                    let point = vector(&[x, y]);
+                    let holds = apply(&a, &point)
+                        .iter()
+                        .zip(b.iter())
+                        .all(|(lhs, rhs)| (lhs - rhs).complete().is_divisible(&Integer::from(n)));
+                    count += i64::from(holds);
+                    match &solution {
+                        Some(solution) => assert_eq!(in_lattice(solution, &point), holds),
+                        None => assert!(!holds),
+                    }
+                }
+            }
+            if let Some(solution) = solution {
+                assert_eq!(solution.dimension(), 2);
+                assert!(solution.particular.iter().all(|x| *x >= 0 && *x < n));
+                // the index of the lattice in Z^2 is n^2 / #solutions
+                let index: Integer = solution
+                    .generators
+                    .iter()
+                    .enumerate()
+                    .map(|(i, g)| g[i].clone())
+                    .product();
+                assert_eq!(index * count, n * n);
+            }
+        }
+        assert!(
+            solve_linear_system_mod(&matrix(&[&[1]]), &vector(&[1]), &Integer::from(0)).is_err()
+        );
+    }
+}
@@ -0,0 +1,511 @@
+/*
+tables of the usual multiplicative functions for every n up to N
+
+linear_sieve is Euler's sieve: every composite i * p is reached exactly once,
+from p = spf(i * p), so f(i * p) comes straight from f(i)
+- p does not divide i: f(i p) = f(i) f(p)
+- p divides i: low[] keeps the p^k part of every n, either n is a prime power and
+  f(p^k) follows from f(p^(k - 1)), or f(n) = f(n / p^k) f(p^k)
+that is ~30 bytes per n so the whole table stops being practical around 10^9
+
+for N up to 10^10 and beyond sieve_segment does [low, high] on its own with the
+primes up to sqrt(high), par_for_each_segment runs those on the large pool and
+hands every finished segment to a callback, nothing is kept around
+*/
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use super::factor_u64::isqrt_u64;
+use super::spf_sieve::SpfTable;
+use super::threading::get_large_pool;
+
+const SEGMENT_SIZE: u64 = 1 << 16;
+const FILE_MAGIC: &[u8; 8] = b"MFTABLE1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MultiplicativeTables {
+    // every table is indexed by n - start, n = 0 has all zeros
+    start: u64,
+    mobius: Vec<i8>,
+    totient: Vec<u64>,
+    divisor_count: Vec<u32>,
+    divisor_sum: Vec<u64>,
+    omega: Vec<u8>,
+    big_omega: Vec<u8>,
+    liouville: Vec<i8>,
+}
+
+impl MultiplicativeTables {
+    fn zeroed(start: u64, len: usize) -> Self {
+        MultiplicativeTables {
+            start,
+            mobius: vec![0; len],
+            totient: vec![0; len],
+            divisor_count: vec![0; len],
+            divisor_sum: vec![0; len],
+            omega: vec![0; len],
+            big_omega: vec![0; len],
+            liouville: vec![0; len],
+        }
+    }
+
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    pub fn end(&self) -> u64 {
+        // last n covered, inclusive
+        self.start + self.len() as u64 - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.mobius.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mobius.is_empty()
+    }
+
+    pub fn mobius(&self) -> &[i8] {
+        &self.mobius
+    }
+
+    pub fn totient(&self) -> &[u64] {
+        &self.totient
+    }
+
+    pub fn divisor_count(&self) -> &[u32] {
+        &self.divisor_count
+    }
+
+    pub fn divisor_sum(&self) -> &[u64] {
+        &self.divisor_sum
+    }
+
+    pub fn omega(&self) -> &[u8] {
+        &self.omega
+    }
+
+    pub fn big_omega(&self) -> &[u8] {
+        &self.big_omega
+    }
+
+    pub fn liouville(&self) -> &[i8] {
+        &self.liouville
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        /*
+        magic, start and length, then every table as little endian values
+        in the order of the fields
+        */
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(FILE_MAGIC)?;
+        writer.write_all(&self.start.to_le_bytes())?;
+        writer.write_all(&(self.len() as u64).to_le_bytes())?;
+        for v in self.mobius.iter() {
+            writer.write_all(&v.to_le_bytes())?;
+        }
+        for v in self.totient.iter() {
+            writer.write_all(&v.to_le_bytes())?;
+        }
+        for v in self.divisor_count.iter() {
+            writer.write_all(&v.to_le_bytes())?;
+        }
+        for v in self.divisor_sum.iter() {
+            writer.write_all(&v.to_le_bytes())?;
+        }
+        writer.write_all(&self.omega)?;
+        writer.write_all(&self.big_omega)?;
+        for v in self.liouville.iter() {
+            writer.write_all(&v.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != FILE_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a multiplicative table file",
+            ));
+        }
+        let start = _read_u64(&mut reader)?;
+        let len = _read_u64(&mut reader)? as usize;
+        let mut tables = MultiplicativeTables::zeroed(start, len);
+
+        let mut bytes = vec![0u8; len];
+        reader.read_exact(&mut bytes)?;
+        tables.mobius = bytes.iter().map(|b| *b as i8).collect();
+        for v in tables.totient.iter_mut() {
+            *v = _read_u64(&mut reader)?;
+        }
+        for v in tables.divisor_count.iter_mut() {
+            let mut buf = [0u8; 4];
+            reader.read_exact(&mut buf)?;
+            *v = u32::from_le_bytes(buf);
+        }
+        for v in tables.divisor_sum.iter_mut() {
+            *v = _read_u64(&mut reader)?;
+        }
+        reader.read_exact(&mut tables.omega)?;
+        reader.read_exact(&mut tables.big_omega)?;
+        reader.read_exact(&mut bytes)?;
+        tables.liouville = bytes.iter().map(|b| *b as i8).collect();
+        Ok(tables)
+    }
+}
+
+fn _read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+pub fn linear_sieve(n: u64) -> MultiplicativeTables {
+    // μ, φ, σ0, σ1, ω, Ω and λ for every 0 <= i <= n in one pass
+    if n > u32::MAX as u64 {
+        panic!("linear_sieve limit {} does not fit the u32 storage", n);
+    }
+    let size = n as usize + 1;
+    let mut t = MultiplicativeTables::zeroed(0, size);
+    // the power of the smallest prime in i
+    let mut low = vec![0u32; size];
+    let mut primes: Vec<u32> = Vec::new();
+
+    if size > 1 {
+        t.mobius[1] = 1;
+        t.totient[1] = 1;
+        t.divisor_count[1] = 1;
+        t.divisor_sum[1] = 1;
+        t.liouville[1] = 1;
+        low[1] = 1;
+    }
+
+    for i in 2..size {
+        if low[i] == 0 {
+            primes.push(i as u32);
+            low[i] = i as u32;
+            t.mobius[i] = -1;
+            t.totient[i] = i as u64 - 1;
+            t.divisor_count[i] = 2;
+            t.divisor_sum[i] = i as u64 + 1;
+            t.omega[i] = 1;
+            t.big_omega[i] = 1;
+            t.liouville[i] = -1;
+        }
+        for &p in primes.iter() {
+            let p = p as usize;
+            let m = i * p;
+            if m >= size {
+                break;
+            }
+            t.big_omega[m] = t.big_omega[i] + 1;
+            t.liouville[m] = -t.liouville[i];
+            if i.is_multiple_of(p) {
+                low[m] = low[i] * p as u32;
+                t.mobius[m] = 0;
+                t.omega[m] = t.omega[i];
+                if low[m] as usize == m {
+                    t.totient[m] = t.totient[i] * p as u64;
+                    t.divisor_count[m] = t.divisor_count[i] + 1;
+                    t.divisor_sum[m] = t.divisor_sum[i] * p as u64 + 1;
+                } else {
+                    let pk = low[m] as usize;
+                    let rest = m / pk;
+                    t.totient[m] = t.totient[rest] * t.totient[pk];
+                    t.divisor_count[m] = t.divisor_count[rest] * t.divisor_count[pk];
+                    t.divisor_sum[m] = t.divisor_sum[rest] * t.divisor_sum[pk];
+                }
+                break;
+            }
+            low[m] = p as u32;
+            t.mobius[m] = -t.mobius[i];
+            t.omega[m] = t.omega[i] + 1;
+            t.totient[m] = t.totient[i] * (p as u64 - 1);
+            t.divisor_count[m] = t.divisor_count[i] * 2;
+            t.divisor_sum[m] = t.divisor_sum[i] * (p as u64 + 1);
+        }
+    }
+    t
+}
+
+pub fn mobius_table(n: u64) -> Vec<i8> {
+    // only μ, same sieve without the other six tables
+    if n > u32::MAX as u64 {
+        panic!("mobius_table limit {} does not fit the u32 storage", n);
+    }
+    let size = n as usize + 1;
+    let mut mobius = vec![0i8; size];
+    let mut composite = vec![false; size];
+    let mut primes: Vec<usize> = Vec::new();
+    if size > 1 {
+        mobius[1] = 1;
+    }
+    for i in 2..size {
+        if !composite[i] {
+            primes.push(i);
+            mobius[i] = -1;
+        }
+        for &p in primes.iter() {
+            let m = i * p;
+            if m >= size {
+                break;
+            }
+            composite[m] = true;
+            if i.is_multiple_of(p) {
+                break;
+            }
+            mobius[m] = -mobius[i];
+        }
+    }
+    mobius
+}
+
+pub fn totient_table(n: u64) -> Vec<u64> {
+    // only φ, for callers that need a long prefix and can't afford all tables
+    if n > u32::MAX as u64 {
+        panic!("totient_table limit {} does not fit the u32 storage", n);
+    }
+    let size = n as usize + 1;
+    let mut totient = vec![0u64; size];
+    let mut primes: Vec<usize> = Vec::new();
+    if size > 1 {
+        totient[1] = 1;
+    }
+    for i in 2..size {
+        if totient[i] == 0 {
+            primes.push(i);
+            totient[i] = i as u64 - 1;
+        }
+        for &p in primes.iter() {
+            let m = i * p;
+            if m >= size {
+                break;
+            }
+            if i.is_multiple_of(p) {
+                totient[m] = totient[i] * p as u64;
+                break;
+            }
+            totient[m] = totient[i] * (p as u64 - 1);
+        }
+    }
+    totient
+}
+
+fn _sieve_segment_with(low: u64, high: u64, base_primes: &[u32]) -> MultiplicativeTables {
+    /*
+    every value starts as the empty product and each base prime p is divided out
+    of its multiples, whatever remains above 1 is one prime > sqrt(high)
+    */
+    let len = (high - low + 1) as usize;
+    let mut t = MultiplicativeTables::zeroed(low, len);
+    let mut remaining: Vec<u64> = (low..=high).collect();
+    for i in 0..len {
+        t.mobius[i] = 1;
+        t.totient[i] = 1;
+        t.divisor_count[i] = 1;
+        t.divisor_sum[i] = 1;
+    }
+
+    for &p in base_primes {
+        let p = p as u64;
+        if p * p > high {
+            break;
+        }
+        let mut multiple = low.div_ceil(p).max(1) * p;
+        while multiple <= high {
+            let i = (multiple - low) as usize;
+            let mut exp = 0;
+            let mut pk = 1;
+            while remaining[i].is_multiple_of(p) {
+                remaining[i] /= p;
+                pk *= p;
+                exp += 1;
+            }
+            t.mobius[i] = if exp > 1 { 0 } else { -t.mobius[i] };
+            t.totient[i] *= pk / p * (p - 1);
+            t.divisor_count[i] *= exp + 1;
+            t.divisor_sum[i] *= (pk * p - 1) / (p - 1);
+            t.omega[i] += 1;
+            t.big_omega[i] += exp as u8;
+            multiple += p;
+        }
+    }
+
+    for (i, rest) in remaining.into_iter().enumerate() {
+        if rest > 1 {
+            t.mobius[i] = -t.mobius[i];
+            t.totient[i] *= rest - 1;
+            t.divisor_count[i] *= 2;
+            t.divisor_sum[i] *= rest + 1;
+            t.omega[i] += 1;
+            t.big_omega[i] += 1;
+        }
+        t.liouville[i] = if t.big_omega[i].is_multiple_of(2) {
+            1
+        } else {
+            -1
+        };
+    }
+    if low == 0 {
+        // 0 is the one value that is not the empty product
+        t.mobius[0] = 0;
+        t.totient[0] = 0;
+        t.divisor_count[0] = 0;
+        t.divisor_sum[0] = 0;
+        t.liouville[0] = 0;
+    }
+    t
+}
+
+fn _base_primes(high: u64) -> Vec<u32> {
+    SpfTable::new(isqrt_u64(high).max(2)).primes().to_vec()
+}
+
+pub fn sieve_segment(low: u64, high: u64) -> MultiplicativeTables {
+    // the same tables for [low, high] only
+    if low > high {
+        panic!("low > high");
+    }
+    _sieve_segment_with(low, high, &_base_primes(high))
+}
+
+pub fn par_for_each_segment<F>(a: u64, b: u64, f: F)
+where
+    F: Fn(&MultiplicativeTables) + Sync + Send,
+{
+    /*
+    sieves [a, b] in segments of 2^16 on the large pool
+    f gets every segment once, in no particular order
+    */
+    if a > b {
+        panic!("a > b");
+    }
+    let base_primes = _base_primes(b);
+    let mut segments = Vec::new();
+    let mut low = a;
+    while low <= b {
+        let high = b.min(low.saturating_add(SEGMENT_SIZE - 1));
+        segments.push((low, high));
+        if high == u64::MAX {
+            break;
+        }
+        low = high + 1;
+    }
+
+    let pool = get_large_pool();
+    pool.install(|| {
+        segments
+            .par_iter()
+            .for_each(|(low, high)| f(&_sieve_segment_with(*low, *high, &base_primes)))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::spf_sieve::{
+        divisor_count_range, divisor_sum_range, mobius_range, totient_range,
+    };
+    use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+    #[test]
+    fn test_linear_sieve_small() {
+        let t = linear_sieve(12);
+        assert_eq!(t.len(), 13);
+        assert_eq!(t.mobius()[1..], [1, -1, -1, 0, -1, 1, -1, 0, 0, 1, -1, 0]);
+        assert_eq!(t.totient()[1..], [1, 1, 2, 2, 4, 2, 6, 4, 6, 4, 10, 4]);
+        assert_eq!(t.divisor_count()[1..], [1, 2, 2, 3, 2, 4, 2, 4, 3, 4, 2, 6]);
+        assert_eq!(
+            t.divisor_sum()[1..],
+            [1, 3, 4, 7, 6, 12, 8, 15, 13, 18, 12, 28]
+        );
+        assert_eq!(t.omega()[1..], [0, 1, 1, 1, 1, 2, 1, 1, 1, 2, 1, 2]);
+        assert_eq!(t.big_omega()[1..], [0, 1, 1, 2, 1, 2, 1, 3, 2, 2, 1, 3]);
+        assert_eq!(
+            t.liouville()[1..],
+            [1, -1, -1, 1, -1, 1, -1, -1, 1, 1, -1, -1]
+        );
+        assert_eq!(t.mobius()[0], 0);
+    }
+
+    #[test]
+    fn test_linear_sieve_matches_spf_ranges() {
+        let n = 200_000;
+        let t = linear_sieve(n);
+        assert_eq!(t.mobius()[1..], mobius_range(1, n)[..]);
+        assert_eq!(t.totient()[1..], totient_range(1, n)[..]);
+        let counts: Vec<u32> = divisor_count_range(1, n)
+            .iter()
+            .map(|v| *v as u32)
+            .collect();
+        assert_eq!(t.divisor_count()[1..], counts[..]);
+        let sums: Vec<u64> = divisor_sum_range(1, n).iter().map(|v| *v as u64).collect();
+        assert_eq!(t.divisor_sum()[1..], sums[..]);
+        assert_eq!(mobius_table(n), t.mobius());
+        assert_eq!(totient_table(n), t.totient());
+    }
+
+    #[test]
+    fn test_sieve_segment_matches_linear_sieve() {
+        let t = linear_sieve(300_000);
+        for (low, high) in [(0u64, 1000u64), (1, 70_000), (123_457, 300_000)] {
+            let segment = sieve_segment(low, high);
+            let range = low as usize..=high as usize;
+            assert_eq!(segment.start(), low);
+            assert_eq!(segment.end(), high);
+            assert_eq!(segment.mobius(), &t.mobius()[range.clone()]);
+            assert_eq!(segment.totient(), &t.totient()[range.clone()]);
+            assert_eq!(segment.divisor_count(), &t.divisor_count()[range.clone()]);
+            assert_eq!(segment.divisor_sum(), &t.divisor_sum()[range.clone()]);
+            assert_eq!(segment.omega(), &t.omega()[range.clone()]);
+            assert_eq!(segment.big_omega(), &t.big_omega()[range.clone()]);
+            assert_eq!(segment.liouville(), &t.liouville()[range]);
+        }
+    }
+
+    #[test]
+    fn test_par_for_each_segment() {
+        // M(10^6) = 212, sum of φ up to 10^6 = 303963552392
+        let mertens = AtomicI64::new(0);
+        let totient_sum = AtomicU64::new(0);
+        par_for_each_segment(1, 1_000_000, |segment| {
+            let m: i64 = segment.mobius().iter().map(|v| *v as i64).sum();
+            mertens.fetch_add(m, Ordering::Relaxed);
+            totient_sum.fetch_add(segment.totient().iter().sum(), Ordering::Relaxed);
+        });
+        assert_eq!(mertens.into_inner(), 212);
+        assert_eq!(totient_sum.into_inner(), 303_963_552_392);
+    }
+
+    #[test]
+    fn test_segment_near_10_pow_10() {
+        let low = 10_000_000_000u64 - 500;
+        let segment = sieve_segment(low, low + 1000);
+        // 10^10 = 2^10 5^10
+        let i = 500;
+        assert_eq!(segment.mobius()[i], 0);
+        assert_eq!(segment.totient()[i], 4_000_000_000);
+        assert_eq!(segment.divisor_count()[i], 121);
+        assert_eq!(segment.omega()[i], 2);
+        assert_eq!(segment.big_omega()[i], 20);
+        assert_eq!(segment.liouville()[i], 1);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let t = sieve_segment(1000, 5000);
+        let path = std::env::temp_dir().join(format!("linear_sieve_{}.bin", std::process::id()));
+        t.save(&path).unwrap();
+        let loaded = MultiplicativeTables::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, t);
+    }
+}
@@ -18,19 +18,23 @@ pub fn get_miller_rabin_bases(n: &Integer) -> &'static [u32] {
     match n.cmp0() {
         std::cmp::Ordering::Less => &EMPTY_BASES,
         // 2^18 - 1 = 262143
-        _ => match n.to_u64().unwrap_or(u64::MAX) {
-            0..=2046 => &BASES_2,
-            2047..=1373652 => &BASES_2_3,
-            1373653..=9080190 => &BASES_31_73,
-            9080191..=25326000 => &BASES_2_3_5,
-            25326001..=3215031750 => &BASES_2_3_5_7,
-            3215031751..=4759123140 => &BASES_2_7_61,
-            4759123141..=1122004669632 => &BASES_2_13_23_1662803,
-            1122004669633..=2152302898746 => &BASES_2_3_5_7_11,
-            2152302898747..=3474749660382 => &BASES_2_3_5_7_11_13,
-            3474749660383..=341550071728320 => &BASES_2_3_5_7_11_13_17,
-            341550071728321..=3825123056546413050 => &BASES_2_3_5_7_11_13_17_19_23,
-            _ => &BASES_ALL,
-        },
+        _ => get_miller_rabin_bases_u64(n.to_u64().unwrap_or(u64::MAX)),
+    }
+}
+
+pub fn get_miller_rabin_bases_u64(n: u64) -> &'static [u32] {
+    match n {
+        0..=2046 => &BASES_2,
+        2047..=1373652 => &BASES_2_3,
+        1373653..=9080190 => &BASES_31_73,
+        9080191..=25326000 => &BASES_2_3_5,
+        25326001..=3215031750 => &BASES_2_3_5_7,
+        3215031751..=4759123140 => &BASES_2_7_61,
+        4759123141..=1122004669632 => &BASES_2_13_23_1662803,
+        1122004669633..=2152302898746 => &BASES_2_3_5_7_11,
+        2152302898747..=3474749660382 => &BASES_2_3_5_7_11_13,
+        3474749660383..=341550071728320 => &BASES_2_3_5_7_11_13_17,
+        341550071728321..=3825123056546413050 => &BASES_2_3_5_7_11_13_17_19_23,
+        _ => &BASES_ALL,
     }
 }
@@ -1,16 +1,41 @@
+pub mod arithmetic_function;
+pub mod batch_gcd;
 pub mod bitscan;
 pub mod chineese_remainder_theorem;
 pub mod collatz;
 pub mod common;
+pub mod cunningham;
+pub mod discrete_log;
+pub mod factor_u64;
+pub mod factorization;
 pub mod fib_calc;
+pub mod finite_field;
+pub mod gcd;
+pub mod index_calculus;
 pub mod inneficient;
+pub mod int_ring;
+pub mod linear_congruence;
+pub mod linear_sieve;
 pub mod macros;
 pub mod miller_rabin_bases;
+pub mod modint;
+pub mod nthroot_mod;
 pub mod num_utils;
 pub mod padic;
+pub mod partition;
+pub mod perfect_power;
+pub mod poly;
+pub mod poly_congruence;
 pub mod primes;
+pub mod primitive_root;
+pub mod quadratic_residue;
+pub mod ring;
+pub mod spf_sieve;
+pub mod sqrt_mod;
 pub mod static_data;
+pub mod summatory;
 pub mod threading;
+pub mod totient;
 pub mod traits;
 pub mod witness_accuracy;
 pub use primes::*;
@@ -0,0 +1,350 @@
+/*
+integers mod n sharing one context
+
+a ModContext fixes the modulus, ModInt is a residue in [0, n) plus an Arc of its
+context, the std ops work on values and references, mixing two moduli panics
+like a shape mismatch would
+
+products are reduced with gmp's division and powers go to mpz_powm. montgomery
+(REDC) and barrett reduction were tried here first, benches/modint keeps both
+and compares them from 64 to 2048 bits. on top of rug every extra multiplication
+is a full bignum one: mpz_powm is 3 to 20 times faster than either for powers,
+and for chains of products gmp wins up to 1024 bits and montgomery only draws
+level at 2048, not enough to pay for converting in and out
+*/
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::sync::Arc;
+
+use rug::{Complete, Integer};
+
+use super::num_utils::pow_mod;
+use super::sqrt_mod::sqrt_mod;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ModContext {
+    modulus: Integer,
+}
+
+impl ModContext {
+    pub fn new(modulus: &Integer) -> Result<ModContext, String> {
+        if *modulus <= 0 {
+            return Err("n must be > 0".to_string());
+        }
+        Ok(ModContext {
+            modulus: modulus.clone(),
+        })
+    }
+
+    pub fn modulus(&self) -> &Integer {
+        &self.modulus
+    }
+
+    fn _reduce(&self, value: Integer) -> Integer {
+        value.modulo(&self.modulus)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModInt {
+    // the residue in [0, n)
+    value: Integer,
+    context: Arc<ModContext>,
+}
+
+impl ModInt {
+    pub fn new(value: &Integer, context: &Arc<ModContext>) -> ModInt {
+        ModInt {
+            value: context._reduce(value.clone()),
+            context: Arc::clone(context),
+        }
+    }
+
+    pub fn zero(context: &Arc<ModContext>) -> ModInt {
+        ModInt {
+            value: Integer::new(),
+            context: Arc::clone(context),
+        }
+    }
+
+    pub fn one(context: &Arc<ModContext>) -> ModInt {
+        ModInt {
+            value: Integer::from(1) % &context.modulus,
+            context: Arc::clone(context),
+        }
+    }
+
+    pub fn value(&self) -> Integer {
+        // the residue in [0, n)
+        self.value.clone()
+    }
+
+    pub fn modulus(&self) -> &Integer {
+        &self.context.modulus
+    }
+
+    pub fn context(&self) -> &Arc<ModContext> {
+        &self.context
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.value == 0
+    }
+
+    fn _with_value(&self, value: Integer) -> ModInt {
+        ModInt {
+            value,
+            context: Arc::clone(&self.context),
+        }
+    }
+
+    fn _check_context(&self, other: &ModInt) {
+        /* equal contexts built apart are fine */
+        if !Arc::ptr_eq(&self.context, &other.context) && self.context != other.context {
+            panic!(
+                "ModInt values from different contexts: mod {} and mod {}",
+                self.context.modulus, other.context.modulus
+            );
+        }
+    }
+
+    pub fn inverse(&self) -> Option<ModInt> {
+        let inverse = self.value.clone().invert(&self.context.modulus).ok()?;
+        Some(ModInt::new(&inverse, &self.context))
+    }
+
+    pub fn pow(&self, exponent: &Integer) -> ModInt {
+        // through gmp, a negative exponent needs a unit
+        if *exponent < 0 && self.inverse().is_none() {
+            panic!("{} is not invertible mod {}", self.value, self.modulus());
+        }
+        self._with_value(pow_mod(&self.value, exponent, &self.context.modulus))
+    }
+
+    pub fn sqrt(&self) -> Option<ModInt> {
+        // the smallest square root, None when there is none
+        let roots = sqrt_mod(&self.value, &self.context.modulus, false).ok()?;
+        roots.first().map(|root| ModInt::new(root, &self.context))
+    }
+}
+
+impl PartialEq for ModInt {
+    fn eq(&self, other: &ModInt) -> bool {
+        self.context.modulus == other.context.modulus && self.value == other.value
+    }
+}
+
+impl Eq for ModInt {}
+
+impl fmt::Display for ModInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+fn _add(a: &ModInt, b: &ModInt) -> ModInt {
+    a._check_context(b);
+    let mut value = (&a.value + &b.value).complete();
+    if value >= a.context.modulus {
+        value -= &a.context.modulus;
+    }
+    a._with_value(value)
+}
+
+fn _sub(a: &ModInt, b: &ModInt) -> ModInt {
+    a._check_context(b);
+    let mut value = (&a.value - &b.value).complete();
+    if value < 0 {
+        value += &a.context.modulus;
+    }
+    a._with_value(value)
+}
+
+fn _mul(a: &ModInt, b: &ModInt) -> ModInt {
+    a._check_context(b);
+    a._with_value(a.context._reduce((&a.value * &b.value).complete()))
+}
+
+fn _div(a: &ModInt, b: &ModInt) -> ModInt {
+    match b.inverse() {
+        Some(inverse) => _mul(a, &inverse),
+        None => panic!("{} is not invertible mod {}", b.value(), b.modulus()),
+    }
+}
+
+macro_rules! modint_binary_op {
+    /*
+    Trait, method, assign trait, assign method and the function doing the work,
+    gives the op for every mix of values and references plus the assign op
+    */
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $op:ident) => {
+        impl $trait<&ModInt> for &ModInt {
+            type Output = ModInt;
+            fn $method(self, other: &ModInt) -> ModInt {
+                $op(self, other)
+            }
+        }
+
+        impl $trait<ModInt> for &ModInt {
+            type Output = ModInt;
+            fn $method(self, other: ModInt) -> ModInt {
+                $op(self, &other)
+            }
+        }
+
+        impl $trait<&ModInt> for ModInt {
+            type Output = ModInt;
+            fn $method(self, other: &ModInt) -> ModInt {
+                $op(&self, other)
+            }
+        }
+
+        impl $trait<ModInt> for ModInt {
+            type Output = ModInt;
+            fn $method(self, other: ModInt) -> ModInt {
+                $op(&self, &other)
+            }
+        }
+
+        impl $assign_trait<&ModInt> for ModInt {
+            fn $assign_method(&mut self, other: &ModInt) {
+                *self = $op(self, other);
+            }
+        }
+
+        impl $assign_trait<ModInt> for ModInt {
+            fn $assign_method(&mut self, other: ModInt) {
+                *self = $op(self, &other);
+            }
+        }
+    };
+}
+
+modint_binary_op!(Add, add, AddAssign, add_assign, _add);
+modint_binary_op!(Sub, sub, SubAssign, sub_assign, _sub);
+modint_binary_op!(Mul, mul, MulAssign, mul_assign, _mul);
+modint_binary_op!(Div, div, DivAssign, div_assign, _div);
+
+impl Neg for &ModInt {
+    type Output = ModInt;
+    fn neg(self) -> ModInt {
+        _sub(&ModInt::zero(&self.context), self)
+    }
+}
+
+impl Neg for ModInt {
+    type Output = ModInt;
+    fn neg(self) -> ModInt {
+        -&self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic_matches_integer() {
+        for n in 1..60u32 {
+            let n = Integer::from(n);
+            let context = Arc::new(ModContext::new(&n).unwrap());
+            for a in -3..n.to_i32().unwrap() {
+                for b in 0..n.to_i32().unwrap() {
+                    let (a_int, b_int) = (Integer::from(a), Integer::from(b));
+                    let x = ModInt::new(&a_int, &context);
+                    let y = ModInt::new(&b_int, &context);
+                    assert_eq!((&x + &y).value(), (&a_int + &b_int).complete().modulo(&n));
+                    assert_eq!((&x - &y).value(), (&a_int - &b_int).complete().modulo(&n));
+                    assert_eq!((&x * &y).value(), (&a_int * &b_int).complete().modulo(&n));
+                    assert_eq!((-&x).value(), (-a_int.clone()).modulo(&n));
+                    if let Some(inverse) = y.inverse() {
+                        assert_eq!((&x / &y) * &y, x);
+                        assert_eq!((&inverse * &y).value(), Integer::from(1) % &n);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_modulus() {
+        // a 521 bit mersenne prime and an even multiple, against gmp
+        let p = (Integer::from(1) << 521) - 1u32;
+        let even = Integer::from(&p * 6u32);
+        let a = Integer::from(3).pow_mod(&Integer::from(1000), &p).unwrap();
+        let b = Integer::from(7).pow_mod(&Integer::from(999), &p).unwrap();
+        for n in [&p, &even] {
+            let context = Arc::new(ModContext::new(n).unwrap());
+            let x = ModInt::new(&a, &context);
+            let y = ModInt::new(&b, &context);
+            assert_eq!((&x * &y).value(), (&a * &b).complete().modulo(n));
+            let e = Integer::from(12_345_678_901u64);
+            assert_eq!(x.pow(&e).value(), a.clone().pow_mod(&e, n).unwrap());
+            let mut z = x.clone();
+            z *= &y;
+            z += &x;
+            z -= y.clone();
+            assert_eq!(z.value(), (Integer::from(&a * &b) + &a - &b).modulo(n));
+        }
+        // fermat through the shared layer
+        let context = Arc::new(ModContext::new(&p).unwrap());
+        let p_minus_1 = Integer::from(&p - 1u32);
+        for base in 2..20u32 {
+            assert_eq!(
+                ModInt::new(&Integer::from(base), &context).pow(&p_minus_1),
+                ModInt::one(&context)
+            );
+        }
+    }
+
+    #[test]
+    fn test_pow_inverse_sqrt() {
+        let n = Integer::from(1_000_000_007u64);
+        let context = Arc::new(ModContext::new(&n).unwrap());
+        let x = ModInt::new(&Integer::from(5), &context);
+        assert_eq!(x.pow(&Integer::from(0)), ModInt::one(&context));
+        assert_eq!(x.pow(&Integer::from(-1)), x.inverse().unwrap());
+        assert_eq!(
+            x.pow(&Integer::from(-3)) * x.pow(&Integer::from(3)),
+            ModInt::one(&context)
+        );
+        let square = &x * &x;
+        let root = square.sqrt().unwrap();
+        assert_eq!(&root * &root, square);
+        // 5 is not a square mod 1e9 + 7
+        assert_eq!(x.sqrt(), None);
+
+        let context = Arc::new(ModContext::new(&Integer::from(12)).unwrap());
+        let six = ModInt::new(&Integer::from(6), &context);
+        assert_eq!(six.inverse(), None);
+        assert!(six.pow(&Integer::from(2)).is_zero());
+        assert_eq!(
+            ModInt::new(&Integer::from(4), &context)
+                .sqrt()
+                .unwrap()
+                .value(),
+            2
+        );
+        assert_eq!(
+            format!("{}", ModInt::new(&Integer::from(-1), &context)),
+            "11"
+        );
+
+        assert!(ModContext::new(&Integer::from(0)).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "different contexts")]
+    fn test_mixed_moduli() {
+        let a = Arc::new(ModContext::new(&Integer::from(7)).unwrap());
+        let b = Arc::new(ModContext::new(&Integer::from(11)).unwrap());
+        let _ = ModInt::one(&a) + ModInt::one(&b);
+    }
+
+    #[test]
+    #[should_panic(expected = "not invertible")]
+    fn test_division_by_non_unit() {
+        let context = Arc::new(ModContext::new(&Integer::from(9)).unwrap());
+        let _ = ModInt::one(&context) / ModInt::new(&Integer::from(3), &context);
+    }
+}
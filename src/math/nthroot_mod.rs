@@ -0,0 +1,492 @@
+/*
+k-th roots mod n, every x with x^k = a mod n
+
+prime p, a a unit: with d = gcd(k, p - 1) and s k = d mod p - 1, x^k = a is the
+same as x^d = a^s once a^((p - 1) / d) = 1. x^d is taken one prime r of d at a
+time with Adleman-Manders-Miller, p - 1 = r^t s', rho a non r-th power:
+    delta^alpha with r alpha = 1 mod s' is a root up to an element of order r^(t - 1),
+    that error is walked down one power of r at a time with logs in the order r
+    group generated by rho^(r^(t - 1) s')
+when d has a repeated prime, the r-th root has to be picked among its r conjugates
+so it stays a power of what is left of d
+every root is x0 times a d-th root of unity
+
+prime powers: a = p^v u, roots are p^(v / k) y with y^k = u mod p^(e - v), which
+needs k | v, y free mod p^(e - v / k). the roots mod p are lifted with newton when
+p does not divide k. when p^g || k the roots mod p^i are whole classes mod
+p^(i - g), they are kept as classes and each one has a single lift per power
+
+composite n: every combination of the roots mod the prime powers glued with the CRT,
+as residue classes so a single root costs no more than the number of classes
+*/
+use rug::ops::Pow;
+use rug::Integer;
+
+use super::chineese_remainder_theorem::{expand_classes, CrtContext};
+use super::discrete_log::baby_step_giant_step;
+use super::factorization::{factorint, is_probable_prime};
+use super::gcd::extended_gcd;
+use super::num_utils::pow_mod;
+
+fn _is_power_residue(z: &Integer, m: &Integer, p: &Integer) -> bool {
+    // z an m-th power mod p, m | p - 1
+    let exponent = Integer::from(p - 1u32) / m;
+    pow_mod(z, &exponent, p) == 1
+}
+
+fn _amm(delta: &Integer, r: &Integer, p: &Integer) -> Option<(Integer, Integer)> {
+    // an r-th root of delta and a primitive r-th root of unity, r a prime dividing p - 1
+    if !_is_power_residue(delta, r, p) {
+        return None;
+    }
+    let mut s = Integer::from(p - 1u32);
+    let t = s.remove_factor_mut(r);
+
+    let mut rho = Integer::from(2);
+    while _is_power_residue(&rho, r, p) {
+        rho += 1;
+    }
+    let r_t_1 = r.clone().pow(t - 1);
+    let unity = pow_mod(&rho, &Integer::from(&r_t_1 * &s), p);
+
+    /* r alpha = 1 mod s, alpha = 0 when s = 1 */
+    let alpha = if s == 1 {
+        Integer::new()
+    } else {
+        r.clone().invert(&s).unwrap()
+    };
+    let mut b = pow_mod(delta, &(Integer::from(r * &alpha) - 1u32), p);
+    let mut c = pow_mod(&rho, &s, p);
+    let mut h = Integer::from(1);
+    for i in 1..t {
+        let d = pow_mod(&b, &r.clone().pow(t - 1 - i), p);
+        let j = if d == 1 {
+            Integer::new()
+        } else {
+            let log = baby_step_giant_step(&d, &unity, p, r)?;
+            (r - log).modulo(r)
+        };
+        let c_r = pow_mod(&c, r, p);
+        b = (b * pow_mod(&c_r, &j, p)).modulo(p);
+        h = (h * pow_mod(&c, &j, p)).modulo(p);
+        c = c_r;
+    }
+    let root = (pow_mod(delta, &alpha, p) * h).modulo(p);
+    Some((root, unity))
+}
+
+pub fn adleman_manders_miller(delta: &Integer, r: &Integer, p: &Integer) -> Option<Integer> {
+    // some x with x^r = delta mod p for a prime r | p - 1, None when there is none
+    let delta = delta.clone().modulo(p);
+    if delta == 0 {
+        return Some(delta);
+    }
+    _amm(&delta, r, p).map(|(root, _)| root)
+}
+
+pub fn nthroot_mod_prime(a: &Integer, k: u64, p: &Integer) -> Vec<Integer> {
+    // every root of x^k = a mod the prime p sorted
+    let a = a.clone().modulo(p);
+    if a == 0 {
+        return vec![a];
+    }
+    let p_minus_1 = Integer::from(p - 1u32);
+    let (d, s, _) = extended_gcd(&Integer::from(k), &p_minus_1);
+    if !_is_power_residue(&a, &d, p) {
+        return Vec::new();
+    }
+    /* x^d = a^s, one prime of d at a time */
+    let mut x = pow_mod(&a, &s, p);
+    if d == 1 {
+        return vec![x];
+    }
+    let primes = factorint(&d);
+    let mut remaining = d.clone();
+    for (r, e) in primes.factors().iter() {
+        for _ in 0..*e {
+            remaining /= r;
+            let (root, unity) = _amm(&x, r, p).unwrap();
+            let mut candidate = root;
+            while !_is_power_residue(&candidate, &remaining, p) {
+                candidate = (candidate * &unity).modulo(p);
+            }
+            x = candidate;
+        }
+    }
+
+    /*
+    the others differ by a d-th root of unity, h^((p - 1) / d) has order d unless
+    its (d / r)-th power is 1 for a prime r | d, so only d gets factored
+    */
+    let cofactor = Integer::from(&p_minus_1 / &d);
+    let mut h = Integer::from(2);
+    let zeta = loop {
+        let zeta = pow_mod(&h, &cofactor, p);
+        let primitive = primes
+            .factors()
+            .keys()
+            .all(|r| pow_mod(&zeta, &Integer::from(&d / r), p) != 1);
+        if primitive {
+            break zeta;
+        }
+        h += 1;
+    };
+    let mut roots = Vec::new();
+    let mut current = x;
+    let mut i = Integer::new();
+    while i < d {
+        roots.push(current.clone());
+        current = (current * &zeta).modulo(p);
+        i += 1;
+    }
+    roots.sort();
+    roots
+}
+
+fn _lift_unit_roots(
+    roots: Vec<Integer>,
+    u: &Integer,
+    k: u64,
+    p: &Integer,
+    f: u32,
+) -> (Vec<Integer>, u32) {
+    /*
+    roots of y^k = u mod p^f, u a unit, from the roots mod p, as classes mod p^c
+    with c the second value
+    */
+    let k_int = Integer::from(k);
+    if !k_int.is_divisible(p) {
+        /* newton, every root mod p has exactly one lift */
+        let lifted = roots
+            .into_iter()
+            .map(|mut y| {
+                let mut precision = 1u32;
+                while precision < f {
+                    precision = (2 * precision).min(f);
+                    let modulus = p.clone().pow(precision);
+                    let power = pow_mod(&y, &Integer::from(k - 1), &modulus);
+                    let derivative = Integer::from(&power * &k_int).invert(&modulus).unwrap();
+                    let error = Integer::from(&power * &y) - u;
+                    y = (&y - error * derivative).modulo(&modulus);
+                }
+                y
+            })
+            .collect();
+        return (lifted, f);
+    }
+    /*
+    p | k, k = p^g k': (y + t p^c)^k = y^k + k y^(k - 1) t p^c mod p^(c + g + 1)
+    for c >= 1, c >= 2 when p = 2, so y^k mod p^(c + g) only depends on y mod p^c
+    the roots mod p^i are classes mod p^(i - g), each with one lift
+        t = (u - y^k) / p^i (k' y^(k - 1))^-1 mod p
+    */
+    let mut k_prime = k_int.clone();
+    let g = k_prime.remove_factor_mut(p);
+    let (mut current, mut c) = if *p == 2 {
+        let c = f.min(2);
+        let units = (1..1u32 << c).step_by(2).map(Integer::from).collect();
+        (units, c)
+    } else {
+        (roots, 1)
+    };
+    let level = f.min(c + g);
+    let modulus = p.clone().pow(level);
+    let target = u.clone().modulo(&modulus);
+    current.retain(|y| pow_mod(y, &k_int, &modulus) == target);
+    for i in level..f {
+        let p_i = p.clone().pow(i);
+        let next_modulus = Integer::from(&p_i * p);
+        let p_c = p.clone().pow(c);
+        for y in current.iter_mut() {
+            let power = pow_mod(y, &Integer::from(k - 1), &next_modulus);
+            let error = (u - Integer::from(&power * &*y)).modulo(&next_modulus) / &p_i;
+            let slope = Integer::from(&k_prime * &power).invert(p).unwrap();
+            let t = (error * slope).modulo(p);
+            *y += t * &p_c;
+        }
+        c += 1;
+    }
+    (current, c)
+}
+
+fn _root_classes(a: &Integer, k: u64, p: &Integer, e: u32) -> (Vec<Integer>, Integer) {
+    /*
+    the roots of x^k = a mod p^e as sorted residue classes mod m, m a divisor of p^e
+    a = 0: the multiples of p^ceil(e / k)
+    a = p^v u: p^w y with y^k = u mod p^f, f = e - v, w = v / k,
+    y is free mod p^(e - w) so p^w y is free mod m = p^(f + w)
+    */
+    let pe = p.clone().pow(e);
+    let mut u = a.clone().modulo(&pe);
+    if u == 0 {
+        let w = (e as u64).div_ceil(k) as u32;
+        return (vec![Integer::new()], p.clone().pow(w));
+    }
+    let v = u.remove_factor_mut(p);
+    if !(v as u64).is_multiple_of(k) {
+        return (Vec::new(), pe);
+    }
+    let w = (v as u64 / k) as u32;
+    let f = e - v;
+    let base = if *p == 2 {
+        /* the only unit mod 2 */
+        vec![Integer::from(1)]
+    } else {
+        nthroot_mod_prime(&u, k, p)
+    };
+    let scale = p.clone().pow(w);
+    let (lifted, c) = _lift_unit_roots(base, &u, k, p, f);
+    let mut classes: Vec<Integer> = lifted.into_iter().map(|y| y * &scale).collect();
+    classes.sort();
+    classes.dedup();
+    (classes, p.clone().pow(c + w))
+}
+
+pub fn nthroot_mod_prime_power(
+    a: &Integer,
+    k: u64,
+    p: &Integer,
+    e: u32,
+    all_roots: bool,
+) -> Vec<Integer> {
+    // every root of x^k = a mod p^e sorted, with all_roots false just the smallest one
+    let (classes, m) = _root_classes(a, k, p, e);
+    if !all_roots {
+        return classes.into_iter().take(1).collect();
+    }
+    expand_classes(&classes, &m, &p.clone().pow(e))
+}
+
+pub fn nthroot_mod(
+    a: &Integer,
+    k: u64,
+    n: &Integer,
+    all_roots: bool,
+) -> Result<Vec<Integer>, String> {
+    /*
+    every x with x^k = a mod n sorted, empty when there is none,
+    with all_roots false just the smallest one, the classes of the prime
+    powers are glued before expanding like in sqrt_mod
+    */
+    if k == 0 {
+        return Err("k should be positive".to_string());
+    }
+    if *n <= 0 {
+        return Err("n must be > 0".to_string());
+    }
+    let a = a.clone().modulo(n);
+    if *n == 1 {
+        return Ok(vec![Integer::new()]);
+    }
+    if is_probable_prime(n) {
+        let mut roots = nthroot_mod_prime(&a, k, n);
+        if !all_roots {
+            roots.truncate(1);
+        }
+        return Ok(roots);
+    }
+    let mut local = Vec::new();
+    let mut moduli = Vec::new();
+    for (p, e) in factorint(n).factors().iter() {
+        let (classes, m) = _root_classes(&a, k, p, *e);
+        if classes.is_empty() {
+            return Ok(Vec::new());
+        }
+        local.push(classes);
+        moduli.push(m);
+    }
+    let context = CrtContext::new(&moduli)?;
+    let mut classes = context.combinations(&local)?;
+    if !all_roots {
+        classes.truncate(1);
+        return Ok(classes);
+    }
+    Ok(expand_classes(&classes, context.modulus(), n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_roots(a: u64, k: u64, n: u64) -> Vec<Integer> {
+        let n_int = Integer::from(n);
+        (0..n)
+            .filter(|x| pow_mod(&Integer::from(*x), &Integer::from(k), &n_int) == a % n)
+            .map(Integer::from)
+            .collect()
+    }
+
+    #[test]
+    fn test_adleman_manders_miller() {
+        // r^t || p - 1 with t > 1 exercises the walk
+        for (r, p) in [
+            (3u64, 109u64),
+            (3, 163),
+            (5, 101),
+            (5, 251),
+            (2, 97),
+            (7, 4_733),
+        ] {
+            let (r_int, p_int) = (Integer::from(r), Integer::from(p));
+            for a in 1..p {
+                let a_int = Integer::from(a);
+                let expected = !brute_roots(a, r, p).is_empty();
+                let root = adleman_manders_miller(&a_int, &r_int, &p_int);
+                assert_eq!(root.is_some(), expected, "a = {}, r = {}, p = {}", a, r, p);
+                if let Some(root) = root {
+                    assert_eq!(pow_mod(&root, &r_int, &p_int), a_int);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_nthroot_mod() {
+        let roots = |a: u64, k: u64, n: u64| {
+            nthroot_mod(&Integer::from(a), k, &Integer::from(n), true).unwrap()
+        };
+        assert_eq!(roots(11, 4, 19), vec![8, 11]);
+        assert_eq!(roots(68, 3, 109), vec![23, 32, 54]);
+        assert_eq!(roots(8, 3, 27), vec![2, 11, 20]);
+        assert_eq!(roots(0, 3, 8), vec![0, 2, 4, 6]);
+        assert_eq!(roots(1, 2, 1), vec![0]);
+        assert_eq!(roots(3, 3, 7), Vec::<Integer>::new());
+        assert_eq!(roots(5, 1, 7), vec![5]);
+        assert_eq!(roots(16, 4, 64), brute_roots(16, 4, 64));
+        assert!(nthroot_mod(&Integer::from(3), 0, &Integer::from(7), true).is_err());
+        assert!(nthroot_mod(&Integer::from(3), 2, &Integer::from(0), true).is_err());
+    }
+
+    #[test]
+    fn test_nthroot_mod_matches_brute_force() {
+        for n in 1..130u64 {
+            for k in 1..7u64 {
+                for a in 0..n {
+                    let expected = brute_roots(a, k, n);
+                    let got = nthroot_mod(&Integer::from(a), k, &Integer::from(n), true).unwrap();
+                    assert_eq!(got, expected, "a = {}, k = {}, n = {}", a, k, n);
+                    let smallest =
+                        nthroot_mod(&Integer::from(a), k, &Integer::from(n), false).unwrap();
+                    assert_eq!(smallest, expected.into_iter().take(1).collect::<Vec<_>>());
+                }
+            }
+        }
+        // p | k with higher powers of p
+        for (k, n) in [
+            (9u64, 243u64),
+            (4, 256),
+            (25, 625),
+            (6, 1296),
+            (8, 512),
+            (18, 729),
+            (10, 500),
+        ] {
+            for a in 0..n {
+                let got = nthroot_mod(&Integer::from(a), k, &Integer::from(n), true).unwrap();
+                assert_eq!(got, brute_roots(a, k, n), "a = {}, k = {}, n = {}", a, k, n);
+            }
+        }
+    }
+
+    #[test]
+    fn test_nthroot_mod_large() {
+        // p - 1 = 2^3 * 3^2 * ... and a cube root mod a 64 bit prime
+        let p = Integer::from(18_446_744_073_709_551_557u64);
+        let x = Integer::from(1_234_567_890_123u64);
+        for k in [2u64, 3, 11, 22, 137] {
+            let a = pow_mod(&x, &Integer::from(k), &p);
+            let roots = nthroot_mod(&a, k, &p, true).unwrap();
+            assert!(roots.contains(&x));
+            let d = Integer::from(k).gcd(&Integer::from(&p - 1u32));
+            assert_eq!(Integer::from(roots.len()), d);
+            for root in roots.iter() {
+                assert_eq!(pow_mod(root, &Integer::from(k), &p), a);
+            }
+        }
+        // and through a prime power
+        let q = Integer::from(1_000_003u64);
+        let n = q.clone().pow(3);
+        let a = pow_mod(&x, &Integer::from(3), &n);
+        let roots = nthroot_mod(&a, 3, &n, true).unwrap();
+        assert!(roots.contains(&x.clone().modulo(&n)));
+    }
+
+    #[test]
+    fn test_nthroot_mod_without_factoring_p_minus_1() {
+        // p - 1 has two 90 bit prime factors and gcd(3, p - 1) = 1, so the cube root is unique
+        let p: Integer = "194956428569330602684028182032818218031659648447338944171"
+            .parse()
+            .unwrap();
+        assert!(is_probable_prime(&p));
+        assert_eq!(Integer::from(&p - 1u32).gcd(&Integer::from(3)), 1);
+        let roots = nthroot_mod(&Integer::from(5), 3, &p, true).unwrap();
+        assert_eq!(roots.len(), 1);
+        assert_eq!(pow_mod(&roots[0], &Integer::from(3), &p), 5);
+        // d = 2 only needs the factors of 2
+        let x = Integer::from(123_456_789u64);
+        let a = pow_mod(&x, &Integer::from(4), &p);
+        let roots = nthroot_mod(&a, 4, &p, true).unwrap();
+        assert_eq!(roots, {
+            let mut expected = vec![x.clone(), Integer::from(&p - &x)];
+            expected.sort();
+            expected
+        });
+    }
+
+    #[test]
+    fn test_nthroot_mod_large_p_dividing_k() {
+        // every lift of a root works or none does, they stay classes mod q
+        let q = Integer::from(1_000_003u64);
+        let n = Integer::from(&q * &q);
+        let a = pow_mod(&Integer::from(2), &q, &n);
+        let smallest = nthroot_mod(&a, 1_000_003, &n, false).unwrap();
+        assert_eq!(smallest, vec![2]);
+        let (classes, m) = _root_classes(&a, 1_000_003, &q, 2);
+        assert_eq!((classes, m), (vec![Integer::from(2)], q.clone()));
+
+        // 2^61 - 1 to the third power, k = p and k = 2 p
+        let p = Integer::from((1u64 << 61) - 1);
+        let n = p.clone().pow(3);
+        let x = Integer::from(987_654_321u64);
+        for k in [(1u64 << 61) - 1, (1u64 << 62) - 2] {
+            let a = pow_mod(&x, &Integer::from(k), &n);
+            let smallest = nthroot_mod(&a, k, &n, false).unwrap();
+            assert_eq!(smallest.len(), 1);
+            assert_eq!(pow_mod(&smallest[0], &Integer::from(k), &n), a);
+            let (classes, m) = _root_classes(&a, k, &p, 3);
+            assert_eq!(m, p.clone().pow(2));
+            assert!(classes.contains(&x));
+        }
+    }
+
+    #[test]
+    fn test_single_root_of_large_prime_powers() {
+        // 2^60 and 3^20 roots, a single one must not list them
+        let two = Integer::from(2);
+        let n = two.clone().pow(90);
+        assert_eq!(nthroot_mod(&Integer::new(), 3, &n, false).unwrap(), vec![0]);
+        assert_eq!(
+            nthroot_mod_prime_power(&Integer::new(), 3, &two, 90, false),
+            vec![0]
+        );
+        let three = Integer::from(3);
+        let n = three.clone().pow(40);
+        let a = three.clone().pow(30);
+        assert_eq!(
+            nthroot_mod(&a, 3, &n, false).unwrap(),
+            vec![three.clone().pow(10)]
+        );
+        assert_eq!(
+            nthroot_mod_prime_power(&Integer::new(), 2, &three, 8, true).len(),
+            81
+        );
+
+        // and glued with a large prime
+        let q = Integer::from(1_000_003u64);
+        let n = Integer::from(&q * &n);
+        let smallest = nthroot_mod(&a, 3, &n, false).unwrap();
+        assert_eq!(smallest.len(), 1);
+        assert_eq!(pow_mod(&smallest[0], &three, &n), a);
+        assert!(smallest[0] <= three.pow(10));
+    }
+}
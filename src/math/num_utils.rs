@@ -17,6 +17,11 @@ pub fn pow_large(base: &Integer, exponent: &Integer) -> Integer {
     result
 }
 
+pub fn pow_mod(base: &Integer, exponent: &Integer, n: &Integer) -> Integer {
+    // base^exponent mod n through gmp, panics if a negative exponent has no inverse
+    Integer::from(base.pow_mod_ref(exponent, n).unwrap())
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -53,6 +58,14 @@ mod tests {
         assert_eq!(result, Integer::from(-8));
     }
 
+    #[test]
+    fn test_pow_mod() {
+        let n = Integer::from(1_000_000_007);
+        assert_eq!(pow_mod(&Integer::from(2), &Integer::from(10), &n), 1024);
+        assert_eq!(pow_mod(&Integer::from(-2), &Integer::from(3), &n), 999_999_999);
+        assert_eq!(pow_mod(&Integer::from(3), &Integer::from(-1), &n), 333_333_336);
+    }
+
     #[test]
     fn test_pow_large_base() {
         let result = pow_large(&Integer::from(1000), &Integer::from(2));
@@ -1,12 +1,14 @@
 extern crate rayon;
 extern crate rug;
 
+use super::modint::{ModContext, ModInt};
 use super::num_utils::pow_large;
 use super::threading::get_large_pool;
 use rayon::prelude::*;
 use rug::ops::Pow;
 use rug::{Complete, Integer, Rational};
 use std::ops::{Add, Mul, Sub};
+use std::sync::Arc;
 
 use std::default::Default;
 
@@ -40,18 +42,19 @@ pub fn x_pow_y_pow_z_mod_k(number_config: NumberConfig) -> Vec<String> {
 
     let mut results = vec![String::new(); (number_config.end - number_config.start + 1) as usize];
 
+    // every n shares the modulus 10^DIGIT_LIMIT
+    let modulus = Integer::from(number_config.exponent).pow(number_config.digit_limit);
+    let context = Arc::new(ModContext::new(&modulus).unwrap());
+    let base = ModInt::new(&Integer::from(number_config.base), &context);
+
     pool.install(|| {
         results.par_iter_mut().enumerate().for_each(|(i, result)| {
             let n = number_config.start + i;
 
-            let modulus = Integer::from(number_config.exponent).pow(number_config.digit_limit);
             let exponent = pow_large(&Integer::from(number_config.exponent), &Integer::from(n));
 
             // Calculate 2^(10^n) mod 10^DIGIT_LIMIT
-            let res = Integer::from(number_config.base)
-                .pow_mod(&exponent, &modulus)
-                .unwrap();
-            *result = res.to_string();
+            *result = base.pow(&exponent).to_string();
         });
     });
 
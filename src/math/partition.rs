@@ -0,0 +1,315 @@
+/*
+the partition function p(n) and a few restricted versions
+
+small n: Euler's pentagonal recurrence
+    p(m) = sum over j >= 1 of (-1)^(j + 1) (p(m - j(3j - 1)/2) + p(m - j(3j + 1)/2))
+O(n^1.5) additions, and every table value comes along for free
+
+large n: the Hardy-Ramanujan-Rademacher series
+    p(n) = 1 / (pi sqrt 2) sum over k of A_k(n) sqrt(k) d/dn (sinh(C lambda / k) / lambda)
+with lambda = sqrt(n - 1/24), C = pi sqrt(2/3). the derivative is
+    ((C lambda / k) cosh(C lambda / k) - sinh(C lambda / k)) / (2 lambda^3)
+the k-th term has about C sqrt(n) / k bits so each one is computed with only the
+precision it needs, A_k(n) uses Selberg's formula instead of Dedekind sums
+    A_k(n) = sqrt(k / 3) sum over l mod 2k with (3l^2 + l) / 2 = -n mod k of
+             (-1)^l cos((6l + 1) pi / 6k)
+the number of terms comes from Rademacher's bound on the tail, the result is
+rounded to the nearest integer
+*/
+use rug::float::Constant;
+use rug::ops::Pow;
+use rug::{Float, Integer};
+
+use super::spf_sieve::SpfTable;
+
+// above this p(n) is computed with the Rademacher series
+const RADEMACHER_THRESHOLD: u64 = 1000;
+// bits of every Rademacher term below its leading one
+const GUARD_BITS: u32 = 64;
+
+fn _pentagonal_offsets(n: u64) -> Vec<(u64, bool)> {
+    // (generalized pentagonal number, add or subtract) up to n in increasing order
+    let mut offsets = Vec::new();
+    let mut j = 1u64;
+    loop {
+        let add = j % 2 == 1;
+        let first = j * (3 * j - 1) / 2;
+        if first > n {
+            break;
+        }
+        offsets.push((first, add));
+        let second = j * (3 * j + 1) / 2;
+        if second <= n {
+            offsets.push((second, add));
+        }
+        j += 1;
+    }
+    offsets
+}
+
+pub fn partition_table(n: u64) -> Vec<Integer> {
+    // p(0), p(1), ..., p(n)
+    let offsets = _pentagonal_offsets(n);
+    let mut table: Vec<Integer> = Vec::with_capacity(n as usize + 1);
+    table.push(Integer::from(1));
+    for m in 1..=n as usize {
+        let mut value = Integer::new();
+        for (offset, add) in offsets.iter() {
+            let offset = *offset as usize;
+            if offset > m {
+                break;
+            }
+            if *add {
+                value += &table[m - offset];
+            } else {
+                value -= &table[m - offset];
+            }
+        }
+        table.push(value);
+    }
+    table
+}
+
+pub fn partition_table_mod(n: u64, modulus: u64) -> Vec<u64> {
+    // p(0), ..., p(n) mod m, no big numbers at all
+    if modulus == 0 {
+        panic!("modulus must be positive");
+    }
+    let offsets = _pentagonal_offsets(n);
+    let mut table: Vec<u64> = Vec::with_capacity(n as usize + 1);
+    table.push(1 % modulus);
+    for m in 1..=n as usize {
+        let mut value = 0u64;
+        for (offset, add) in offsets.iter() {
+            let offset = *offset as usize;
+            if offset > m {
+                break;
+            }
+            let term = table[m - offset];
+            value = if *add {
+                ((value as u128 + term as u128) % modulus as u128) as u64
+            } else {
+                ((value as u128 + modulus as u128 - term as u128) % modulus as u128) as u64
+            };
+        }
+        table.push(value);
+    }
+    table
+}
+
+pub fn partition_pentagonal(n: u64) -> Integer {
+    partition_table(n).swap_remove(n as usize)
+}
+
+fn _rademacher_terms(n: u64) -> u64 {
+    /*
+    smallest N with Rademacher's tail bound below 1/4
+    |R(n, N)| < 44 pi^2 / (225 sqrt 3) N^-1/2
+                + pi sqrt 2 / 75 (N / (n - 1))^1/2 sinh(pi / N sqrt(2n / 3))
+    */
+    let pi = std::f64::consts::PI;
+    let n_f = n as f64;
+    let mut terms = ((n_f.sqrt() / 4.0) as u64).max(1);
+    loop {
+        let k = terms as f64;
+        let bound = 44.0 * pi * pi / (225.0 * 3f64.sqrt()) / k.sqrt()
+            + pi * 2f64.sqrt() / 75.0
+                * (k / (n_f - 1.0)).sqrt()
+                * (pi / k * (2.0 * n_f / 3.0).sqrt()).sinh();
+        if bound < 0.25 {
+            return terms;
+        }
+        terms += (terms / 8).max(1);
+    }
+}
+
+fn _selberg_a(k: u64, n: u64, precision: u32) -> Float {
+    // A_k(n) through Selberg's formula, only the l with (3l^2 + l) / 2 = -n mod k count
+    let target = (k - n % k) % k;
+    let mut total = Float::with_val(precision, 0);
+    let pi = Float::with_val(precision, Constant::Pi);
+    for l in 0..2 * k {
+        let pentagonal = ((3 * l as u128 * l as u128 + l as u128) / 2 % k as u128) as u64;
+        if pentagonal != target {
+            continue;
+        }
+        let angle = Float::with_val(precision, 6 * l + 1) * &pi / Float::with_val(precision, 6 * k);
+        let value = angle.cos();
+        if l % 2 == 0 {
+            total += value;
+        } else {
+            total -= value;
+        }
+    }
+    total * (Float::with_val(precision, k) / 3u32).sqrt()
+}
+
+pub fn partition_rademacher(n: u64) -> Integer {
+    if n < 2 {
+        return Integer::from(1);
+    }
+    let n_f = n as f64;
+    // log2 of the leading term, C sqrt(n) / ln 2
+    let leading_bits =
+        (std::f64::consts::PI * (2.0 * n_f / 3.0).sqrt() / std::f64::consts::LN_2) as u32 + 64;
+    let precision_for =
+        |k: u64| leading_bits / k as u32 + GUARD_BITS + 2 * 64u32.saturating_sub(n.leading_zeros());
+
+    let full = precision_for(1);
+    let mut total = Float::with_val(full, 0);
+    for k in 1..=_rademacher_terms(n) {
+        let precision = precision_for(k);
+        let a = _selberg_a(k, n, precision);
+        if a.is_zero() {
+            continue;
+        }
+        let pi = Float::with_val(precision, Constant::Pi);
+        let c = (Float::with_val(precision, 2) / 3u32).sqrt() * &pi;
+        let lambda = (Float::with_val(precision, 24 * n - 1) / 24u32).sqrt();
+        let x = (c * &lambda) / Float::with_val(precision, k);
+        let (sinh, cosh) = x.clone().sinh_cosh(Float::new(precision));
+        let derivative = (x * cosh - sinh) / (lambda.pow(3u32) * 2u32);
+        let term = a * Float::with_val(precision, k).sqrt() * derivative;
+        total += term;
+    }
+    let pi = Float::with_val(full, Constant::Pi);
+    total /= pi * Float::with_val(full, 2).sqrt();
+    total.round().to_integer().unwrap()
+}
+
+pub fn partition(n: u64) -> Integer {
+    if n <= RADEMACHER_THRESHOLD {
+        partition_pentagonal(n)
+    } else {
+        partition_rademacher(n)
+    }
+}
+
+pub fn distinct_partition_table(n: u64) -> Vec<Integer> {
+    // q(0), ..., q(n), partitions into distinct parts, the coefficients of prod (1 + x^k)
+    let n = n as usize;
+    let mut table = vec![Integer::new(); n + 1];
+    table[0] = Integer::from(1);
+    for part in 1..=n {
+        for total in (part..=n).rev() {
+            let (low, high) = table.split_at_mut(total);
+            high[0] += &low[total - part];
+        }
+    }
+    table
+}
+
+pub fn partitions_with_parts_at_most(n: u64, k: u64) -> Integer {
+    // partitions of n into parts <= k, the same as into at most k parts
+    let n = n as usize;
+    let mut table = vec![Integer::new(); n + 1];
+    table[0] = Integer::from(1);
+    for part in 1..=(k as usize).min(n) {
+        for total in part..=n {
+            let (low, high) = table.split_at_mut(total);
+            high[0] += &low[total - part];
+        }
+    }
+    table.swap_remove(n)
+}
+
+pub fn prime_partition_table(n: u64) -> Vec<Integer> {
+    // partitions into prime parts for 0, ..., n
+    let n_usize = n as usize;
+    let mut table = vec![Integer::new(); n_usize + 1];
+    table[0] = Integer::from(1);
+    if n < 2 {
+        return table;
+    }
+    for &p in SpfTable::new(n).primes() {
+        let p = p as usize;
+        for total in p..=n_usize {
+            let (low, high) = table.split_at_mut(total);
+            high[0] += &low[total - p];
+        }
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partition_table() {
+        let table = partition_table(30);
+        let expected = [
+            1u64, 1, 2, 3, 5, 7, 11, 15, 22, 30, 42, 56, 77, 101, 135, 176, 231, 297, 385, 490,
+            627, 792, 1002, 1255, 1575, 1958, 2436, 3010, 3718, 4565, 5604,
+        ];
+        for (value, e) in table.iter().zip(expected.iter()) {
+            assert_eq!(value, e);
+        }
+        assert_eq!(partition_pentagonal(100), 190_569_292u64);
+        assert_eq!(partition_pentagonal(200), 3_972_999_029_388u64);
+        assert_eq!(
+            partition(1000),
+            Integer::from_str_radix("24061467864032622473692149727991", 10).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_rademacher_matches_pentagonal() {
+        let table = partition_table(3000);
+        for n in (0..=3000u64).step_by(37).chain([1, 2, 3, 4, 5, 2999, 3000]) {
+            assert_eq!(partition_rademacher(n), table[n as usize], "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn test_rademacher_large() {
+        let digits = partition(100_000).to_string();
+        assert_eq!(digits.len(), 347);
+        assert!(digits.starts_with("27493510569775696512677516320986352688173429315980"));
+        let digits = partition(1_000_000).to_string();
+        assert_eq!(digits.len(), 1108);
+        assert!(digits.starts_with("14716849863582233986310047606098959434840304844391"));
+        assert!(digits.ends_with("15630003467104673818"));
+    }
+
+    #[test]
+    fn test_partition_table_mod() {
+        let table = partition_table(500);
+        for m in [2u64, 5, 7, 11, 1_000_000_007, u64::MAX / 3] {
+            let reduced = partition_table_mod(500, m);
+            for (big, small) in table.iter().zip(reduced.iter()) {
+                assert_eq!(Integer::from(big % m), *small);
+            }
+        }
+        // Ramanujan's congruences
+        let reduced = partition_table_mod(2000, 5 * 7 * 11);
+        for n in 0..=380usize {
+            assert_eq!(reduced[5 * n + 4] % 5, 0);
+            if 7 * n + 5 <= 2000 {
+                assert_eq!(reduced[7 * n + 5] % 7, 0);
+            }
+            if 11 * n + 6 <= 2000 {
+                assert_eq!(reduced[11 * n + 6] % 11, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_restricted_partitions() {
+        let distinct = distinct_partition_table(100);
+        assert_eq!(distinct[10], 10);
+        assert_eq!(distinct[100], 444_793);
+        assert_eq!(partitions_with_parts_at_most(10, 3), 14);
+        assert_eq!(partitions_with_parts_at_most(10, 10), 42);
+        assert_eq!(partitions_with_parts_at_most(10, 100), 42);
+        assert_eq!(partitions_with_parts_at_most(0, 0), 1);
+        // oeis A000607
+        let primes = prime_partition_table(20);
+        let expected = [
+            1u32, 0, 1, 1, 1, 2, 2, 3, 3, 4, 5, 6, 7, 9, 10, 12, 14, 17, 19, 23, 26,
+        ];
+        for (value, e) in primes.iter().zip(expected.iter()) {
+            assert_eq!(value, e);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use lazy_static::lazy_static;
+use rug::{Complete, Integer};
+
+use super::static_data::SMALL_PRIME_CACHE;
+
+fn create_square_table(modulus: usize) -> Vec<bool> {
+    let mut table = vec![false; modulus];
+    for i in 0..modulus {
+        table[(i * i) % modulus] = true;
+    }
+    table
+}
+
+lazy_static! {
+    static ref SQUARES_MOD_64: Vec<bool> = create_square_table(64);
+    static ref SQUARES_MOD_63: Vec<bool> = create_square_table(63);
+    static ref SQUARES_MOD_65: Vec<bool> = create_square_table(65);
+    static ref SQUARES_MOD_11: Vec<bool> = create_square_table(11);
+}
+
+pub fn is_square(n: &Integer) -> bool {
+    /*
+    quadratic residue filters before doing any root
+    mod 64 only needs the low bits, then one reduction mod 63 * 65 * 11
+    feeds the other three tables
+    together they reject ~99.9% of non squares
+    */
+    if n.cmp0() == std::cmp::Ordering::Less {
+        return false;
+    }
+    if !SQUARES_MOD_64[n.mod_u(64) as usize] {
+        return false;
+    }
+    let r = n.mod_u(63 * 65 * 11) as usize;
+    if !SQUARES_MOD_63[r % 63] || !SQUARES_MOD_65[r % 65] || !SQUARES_MOD_11[r % 11] {
+        return false;
+    }
+    n.is_perfect_square()
+}
+
+pub fn isqrt(n: &Integer) -> (Integer, Integer) {
+    // root and remainder, n = root^2 + remainder
+    if n.cmp0() == std::cmp::Ordering::Less {
+        panic!("isqrt of a negative number {}", n);
+    }
+    n.sqrt_rem_ref().complete()
+}
+
+pub fn iroot(n: &Integer, k: u32) -> (Integer, Integer) {
+    /*
+    integer k-th root truncated toward zero and the remainder
+    n = root^k + remainder
+    negative n only has a real root for odd k
+    */
+    if k == 0 {
+        panic!("0-th root is undefined");
+    }
+    if n.cmp0() == std::cmp::Ordering::Less && k.is_multiple_of(2) {
+        panic!("even root of a negative number {}", n);
+    }
+    n.root_rem_ref(k).complete()
+}
+
+pub fn is_perfect_power(n: &Integer) -> Option<(Integer, u32)> {
+    /*
+    returns (base, exponent) with the largest possible exponent
+    64 -> (2, 6), -27 -> (-3, 3)
+    0, 1 and -1 are not reported as they are powers of everything
+
+    only prime exponents are tried, when n = r^p we continue with r
+    and the exponents multiply. exponents above log2(n) can't work
+    */
+    let abs_n = n.clone().abs();
+    if abs_n <= 1 {
+        return None;
+    }
+    let negative = n.cmp0() == std::cmp::Ordering::Less;
+    if !negative && !abs_n.is_perfect_power() {
+        return None;
+    }
+
+    let mut base = abs_n;
+    let mut exponent = 1u32;
+    for &p in SMALL_PRIME_CACHE.iter() {
+        if negative && p == 2 {
+            continue;
+        }
+        if p > base.significant_bits() {
+            break;
+        }
+        loop {
+            let (root, rem) = iroot(&base, p);
+            if rem != 0 || root <= 1 {
+                break;
+            }
+            base = root;
+            exponent *= p;
+        }
+    }
+
+    if exponent == 1 {
+        return None;
+    }
+    if negative {
+        base = -base;
+    }
+    Some((base, exponent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rug::ops::Pow;
+
+    #[test]
+    fn test_is_square() {
+        for i in 0..2000u32 {
+            let n = Integer::from(i);
+            let root = n.clone().sqrt();
+            assert_eq!(is_square(&n), (&root * &root).complete() == n, "n = {}", i);
+        }
+        assert!(!is_square(&Integer::from(-4)));
+
+        let big: Integer = Integer::from(10).pow(40) + 7;
+        assert!(is_square(&big.clone().square()));
+        assert!(!is_square(&(big.square() + 1)));
+    }
+
+    #[test]
+    fn test_iroot() {
+        assert_eq!(
+            iroot(&Integer::from(27), 3),
+            (Integer::from(3), Integer::from(0))
+        );
+        assert_eq!(
+            iroot(&Integer::from(30), 3),
+            (Integer::from(3), Integer::from(3))
+        );
+        assert_eq!(
+            iroot(&Integer::from(-30), 3),
+            (Integer::from(-3), Integer::from(-3))
+        );
+        assert_eq!(
+            isqrt(&Integer::from(17)),
+            (Integer::from(4), Integer::from(1))
+        );
+
+        let n = Integer::from(12345).pow(17) + 99;
+        assert_eq!(iroot(&n, 17), (Integer::from(12345), Integer::from(99)));
+    }
+
+    #[test]
+    fn test_is_perfect_power() {
+        assert_eq!(is_perfect_power(&Integer::from(1)), None);
+        assert_eq!(is_perfect_power(&Integer::from(2)), None);
+        assert_eq!(is_perfect_power(&Integer::from(12)), None);
+        assert_eq!(
+            is_perfect_power(&Integer::from(16)),
+            Some((Integer::from(2), 4))
+        );
+        assert_eq!(
+            is_perfect_power(&Integer::from(64)),
+            Some((Integer::from(2), 6))
+        );
+        assert_eq!(
+            is_perfect_power(&Integer::from(36)),
+            Some((Integer::from(6), 2))
+        );
+        assert_eq!(
+            is_perfect_power(&Integer::from(-27)),
+            Some((Integer::from(-3), 3))
+        );
+        assert_eq!(
+            is_perfect_power(&Integer::from(-64)),
+            Some((Integer::from(-4), 3))
+        );
+        assert_eq!(is_perfect_power(&Integer::from(-4)), None);
+    }
+
+    #[test]
+    fn test_is_perfect_power_large() {
+        let base = Integer::from(1_000_003);
+        let n = base.clone().pow(210);
+        assert_eq!(is_perfect_power(&n), Some((base, 210)));
+
+        let n = Integer::from(3).pow(101) * 2;
+        assert_eq!(is_perfect_power(&n), None);
+    }
+}
@@ -0,0 +1,647 @@
+/*
+univariate polynomials over the rings in ring, R[x] as a PolyRing<R> domain
+object whose elements are Poly<T>, T the coefficient type (Integer, Rational,
+ModInt, FieldElement), coefficients low to high with no trailing zeros so the
+derived equality is equality of polynomials
+
+multiplication is schoolbook below KARATSUBA_THRESHOLD coefficients, karatsuba
+above it:
+    a = a0 + x^m a1, b = b0 + x^m b1
+    a b = a0 b0 + x^m ((a0 + a1)(b0 + b1) - a0 b0 - a1 b1) + x^2m a1 b1
+
+over a field R[x] is a euclidean domain, gcd and gcdex come from the trait and
+are monic. over ZZ the gcd goes through contents and a primitive pseudo
+remainder sequence instead
+
+resultants are the determinant of the sylvester matrix, taken with bareiss'
+fraction free elimination, every division in it is exact so it works over any
+euclidean domain. the discriminant is (-1)^(n (n - 1) / 2) res(f, f') / lc(f)
+with f' of formal degree n - 1, which matters in small characteristic
+
+finite_field and poly_congruence do their F_p[x] work here through rem, mul_rem
+and pow_rem. inneficient.rs keeps its index map on purpose: it runs euler's
+pentagonal recurrence for sigma, a sparse list of signed offsets into a table
+that grows one value at a time, not a product of two known polynomials
+*/
+use rug::Integer;
+
+use super::int_ring::IntegerRing;
+use super::ring::{EuclideanDomain, Field, Ring};
+
+const KARATSUBA_THRESHOLD: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Poly<T> {
+    coefficients: Vec<T>,
+}
+
+impl<T> Poly<T> {
+    pub fn coefficients(&self) -> &[T] {
+        // low to high
+        &self.coefficients
+    }
+
+    pub fn degree(&self) -> Option<usize> {
+        // None for the zero polynomial
+        self.coefficients.len().checked_sub(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    pub fn leading_coefficient(&self) -> Option<&T> {
+        self.coefficients.last()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PolyRing<R: Ring> {
+    base: R,
+}
+
+impl<R: Ring> PolyRing<R> {
+    pub fn new(base: R) -> PolyRing<R> {
+        PolyRing { base }
+    }
+
+    pub fn base(&self) -> &R {
+        &self.base
+    }
+
+    pub fn poly(&self, mut coefficients: Vec<R::Element>) -> Poly<R::Element> {
+        // from coefficients low to high, trailing zeros dropped
+        while coefficients.last().is_some_and(|c| self.base.is_zero(c)) {
+            coefficients.pop();
+        }
+        Poly { coefficients }
+    }
+
+    pub fn from_integers(&self, coefficients: &[Integer]) -> Poly<R::Element> {
+        self.poly(coefficients.iter().map(|c| self.base.convert(c)).collect())
+    }
+
+    pub fn x(&self) -> Poly<R::Element> {
+        self.monomial(self.base.one(), 1)
+    }
+
+    pub fn monomial(&self, c: R::Element, degree: usize) -> Poly<R::Element> {
+        // c x^degree
+        let mut coefficients = vec![self.base.zero(); degree];
+        coefficients.push(c);
+        self.poly(coefficients)
+    }
+
+    pub fn scale(&self, f: &Poly<R::Element>, c: &R::Element) -> Poly<R::Element> {
+        self.poly(f.coefficients.iter().map(|a| self.base.mul(a, c)).collect())
+    }
+
+    fn _add_slices(&self, a: &[R::Element], b: &[R::Element]) -> Vec<R::Element> {
+        let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+        let mut sum = long.to_vec();
+        for (s, c) in sum.iter_mut().zip(short.iter()) {
+            *s = self.base.add(s, c);
+        }
+        sum
+    }
+
+    fn _schoolbook(&self, a: &[R::Element], b: &[R::Element]) -> Vec<R::Element> {
+        if a.is_empty() || b.is_empty() {
+            return Vec::new();
+        }
+        let mut product = vec![self.base.zero(); a.len() + b.len() - 1];
+        for (i, x) in a.iter().enumerate() {
+            if self.base.is_zero(x) {
+                continue;
+            }
+            for (j, y) in b.iter().enumerate() {
+                product[i + j] = self.base.add(&product[i + j], &self.base.mul(x, y));
+            }
+        }
+        product
+    }
+
+    fn _karatsuba(&self, a: &[R::Element], b: &[R::Element]) -> Vec<R::Element> {
+        if a.len().min(b.len()) < KARATSUBA_THRESHOLD {
+            return self._schoolbook(a, b);
+        }
+        let m = a.len().max(b.len()) / 2;
+        let (a0, a1) = a.split_at(m.min(a.len()));
+        let (b0, b1) = b.split_at(m.min(b.len()));
+        let low = self._karatsuba(a0, b0);
+        let high = self._karatsuba(a1, b1);
+        let mut middle = self._karatsuba(&self._add_slices(a0, a1), &self._add_slices(b0, b1));
+        for (i, c) in low.iter().enumerate() {
+            middle[i] = self.base.sub(&middle[i], c);
+        }
+        for (i, c) in high.iter().enumerate() {
+            middle[i] = self.base.sub(&middle[i], c);
+        }
+
+        let mut product = vec![self.base.zero(); a.len() + b.len() - 1];
+        for (i, c) in low.iter().enumerate() {
+            product[i] = self.base.add(&product[i], c);
+        }
+        /* the middle can carry zeros past the end of the product */
+        for (i, c) in middle
+            .iter()
+            .enumerate()
+            .take(product.len().saturating_sub(m))
+        {
+            product[i + m] = self.base.add(&product[i + m], c);
+        }
+        for (i, c) in high.iter().enumerate() {
+            product[i + 2 * m] = self.base.add(&product[i + 2 * m], c);
+        }
+        product
+    }
+
+    pub fn evaluate(&self, f: &Poly<R::Element>, x: &R::Element) -> R::Element {
+        // horner
+        f.coefficients
+            .iter()
+            .rev()
+            .fold(self.base.zero(), |value, c| {
+                self.base.add(&self.base.mul(&value, x), c)
+            })
+    }
+
+    pub fn derivative(&self, f: &Poly<R::Element>) -> Poly<R::Element> {
+        self.poly(
+            f.coefficients
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, c)| self.base.mul(&self.base.convert(&Integer::from(i)), c))
+                .collect(),
+        )
+    }
+
+    pub fn compose(&self, f: &Poly<R::Element>, g: &Poly<R::Element>) -> Poly<R::Element> {
+        // f(g), horner with polynomials
+        f.coefficients.iter().rev().fold(self.zero(), |value, c| {
+            self.add(&self.mul(&value, g), &self.poly(vec![c.clone()]))
+        })
+    }
+
+    pub fn pseudo_rem(&self, f: &Poly<R::Element>, g: &Poly<R::Element>) -> Poly<R::Element> {
+        /*
+        lc(g)^(deg f - deg g + 1) f mod g, no division needed, g nonzero
+        every step scales the remainder by lc(g) and cancels its top term
+        */
+        let n = g.degree().expect("division by zero");
+        let lead = g.leading_coefficient().unwrap();
+        let mut r = f.clone();
+        let mut steps = (f.coefficients.len() + 1).saturating_sub(g.coefficients.len());
+        while let Some(d) = r.degree() {
+            if d < n {
+                break;
+            }
+            let top = r.coefficients[d].clone();
+            let shifted = self.mul(&self.monomial(top, d - n), g);
+            r = self.sub(&self.scale(&r, lead), &shifted);
+            steps -= 1;
+        }
+        let power = self.base.pow(lead, steps as u64);
+        self.scale(&r, &power)
+    }
+}
+
+impl<R: Ring> Ring for PolyRing<R> {
+    type Element = Poly<R::Element>;
+
+    fn zero(&self) -> Poly<R::Element> {
+        Poly {
+            coefficients: Vec::new(),
+        }
+    }
+
+    fn one(&self) -> Poly<R::Element> {
+        self.poly(vec![self.base.one()])
+    }
+
+    fn convert(&self, n: &Integer) -> Poly<R::Element> {
+        self.poly(vec![self.base.convert(n)])
+    }
+
+    fn add(&self, a: &Poly<R::Element>, b: &Poly<R::Element>) -> Poly<R::Element> {
+        self.poly(self._add_slices(&a.coefficients, &b.coefficients))
+    }
+
+    fn sub(&self, a: &Poly<R::Element>, b: &Poly<R::Element>) -> Poly<R::Element> {
+        self.add(a, &self.neg(b))
+    }
+
+    fn mul(&self, a: &Poly<R::Element>, b: &Poly<R::Element>) -> Poly<R::Element> {
+        self.poly(self._karatsuba(&a.coefficients, &b.coefficients))
+    }
+
+    fn neg(&self, a: &Poly<R::Element>) -> Poly<R::Element> {
+        self.poly(a.coefficients.iter().map(|c| self.base.neg(c)).collect())
+    }
+
+    fn is_zero(&self, a: &Poly<R::Element>) -> bool {
+        a.is_zero()
+    }
+}
+
+impl<F: Field> EuclideanDomain for PolyRing<F> {
+    fn div_rem(
+        &self,
+        a: &Poly<F::Element>,
+        b: &Poly<F::Element>,
+    ) -> (Poly<F::Element>, Poly<F::Element>) {
+        let n = b.degree().expect("division by zero");
+        let lead_inverse = self.base.inverse(b.leading_coefficient().unwrap()).unwrap();
+        let mut r = a.coefficients.clone();
+        let mut quotient = vec![self.base.zero(); r.len().saturating_sub(n)];
+        /* cancel the top term in place, it is exactly zero after the step */
+        while r.len() > n {
+            let d = r.len() - 1;
+            let q = self.base.mul(&r[d], &lead_inverse);
+            for (i, c) in b.coefficients[..n].iter().enumerate() {
+                r[d - n + i] = self.base.sub(&r[d - n + i], &self.base.mul(&q, c));
+            }
+            r.pop();
+            while r.last().is_some_and(|c| self.base.is_zero(c)) {
+                r.pop();
+            }
+            quotient[d - n] = q;
+        }
+        (self.poly(quotient), Poly { coefficients: r })
+    }
+
+    fn normal_unit(&self, a: &Poly<F::Element>) -> Poly<F::Element> {
+        // makes a monic
+        match a.leading_coefficient() {
+            Some(lead) => self.poly(vec![self.base.inverse(lead).unwrap()]),
+            None => self.one(),
+        }
+    }
+}
+
+impl<F: Field> PolyRing<F> {
+    pub fn rem(&self, a: &Poly<F::Element>, b: &Poly<F::Element>) -> Poly<F::Element> {
+        self.div_rem(a, b).1
+    }
+
+    pub fn mul_rem(
+        &self,
+        a: &Poly<F::Element>,
+        b: &Poly<F::Element>,
+        modulus: &Poly<F::Element>,
+    ) -> Poly<F::Element> {
+        self.rem(&self.mul(a, b), modulus)
+    }
+
+    pub fn pow_rem(
+        &self,
+        f: &Poly<F::Element>,
+        exponent: &Integer,
+        modulus: &Poly<F::Element>,
+    ) -> Poly<F::Element> {
+        // f^exponent mod modulus, square and multiply from the top bit
+        let mut result = self.rem(&self.one(), modulus);
+        for bit in (0..exponent.significant_bits()).rev() {
+            result = self.mul_rem(&result, &result, modulus);
+            if exponent.get_bit(bit) {
+                result = self.mul_rem(&result, f, modulus);
+            }
+        }
+        result
+    }
+}
+
+impl<R: EuclideanDomain> PolyRing<R> {
+    fn _exact_div(&self, a: &R::Element, b: &R::Element) -> R::Element {
+        self.base.div_rem(a, b).0
+    }
+
+    fn _determinant(&self, mut m: Vec<Vec<R::Element>>) -> R::Element {
+        // bareiss, every division is exact
+        let n = m.len();
+        let mut sign = false;
+        let mut previous = self.base.one();
+        for k in 0..n {
+            let pivot = match (k..n).find(|i| !self.base.is_zero(&m[*i][k])) {
+                Some(pivot) => pivot,
+                None => return self.base.zero(),
+            };
+            if pivot != k {
+                m.swap(pivot, k);
+                sign = !sign;
+            }
+            let (upper, lower) = m.split_at_mut(k + 1);
+            let pivot_row = &upper[k];
+            for row in lower.iter_mut() {
+                for j in k + 1..n {
+                    let value = self.base.sub(
+                        &self.base.mul(&row[j], &pivot_row[k]),
+                        &self.base.mul(&row[k], &pivot_row[j]),
+                    );
+                    row[j] = self._exact_div(&value, &previous);
+                }
+            }
+            previous = m[k][k].clone();
+        }
+        let det = if n == 0 { self.base.one() } else { previous };
+        if sign {
+            self.base.neg(&det)
+        } else {
+            det
+        }
+    }
+
+    fn _sylvester(
+        &self,
+        f: &Poly<R::Element>,
+        m: usize,
+        g: &Poly<R::Element>,
+        n: usize,
+    ) -> Vec<Vec<R::Element>> {
+        // f and g of formal degrees m and n, n shifted rows of f then m of g, high to low
+        let size = m + n;
+        let coefficient = |p: &Poly<R::Element>, i: usize| {
+            p.coefficients
+                .get(i)
+                .cloned()
+                .unwrap_or_else(|| self.base.zero())
+        };
+        let mut rows = Vec::with_capacity(size);
+        for (p, degree, count) in [(f, m, n), (g, n, m)] {
+            for shift in 0..count {
+                let mut row = vec![self.base.zero(); size];
+                for i in 0..=degree {
+                    row[shift + i] = coefficient(p, degree - i);
+                }
+                rows.push(row);
+            }
+        }
+        rows
+    }
+
+    pub fn resultant(&self, f: &Poly<R::Element>, g: &Poly<R::Element>) -> R::Element {
+        // 0 when either is zero
+        match (f.degree(), g.degree()) {
+            (Some(m), Some(n)) => self._determinant(self._sylvester(f, m, g, n)),
+            _ => self.base.zero(),
+        }
+    }
+
+    pub fn discriminant(&self, f: &Poly<R::Element>) -> Result<R::Element, String> {
+        let n = match f.degree() {
+            Some(n) if n >= 1 => n,
+            _ => return Err("f should have a positive degree".to_string()),
+        };
+        let resultant = self._determinant(self._sylvester(f, n, &self.derivative(f), n - 1));
+        let value = self._exact_div(&resultant, f.leading_coefficient().unwrap());
+        if (n * (n - 1) / 2) % 2 == 1 {
+            Ok(self.base.neg(&value))
+        } else {
+            Ok(value)
+        }
+    }
+}
+
+impl PolyRing<IntegerRing> {
+    pub fn content(&self, f: &Poly<Integer>) -> Integer {
+        // gcd of the coefficients with the sign of the leading one
+        let g = f.coefficients.iter().fold(Integer::new(), |g, c| g.gcd(c));
+        match f.leading_coefficient() {
+            Some(lead) if *lead < 0 => -g,
+            _ => g,
+        }
+    }
+
+    pub fn primitive_part(&self, f: &Poly<Integer>) -> Poly<Integer> {
+        if f.is_zero() {
+            return f.clone();
+        }
+        let content = self.content(f);
+        self.poly(
+            f.coefficients
+                .iter()
+                .map(|c| Integer::from(c / &content))
+                .collect(),
+        )
+    }
+
+    pub fn gcd(&self, f: &Poly<Integer>, g: &Poly<Integer>) -> Poly<Integer> {
+        // positive leading coefficient, content gcd times the primitive prs
+        let content = self.content(f).gcd(&self.content(g));
+        let (mut a, mut b) = (self.primitive_part(f), self.primitive_part(g));
+        while !b.is_zero() {
+            let r = self.pseudo_rem(&a, &b);
+            a = std::mem::replace(&mut b, self.primitive_part(&r));
+        }
+        self.scale(&a, &content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::finite_field::FiniteField;
+    use crate::math::ring::{crt, PrimeField, RationalField};
+    use rug::Rational;
+
+    fn zz() -> PolyRing<IntegerRing> {
+        PolyRing::new(IntegerRing::new())
+    }
+
+    fn ints(coefficients: &[i64]) -> Vec<Integer> {
+        coefficients.iter().map(|c| Integer::from(*c)).collect()
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let ring = zz();
+        let f = ring.from_integers(&ints(&[1, 2, 3]));
+        let g = ring.from_integers(&ints(&[-1, 0, 0, 1]));
+        assert_eq!(
+            ring.mul(&f, &g),
+            ring.from_integers(&ints(&[-1, -2, -3, 1, 2, 3]))
+        );
+        assert_eq!(ring.add(&f, &g), ring.from_integers(&ints(&[0, 2, 3, 1])));
+        assert_eq!(ring.sub(&f, &f), ring.zero());
+        assert_eq!(ring.zero().degree(), None);
+        assert_eq!(g.degree(), Some(3));
+        assert_eq!(
+            ring.pow(&ring.from_integers(&ints(&[1, 1])), 4),
+            ring.from_integers(&ints(&[1, 4, 6, 4, 1]))
+        );
+        assert_eq!(ring.evaluate(&f, &Integer::from(10)), 321);
+        assert_eq!(ring.derivative(&g), ring.from_integers(&ints(&[0, 0, 3])));
+        assert_eq!(ring.derivative(&ring.one()), ring.zero());
+        // f(g)(t) = f(g(t))
+        let composed = ring.compose(&f, &g);
+        assert_eq!(composed.degree(), Some(6));
+        for t in -5..6 {
+            let t = Integer::from(t);
+            assert_eq!(
+                ring.evaluate(&composed, &t),
+                ring.evaluate(&f, &ring.evaluate(&g, &t))
+            );
+        }
+    }
+
+    #[test]
+    fn test_karatsuba() {
+        // against schoolbook on sizes around and past the threshold
+        let ring = zz();
+        let mut state = 12_345u64;
+        let mut random_poly = |length: usize| {
+            let coefficients: Vec<Integer> = (0..length)
+                .map(|_| {
+                    state = state
+                        .wrapping_mul(6_364_136_223_846_793_005)
+                        .wrapping_add(1);
+                    Integer::from((state >> 33) as i64 - (1 << 30))
+                })
+                .collect();
+            ring.poly(coefficients)
+        };
+        for (m, n) in [
+            (31, 31),
+            (32, 32),
+            (33, 70),
+            (100, 100),
+            (257, 40),
+            (64, 1),
+            (150, 149),
+        ] {
+            let f = random_poly(m);
+            let g = random_poly(n);
+            let expected = ring.poly(ring._schoolbook(f.coefficients(), g.coefficients()));
+            assert_eq!(ring.mul(&f, &g), expected, "m = {}, n = {}", m, n);
+        }
+    }
+
+    #[test]
+    fn test_division_and_gcd_over_fields() {
+        let q = PolyRing::new(RationalField);
+        let f = q.from_integers(&ints(&[-4, 0, -2, 1]));
+        let g = q.from_integers(&ints(&[-3, 2]));
+        let (quotient, remainder) = q.div_rem(&f, &g);
+        assert_eq!(q.add(&q.mul(&quotient, &g), &remainder), f);
+        assert_eq!(remainder.degree(), Some(0));
+        assert_eq!(remainder.coefficients()[0], Rational::from((-41, 8)));
+        // gcd((x - 1)(x + 2), (x - 1)(2x + 3)) = x - 1, monic
+        let a = q.from_integers(&ints(&[-2, 1, 1]));
+        let b = q.from_integers(&ints(&[-3, 1, 2]));
+        assert_eq!(q.gcd(&a, &b), q.from_integers(&ints(&[-1, 1])));
+        let (d, s, t) = q.gcdex(&a, &b);
+        assert_eq!(q.add(&q.mul(&a, &s), &q.mul(&b, &t)), d);
+
+        // over GF(7): x^7 - x splits into every linear factor, crt in F[x]
+        let field = PrimeField::new(&Integer::from(7)).unwrap();
+        let ring = PolyRing::new(field);
+        let x7 = ring.sub(&ring.pow(&ring.x(), 7), &ring.x());
+        assert_eq!(ring.derivative(&ring.pow(&ring.x(), 7)), ring.zero());
+        for a in 0..7 {
+            let linear = ring.from_integers(&ints(&[-a, 1]));
+            assert!(ring.div_rem(&x7, &linear).1.is_zero());
+        }
+        // x^(7^3) = x mod an irreducible cubic, x^7 is not
+        let cubic = ring.from_integers(&ints(&[1, 1, 0, 1]));
+        let power = ring.pow_rem(&ring.x(), &Integer::from(343), &cubic);
+        assert_eq!(power, ring.x());
+        assert_ne!(ring.pow_rem(&ring.x(), &Integer::from(7), &cubic), ring.x());
+        assert_eq!(
+            ring.rem(&ring.pow(&ring.x(), 3), &cubic),
+            ring.from_integers(&ints(&[-1, -1]))
+        );
+        let moduli = [
+            ring.from_integers(&ints(&[1, 0, 1])),
+            ring.from_integers(&ints(&[-2, 1])),
+        ];
+        let residues = [
+            ring.from_integers(&ints(&[3, 1])),
+            ring.from_integers(&ints(&[5])),
+        ];
+        let x = crt(&ring, &residues, &moduli).unwrap();
+        for (r, m) in residues.iter().zip(moduli.iter()) {
+            assert_eq!(ring.div_rem(&ring.sub(&x, r), m).1, ring.zero());
+        }
+
+        // coefficients in GF(4)
+        let gf4 = FiniteField::new(&Integer::from(2), 2).unwrap();
+        let ring = PolyRing::new(gf4);
+        let w = ring.base().generator();
+        // y^2 + y + 1 = (y - w)(y - w^2) over GF(4)
+        let f = ring.from_integers(&ints(&[1, 1, 1]));
+        assert_eq!(ring.evaluate(&f, &w), ring.base().zero());
+        let linear = ring.poly(vec![w.clone(), ring.base().one()]);
+        assert!(ring.div_rem(&f, &linear).1.is_zero());
+    }
+
+    #[test]
+    fn test_gcd_over_integers() {
+        let ring = zz();
+        // 3 (x + 1)^2 (x - 2) and 6 (x + 1)(x + 5)
+        let f = ring.from_integers(&ints(&[-6, -9, 0, 3]));
+        let g = ring.from_integers(&ints(&[30, 36, 6]));
+        assert_eq!(ring.gcd(&f, &g), ring.from_integers(&ints(&[3, 3])));
+        assert_eq!(ring.content(&ring.from_integers(&ints(&[4, -6, -2]))), -2);
+        assert_eq!(
+            ring.primitive_part(&ring.from_integers(&ints(&[4, -6, -2]))),
+            ring.from_integers(&ints(&[-2, 3, 1]))
+        );
+        assert_eq!(
+            ring.gcd(&f, &ring.zero()),
+            ring.scale(&ring.primitive_part(&f), &Integer::from(3))
+        );
+        let coprime = ring.gcd(
+            &ring.from_integers(&ints(&[1, 0, 1])),
+            &ring.from_integers(&ints(&[-1, 1])),
+        );
+        assert_eq!(coprime, ring.one());
+    }
+
+    #[test]
+    fn test_resultant_and_discriminant() {
+        let ring = zz();
+        let f = ring.from_integers(&ints(&[-2, 0, 1]));
+        let g = ring.from_integers(&ints(&[-3, 0, 1]));
+        assert_eq!(ring.resultant(&f, &g), 1);
+        assert_eq!(
+            ring.resultant(
+                &ring.from_integers(&ints(&[1, 0, 1])),
+                &ring.from_integers(&ints(&[-1, 1]))
+            ),
+            2
+        );
+        // common root
+        assert_eq!(
+            ring.resultant(&f, &ring.from_integers(&ints(&[-4, 0, 2]))),
+            0
+        );
+        // constants
+        assert_eq!(ring.resultant(&ring.from_integers(&ints(&[3])), &g), 9);
+        assert_eq!(ring.resultant(&ring.zero(), &g), 0);
+
+        // b^2 - 4ac and -4p^3 - 27q^2
+        for (a, b, c) in [(1i64, 5i64, 6i64), (3, -7, 2), (2, 0, 5), (-4, 4, -1)] {
+            let quadratic = ring.from_integers(&ints(&[c, b, a]));
+            assert_eq!(ring.discriminant(&quadratic).unwrap(), b * b - 4 * a * c);
+        }
+        for (p, q) in [(-3i64, 2i64), (1, 1), (-7, 6), (0, 5)] {
+            let cubic = ring.from_integers(&ints(&[q, p, 0, 1]));
+            assert_eq!(
+                ring.discriminant(&cubic).unwrap(),
+                -4 * p * p * p - 27 * q * q
+            );
+        }
+        assert!(ring.discriminant(&ring.one()).is_err());
+
+        // over GF(3), x^3 - x - 1 has f' = -1 of lower formal degree
+        let ring = PolyRing::new(PrimeField::new(&Integer::from(3)).unwrap());
+        let f = ring.from_integers(&ints(&[-1, -1, 0, 1]));
+        let expected = Integer::from(-4 * -1 - 27).modulo(&Integer::from(3));
+        assert_eq!(ring.discriminant(&f).unwrap().value(), expected);
+        // and over Q the same cubic agrees with ZZ
+        let q = PolyRing::new(RationalField);
+        let f = q.from_integers(&ints(&[-1, -1, 0, 1]));
+        assert_eq!(q.discriminant(&f).unwrap(), Rational::from(-23));
+    }
+}
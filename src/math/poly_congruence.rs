@@ -0,0 +1,267 @@
+/*
+roots of a polynomial congruence f(x) = 0 mod n, coefficients from the highest
+power down like sympy's polynomial_congruence
+
+mod a prime p, in PolyRing<PrimeField> with f turned low to high:
+    small p, evaluate f at every residue with horner
+    large p, cantor zassenhaus: gcd(f, x^p - x) keeps one linear factor per
+    root, then gcd(g, (x + a)^((p - 1) / 2) - 1) splits g between the roots
+    that are (x + a) squares and the rest, trying a = 0, 1, 2, ... until it splits
+
+mod p^e, hensel one power at a time, r a root mod p^i:
+    f'(r) != 0 mod p: a single lift r - f(r) f'(r)^-1 mod p^(i + 1)
+    f'(r) = 0 mod p: f(r + t p^i) = f(r) mod p^(i + 1) for every t, so either all
+    p lifts are roots or none is
+    f(r) is evaluated in PolyRing<ModularRing> mod p^(i + 1)
+
+mod n: every combination of the prime power roots glued with a CrtContext
+*/
+use rug::ops::Pow;
+use rug::Integer;
+
+use super::chineese_remainder_theorem::CrtContext;
+use super::factorization::factorint;
+use super::modint::ModInt;
+use super::poly::{Poly, PolyRing};
+use super::ring::{EuclideanDomain, ModularRing, PrimeField, Ring};
+
+const BRUTE_FORCE_LIMIT: u32 = 1 << 12;
+
+fn _low_to_high(coefficients: &[Integer]) -> Vec<Integer> {
+    coefficients.iter().rev().cloned().collect()
+}
+
+fn _split_linear(ring: &PolyRing<PrimeField>, g: Poly<ModInt>, roots: &mut Vec<Integer>) {
+    // g monic and a product of distinct linear factors, p odd
+    if g.degree() == Some(1) {
+        roots.push((-&g.coefficients()[0]).value());
+        return;
+    }
+    let exponent = Integer::from(ring.base().characteristic() - 1u32) >> 1;
+    let mut a = Integer::new();
+    loop {
+        let shifted = ring.add(&ring.x(), &ring.convert(&a));
+        let h = ring.sub(&ring.pow_rem(&shifted, &exponent, &g), &ring.one());
+        let d = ring.gcd(&g, &h);
+        if d.degree() > Some(0) && d.degree() < g.degree() {
+            let rest = ring.div_rem(&g, &d).0;
+            _split_linear(ring, d, roots);
+            _split_linear(ring, rest, roots);
+            return;
+        }
+        a += 1;
+    }
+}
+
+fn _roots_mod_prime(ring: &PolyRing<PrimeField>, f: &Poly<ModInt>) -> Vec<Integer> {
+    // sorted roots mod p
+    let p = ring.base().characteristic();
+    match f.degree() {
+        /* zero has every residue as a root, a nonzero constant none */
+        None => {
+            let mut x = Integer::new();
+            let mut roots = Vec::new();
+            while x < *p {
+                roots.push(x.clone());
+                x += 1;
+            }
+            return roots;
+        }
+        Some(0) => return Vec::new(),
+        Some(_) => {}
+    }
+    if *p <= BRUTE_FORCE_LIMIT {
+        return (0..p.to_u32().unwrap())
+            .map(Integer::from)
+            .filter(|x| ring.evaluate(f, &ring.base().convert(x)).is_zero())
+            .collect();
+    }
+    /* gcd(f, x^p - x) */
+    let x = ring.x();
+    let g = ring.gcd(f, &ring.sub(&ring.pow_rem(&x, p, f), &x));
+    let mut roots = Vec::new();
+    if g.degree() > Some(0) {
+        _split_linear(ring, g, &mut roots);
+    }
+    roots.sort();
+    roots
+}
+
+fn _roots_mod_prime_power(
+    coefficients: &[Integer],
+    p: &Integer,
+    e: u32,
+) -> Result<Vec<Integer>, String> {
+    let coefficients = _low_to_high(coefficients);
+    let ring = PolyRing::new(PrimeField::new(p)?);
+    let f = ring.from_integers(&coefficients);
+    let mut roots = _roots_mod_prime(&ring, &f);
+    let derivative = ring.derivative(&f);
+    let mut modulus = p.clone();
+    for _ in 1..e {
+        let next_modulus = Integer::from(&modulus * p);
+        let lifted_ring = PolyRing::new(ModularRing::new(&next_modulus)?);
+        let lifted = lifted_ring.from_integers(&coefficients);
+        let mut next = Vec::new();
+        for r in roots.iter() {
+            let value = lifted_ring.evaluate(&lifted, &lifted_ring.base().convert(r));
+            let slope = ring.evaluate(&derivative, &ring.base().convert(r));
+            if let Some(inverse) = slope.inverse() {
+                /* f(r) is a multiple of p^i, t = -(f(r) / p^i) f'(r)^-1 mod p */
+                let t = (-(value.value() / &modulus) * inverse.value()).modulo(p);
+                next.push(t * &modulus + r);
+            } else if value.is_zero() {
+                let mut t = Integer::new();
+                while t < *p {
+                    next.push(Integer::from(&t * &modulus) + r);
+                    t += 1;
+                }
+            }
+        }
+        roots = next;
+        modulus = next_modulus;
+    }
+    roots.sort();
+    Ok(roots)
+}
+
+pub fn solve_poly_congruence(
+    coefficients: &[Integer],
+    n: &Integer,
+) -> Result<Vec<Integer>, String> {
+    // every x in [0, n) with f(x) = 0 mod n sorted, coefficients from the highest power down
+    if *n <= 0 {
+        return Err("n must be > 0".to_string());
+    }
+    if *n == 1 {
+        return Ok(vec![Integer::new()]);
+    }
+    let factors = factorint(n);
+    let mut moduli = Vec::new();
+    let mut local_roots = Vec::new();
+    for (p, e) in factors.factors().iter() {
+        let roots = _roots_mod_prime_power(coefficients, p, *e)?;
+        if roots.is_empty() {
+            return Ok(Vec::new());
+        }
+        moduli.push(p.clone().pow(*e));
+        local_roots.push(roots);
+    }
+
+    CrtContext::new(&moduli)?.combinations(&local_roots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rug::Complete;
+
+    fn poly(coefficients: &[i64]) -> Vec<Integer> {
+        coefficients.iter().map(|c| Integer::from(*c)).collect()
+    }
+
+    fn brute_roots(coefficients: &[Integer], n: u64) -> Vec<Integer> {
+        let ring = PolyRing::new(ModularRing::new(&Integer::from(n)).unwrap());
+        let f = ring.from_integers(&_low_to_high(coefficients));
+        (0..n)
+            .map(Integer::from)
+            .filter(|x| ring.evaluate(&f, &ring.base().convert(x)).is_zero())
+            .collect()
+    }
+
+    #[test]
+    fn test_solve_poly_congruence() {
+        let solve = |f: &[i64], n: u64| solve_poly_congruence(&poly(f), &Integer::from(n)).unwrap();
+        // sympy's polynomial_congruence examples
+        assert_eq!(solve(&[1, 0, -2], 7), vec![3, 4]);
+        assert_eq!(solve(&[1, 0, 0, -2], 3), vec![2]);
+        assert_eq!(solve(&[1, -3, 2], 6), vec![1, 2, 4, 5]);
+        assert_eq!(
+            solve(&[4, 0, 0, 1], 27),
+            brute_roots(&poly(&[4, 0, 0, 1]), 27)
+        );
+        assert_eq!(solve(&[1, 0, 1], 3), Vec::<Integer>::new());
+        assert_eq!(solve(&[6], 3), vec![0, 1, 2]);
+        assert_eq!(solve(&[], 4), vec![0, 1, 2, 3]);
+        assert!(solve_poly_congruence(&poly(&[1]), &Integer::from(0)).is_err());
+    }
+
+    #[test]
+    fn test_solve_poly_congruence_matches_brute_force() {
+        // singular roots: x^2, (x - 1)^2 (x + 2), 8x^3 + 12x + 6 and friends
+        let polynomials: [&[i64]; 8] = [
+            &[1, 0, 0],
+            &[1, 0, -3, 2],
+            &[8, 0, 12, 6],
+            &[1, 0, 0, 0, -16],
+            &[3, -5, 0, 7, 1],
+            &[2, 0, 0, 0, 0, 2],
+            &[1, 1, 1, 1, 1, 1, 1],
+            &[-9, 0, 27],
+        ];
+        for f in polynomials.iter() {
+            let f = poly(f);
+            for n in 1..400u64 {
+                assert_eq!(
+                    solve_poly_congruence(&f, &Integer::from(n)).unwrap(),
+                    brute_roots(&f, n),
+                    "f = {:?}, n = {}",
+                    f,
+                    n
+                );
+            }
+            for n in [729u64, 1024, 3125, 2401 * 3] {
+                assert_eq!(
+                    solve_poly_congruence(&f, &Integer::from(n)).unwrap(),
+                    brute_roots(&f, n)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_prime() {
+        // (x - a)(x - b)(x - c)(x^2 + 1) mod a prime = 3 mod 4, so x^2 + 1 has no roots
+        let p = Integer::from(1_000_000_007u64);
+        let roots = [
+            Integer::from(5),
+            Integer::from(123_456_789),
+            Integer::from(999_999_000),
+        ];
+        let mut f = vec![Integer::from(1)];
+        for factor in [poly(&[1, 0, 1])]
+            .into_iter()
+            .chain(roots.iter().map(|r| vec![Integer::from(1), -r.clone()]))
+        {
+            let mut product = vec![Integer::new(); f.len() + factor.len() - 1];
+            for (i, a) in f.iter().enumerate() {
+                for (j, b) in factor.iter().enumerate() {
+                    product[i + j] += (a * b).complete();
+                }
+            }
+            f = product;
+        }
+        assert_eq!(solve_poly_congruence(&f, &p).unwrap(), roots.to_vec());
+        // a repeated root counts once, and through p^2 it is singular
+        let square = poly(&[1, -20, 100]);
+        assert_eq!(solve_poly_congruence(&square, &p).unwrap(), vec![10]);
+        // a singular root past the brute force limit lifts to every t mod p
+        let q = Integer::from(5_003u32);
+        let q_squared = Integer::from(&q * &q);
+        let lifted = solve_poly_congruence(&square, &q_squared).unwrap();
+        assert_eq!(Integer::from(lifted.len()), q);
+        assert!(lifted
+            .iter()
+            .all(|x| Integer::from(x - 10u32).is_divisible(&q)));
+        // 65537 with a degree 16 split
+        let q = Integer::from(65_537u32);
+        let mut f = poly(&[1]);
+        f.extend(std::iter::repeat_n(Integer::new(), 15));
+        f.push(Integer::from(-1));
+        let roots = solve_poly_congruence(&f, &q).unwrap();
+        assert_eq!(roots.len(), 16);
+        assert!(roots
+            .iter()
+            .all(|x| x.clone().pow_mod(&Integer::from(16), &q).unwrap() == 1));
+    }
+}
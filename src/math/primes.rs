@@ -1,10 +1,13 @@
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use rug::{Complete, Integer};
 use std::str::FromStr;
+use std::sync::Arc;
 
 use super::bitscan::bit_scan1;
 use super::common::{is_mersenne_number, is_power_of_2, lucas_lehmer_q, trailing_zeros};
+use super::factor_u64::is_prime_u64;
 use super::miller_rabin_bases::get_miller_rabin_bases;
+use super::modint::{ModContext, ModInt};
 use super::static_data::{PRIME_CACHE_LIMIT, SMALL_PRIME_CACHE};
 use super::threading::get_large_pool;
 
@@ -23,10 +26,13 @@ fn _check_prime_cache(n: &Integer) -> Option<bool> {
     }
 }
 
-fn _miller_rabin_test(n: &Integer, base: &Integer, s: u32, t: &Integer) -> bool {
-    let mut b = Integer::from(base.pow_mod_ref(t, n).unwrap());
+fn _miller_rabin_test(base: &ModInt, s: u32, t: &Integer) -> bool {
+    // the strong probable prime test to one base, all in the context of n
+    let one = ModInt::one(base.context());
+    let minus_one = -&one;
+    let mut b = base.pow(t);
 
-    if b == 1 || b == n - Integer::from(1) {
+    if b == one || b == minus_one {
         return true;
     }
 
@@ -35,11 +41,11 @@ fn _miller_rabin_test(n: &Integer, base: &Integer, s: u32, t: &Integer) -> bool
     }
 
     for _ in 0..s - 1 {
-        b = Integer::from(b.pow_mod_ref(&Integer::from(2), n).unwrap());
-        if b == n - Integer::from(1) {
+        b = &b * &b;
+        if b == minus_one {
             return true;
         }
-        if b == 1 {
+        if b == one {
             return false;
         }
     }
@@ -69,6 +75,9 @@ pub fn miller_rabin_single(number: &Integer) -> bool {
         Some(val) => return val,
         None => {}
     }
+    if let Some(n) = number.to_u64() {
+        return is_prime_u64(n);
+    }
 
     let n_minus_one = number - Integer::from(1);
     let bit_scan_result = bit_scan1(&n_minus_one, 0);
@@ -76,6 +85,7 @@ pub fn miller_rabin_single(number: &Integer) -> bool {
     let t = Integer::from(number >> s);
 
     let bases = get_miller_rabin_bases(number);
+    let context = Arc::new(ModContext::new(number).unwrap());
 
     for base in bases.iter() {
         let base_mod = if base >= number {
@@ -84,7 +94,9 @@ pub fn miller_rabin_single(number: &Integer) -> bool {
             Integer::from(*base)
         };
 
-        if base_mod >= Integer::from(2) && !_miller_rabin_test(number, &base_mod, s, &t) {
+        if base_mod >= Integer::from(2)
+            && !_miller_rabin_test(&ModInt::new(&base_mod, &context), s, &t)
+        {
             return false;
         }
     }
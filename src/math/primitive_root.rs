@@ -0,0 +1,453 @@
+/*
+multiplicative orders and the structure of the unit group (Z/nZ)*
+
+the order of a divides lambda(n), start from lambda and strip every prime q
+of lambda while a^(order / q) is still 1
+
+(Z/nZ)* is the product of the (Z/p^e)* by CRT, those are cyclic for odd p
+(generated by a primitive root mod p lifted to p^e), for p = 2
+    (Z/2)* = 1, (Z/4)* = <3>, (Z/2^e)* = <-1> x <5> for e >= 3
+so a primitive root mod n exists only for n = 1, 2, 4, p^e, 2 p^e
+the invariant factors come from splitting every cyclic piece into prime power
+pieces and gluing the largest q-pieces of every q together, then the next ones...
+*/
+use rug::ops::Pow;
+use rug::{Complete, Integer};
+
+use super::chineese_remainder_theorem::chinese_remainder_theorem_impl;
+use super::factorization::{factorint, Factorization};
+use super::num_utils::pow_mod;
+
+fn _check_modulus(n: &Integer) -> Result<(), String> {
+    if *n < 2 {
+        return Err("n should be an integer greater than 1".to_string());
+    }
+    Ok(())
+}
+
+fn _check_coprime(a: &Integer, n: &Integer) -> Result<Integer, String> {
+    // a reduced into [0, n), errors out the same way as sympy
+    let reduced = a.clone().modulo(n);
+    if reduced.gcd_ref(n).complete() != 1 {
+        return Err("The two numbers should be relatively prime".to_string());
+    }
+    Ok(reduced)
+}
+
+fn _order_dividing(
+    a: &Integer,
+    n: &Integer,
+    multiple: &Integer,
+    primes: &Factorization,
+) -> Integer {
+    // order of a mod n given some multiple of it and the factorization of that multiple
+    let mut order = multiple.clone();
+    for (q, e) in primes.factors().iter() {
+        for _ in 0..*e {
+            let candidate = (&order / q).complete();
+            if pow_mod(a, &candidate, n) != 1 {
+                break;
+            }
+            order = candidate;
+        }
+    }
+    order
+}
+
+pub fn multiplicative_order(a: &Integer, n: &Integer) -> Result<Integer, String> {
+    // smallest k > 0 with a^k = 1 mod n, sympy's n_order
+    _check_modulus(n)?;
+    let a = _check_coprime(a, n)?;
+    let lambda = factorint(n).carmichael_lambda();
+    Ok(_order_dividing(&a, n, &lambda, &factorint(&lambda)))
+}
+
+fn _has_primitive_root(factorization: &Factorization) -> bool {
+    // n = 1, 2, 4, p^e or 2 p^e
+    let factors = factorization.factors();
+    let two = factors.get(&Integer::from(2)).copied().unwrap_or(0);
+    let odd = factors.len() - usize::from(two > 0);
+    match two {
+        0 | 1 => odd <= 1,
+        2 => odd == 0,
+        _ => false,
+    }
+}
+
+fn _is_generator(g: &Integer, n: &Integer, phi: &Integer, phi_primes: &Factorization) -> bool {
+    // g of order phi(n), only g^(phi / q) for the primes q | phi need checking
+    phi_primes
+        .primes()
+        .all(|q| pow_mod(g, &(phi / q).complete(), n) != 1)
+}
+
+pub fn is_primitive_root(g: &Integer, n: &Integer) -> Result<bool, String> {
+    _check_modulus(n)?;
+    let g = _check_coprime(g, n)?;
+    let factorization = factorint(n);
+    if !_has_primitive_root(&factorization) {
+        return Ok(false);
+    }
+    let phi = factorization.totient();
+    Ok(_is_generator(&g, n, &phi, &factorint(&phi)))
+}
+
+pub fn primitive_root(n: &Integer) -> Result<Option<Integer>, String> {
+    // the smallest primitive root mod n, None when (Z/nZ)* is not cyclic
+    _check_modulus(n)?;
+    let factorization = factorint(n);
+    if !_has_primitive_root(&factorization) {
+        return Ok(None);
+    }
+    if *n == 2 {
+        return Ok(Some(Integer::from(1)));
+    }
+    let phi = factorization.totient();
+    let phi_primes = factorint(&phi);
+    let mut g = Integer::from(2);
+    /* the smallest root is O(log^6 p) under GRH and tiny in practice */
+    while g < *n {
+        if g.gcd_ref(n).complete() == 1 && _is_generator(&g, n, &phi, &phi_primes) {
+            return Ok(Some(g));
+        }
+        g += 1;
+    }
+    Ok(None)
+}
+
+fn _lifted_primitive_root(p: &Integer, e: u32, two: bool) -> Integer {
+    /*
+    a primitive root mod p^e (or 2 p^e when two is set) for an odd prime p
+    g mod p lifts to every p^e unless g^(p - 1) = 1 mod p^2, then g + p does,
+    mod 2 p^e take whichever of g, g + p^e is odd
+    */
+    let mut g = primitive_root(p).unwrap().unwrap();
+    if e > 1 {
+        let p_squared = p.clone().pow(2);
+        if pow_mod(&g, &(p - 1u32).complete(), &p_squared) == 1 {
+            g += p;
+        }
+    }
+    if two && g.is_even() {
+        g += p.clone().pow(e);
+    }
+    g
+}
+
+pub fn any_primitive_root(n: &Integer) -> Result<Option<Integer>, String> {
+    // some primitive root mod n, only the smallest root mod p is searched for
+    _check_modulus(n)?;
+    let factorization = factorint(n);
+    if !_has_primitive_root(&factorization) {
+        return Ok(None);
+    }
+    if *n <= 4 {
+        return Ok(Some(n - Integer::from(1)));
+    }
+    let two = factorization.exponent(&Integer::from(2)) > 0;
+    let (p, e) = factorization
+        .factors()
+        .iter()
+        .find(|(p, _)| **p != 2)
+        .map(|(p, e)| (p.clone(), *e))
+        .unwrap();
+    Ok(Some(_lifted_primitive_root(&p, e, two)))
+}
+
+pub fn all_primitive_roots(n: &Integer) -> Result<Vec<Integer>, String> {
+    // every primitive root mod n sorted, g^k with gcd(k, phi(n)) = 1 for any root g
+    let g = match any_primitive_root(n)? {
+        Some(g) => g,
+        None => return Ok(Vec::new()),
+    };
+    let phi = factorint(n).totient();
+    let mut roots = Vec::new();
+    let mut power = g.clone();
+    let mut k = Integer::from(1);
+    while k <= phi {
+        if k.gcd_ref(&phi).complete() == 1 {
+            roots.push(power.clone());
+        }
+        power = (power * &g).modulo(n);
+        k += 1;
+    }
+    roots.sort();
+    Ok(roots)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitGroup {
+    pub modulus: Integer,
+    // d_1 | d_2 | ... | d_r, (Z/nZ)* = C_d1 x ... x C_dr
+    pub invariant_factors: Vec<Integer>,
+    // generators[i] has order invariant_factors[i]
+    pub generators: Vec<Integer>,
+}
+
+impl UnitGroup {
+    pub fn order(&self) -> Integer {
+        self.invariant_factors.iter().product()
+    }
+
+    pub fn exponent(&self) -> Integer {
+        self.invariant_factors
+            .last()
+            .cloned()
+            .unwrap_or_else(|| Integer::from(1))
+    }
+
+    pub fn rank(&self) -> usize {
+        self.invariant_factors.len()
+    }
+
+    pub fn is_cyclic(&self) -> bool {
+        self.rank() <= 1
+    }
+
+    pub fn element(&self, exponents: &[Integer]) -> Integer {
+        // prod of generators[i]^exponents[i] mod n, negative exponents are inverses
+        let mut result = Integer::from(1).modulo(&self.modulus);
+        for (g, k) in self.generators.iter().zip(exponents.iter()) {
+            result = (result * pow_mod(g, k, &self.modulus)).modulo(&self.modulus);
+        }
+        result
+    }
+}
+
+fn _embed(value: &Integer, prime_power: &Integer, n: &Integer) -> Integer {
+    // x = value mod p^e and 1 mod the rest of n
+    if prime_power == n {
+        return value.clone();
+    }
+    let rest = (n / prime_power).complete();
+    chinese_remainder_theorem_impl(
+        &[value.clone(), Integer::from(1)],
+        &[prime_power.clone(), rest],
+    )
+    .unwrap()
+}
+
+pub fn unit_group(n: &Integer) -> Result<UnitGroup, String> {
+    // invariant factor decomposition of (Z/nZ)* with a generator for every factor
+    if *n < 1 {
+        return Err("n should be a positive integer".to_string());
+    }
+    let factorization = factorint(n);
+
+    /* cyclic pieces (order, generator mod n), one per odd prime and up to two for 2 */
+    let mut cyclic: Vec<(Integer, Integer)> = Vec::new();
+    for (p, e) in factorization.factors().iter() {
+        let pe = p.clone().pow(*e);
+        if *p == 2 {
+            if *e >= 2 {
+                cyclic.push((Integer::from(2), _embed(&(&pe - 1u32).complete(), &pe, n)));
+            }
+            if *e >= 3 {
+                cyclic.push((
+                    Integer::from(1) << (e - 2),
+                    _embed(&Integer::from(5), &pe, n),
+                ));
+            }
+        } else {
+            let order = p.clone().pow(e - 1) * (p - 1u32).complete();
+            let g = _lifted_primitive_root(p, *e, false);
+            cyclic.push((order, _embed(&g, &pe, n)));
+        }
+    }
+
+    /* split into prime power pieces, grouped by the prime */
+    let mut primary: std::collections::BTreeMap<Integer, Vec<(u32, Integer)>> =
+        std::collections::BTreeMap::new();
+    for (order, g) in cyclic.iter() {
+        for (q, a) in factorint(order).factors().iter() {
+            let qa = q.clone().pow(*a);
+            let piece = pow_mod(g, &(order / &qa).complete(), n);
+            primary.entry(q.clone()).or_default().push((*a, piece));
+        }
+    }
+    for pieces in primary.values_mut() {
+        pieces.sort_by_key(|piece| std::cmp::Reverse(piece.0));
+    }
+
+    /* the i-th invariant factor from the end takes the i-th largest piece of every prime */
+    let rank = primary
+        .values()
+        .map(|pieces| pieces.len())
+        .max()
+        .unwrap_or(0);
+    let mut invariant_factors = Vec::with_capacity(rank);
+    let mut generators = Vec::with_capacity(rank);
+    for i in 0..rank {
+        let mut d = Integer::from(1);
+        let mut g = Integer::from(1);
+        for (q, pieces) in primary.iter() {
+            if let Some((a, piece)) = pieces.get(i) {
+                d *= q.clone().pow(*a);
+                g = (g * piece).modulo(n);
+            }
+        }
+        invariant_factors.push(d);
+        generators.push(g);
+    }
+    invariant_factors.reverse();
+    generators.reverse();
+    Ok(UnitGroup {
+        modulus: n.clone(),
+        invariant_factors,
+        generators,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn brute_order(a: u64, n: u64) -> u64 {
+        let mut x = a % n;
+        let mut k = 1;
+        while x != 1 % n {
+            x = x * a % n;
+            k += 1;
+        }
+        k
+    }
+
+    #[test]
+    fn test_multiplicative_order() {
+        let order = |a: i64, n: u64| multiplicative_order(&Integer::from(a), &Integer::from(n));
+        assert_eq!(order(3, 7).unwrap(), 6);
+        assert_eq!(order(1, 5).unwrap(), 1);
+        assert_eq!(order(-2, 7).unwrap(), 6);
+        assert_eq!(order(9, 7).unwrap(), 3);
+        assert!(order(2, 4).is_err());
+        assert!(order(2, 1).is_err());
+        assert!(order(2, 0).is_err());
+        for n in 2..300u64 {
+            for a in 1..n {
+                if Integer::from(a).gcd(&Integer::from(n)) == 1 {
+                    assert_eq!(order(a as i64, n).unwrap(), brute_order(a, n));
+                }
+            }
+        }
+        // 2^127 = 1 mod the Mersenne prime 2^127 - 1
+        let m127 = (Integer::from(1) << 127) - 1u32;
+        assert_eq!(multiplicative_order(&Integer::from(2), &m127).unwrap(), 127);
+    }
+
+    #[test]
+    fn test_primitive_root() {
+        let root = |n: u64| primitive_root(&Integer::from(n)).unwrap();
+        assert_eq!(root(2), Some(Integer::from(1)));
+        assert_eq!(root(4), Some(Integer::from(3)));
+        assert_eq!(root(8), None);
+        assert_eq!(root(18), Some(Integer::from(5)));
+        assert_eq!(root(25), Some(Integer::from(2)));
+        assert_eq!(root(50), Some(Integer::from(3)));
+        assert_eq!(root(41), Some(Integer::from(6)));
+        assert_eq!(root(15), None);
+        assert!(primitive_root(&Integer::from(1)).is_err());
+
+        // the smallest root matches a brute force search
+        for n in 2..500u64 {
+            let phi = (1..=n)
+                .filter(|a| Integer::from(*a).gcd(&Integer::from(n)) == 1)
+                .count() as u64;
+            let expected = (1..n.max(2)).find(|g| {
+                Integer::from(*g).gcd(&Integer::from(n)) == 1 && brute_order(*g, n) == phi
+            });
+            assert_eq!(root(n), expected.map(Integer::from), "n = {}", n);
+            let lifted = any_primitive_root(&Integer::from(n)).unwrap();
+            assert_eq!(lifted.is_some(), expected.is_some());
+            if let Some(g) = lifted {
+                assert!(is_primitive_root(&g, &Integer::from(n)).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_all_primitive_roots() {
+        let roots = |n: u64| -> Vec<u64> {
+            all_primitive_roots(&Integer::from(n))
+                .unwrap()
+                .iter()
+                .map(|g| g.to_u64().unwrap())
+                .collect()
+        };
+        assert_eq!(roots(7), vec![3, 5]);
+        assert_eq!(roots(2), vec![1]);
+        assert_eq!(roots(9), vec![2, 5]);
+        assert_eq!(roots(12), Vec::<u64>::new());
+        for n in [11u64, 27, 54, 98, 121] {
+            let expected: Vec<u64> = (1..n)
+                .filter(|g| {
+                    is_primitive_root(&Integer::from(*g), &Integer::from(n)).unwrap_or(false)
+                })
+                .collect();
+            assert_eq!(roots(n), expected);
+        }
+        assert!(is_primitive_root(&Integer::from(2), &Integer::from(4)).is_err());
+        assert!(!is_primitive_root(&Integer::from(3), &Integer::from(8)).unwrap());
+    }
+
+    #[test]
+    fn test_unit_group() {
+        let structure = |n: u64| -> Vec<u64> {
+            unit_group(&Integer::from(n))
+                .unwrap()
+                .invariant_factors
+                .iter()
+                .map(|d| d.to_u64().unwrap())
+                .collect()
+        };
+        assert_eq!(structure(1), Vec::<u64>::new());
+        assert_eq!(structure(2), Vec::<u64>::new());
+        assert_eq!(structure(8), vec![2, 2]);
+        assert_eq!(structure(15), vec![2, 4]);
+        assert_eq!(structure(16), vec![2, 4]);
+        assert_eq!(structure(105), vec![2, 2, 12]);
+        assert_eq!(structure(11), vec![10]);
+        assert!(unit_group(&Integer::from(0)).is_err());
+
+        for n in 3..400u64 {
+            let group = unit_group(&Integer::from(n)).unwrap();
+            let factors: Vec<u64> = group
+                .invariant_factors
+                .iter()
+                .map(|d| d.to_u64().unwrap())
+                .collect();
+            for pair in factors.windows(2) {
+                assert_eq!(pair[1] % pair[0], 0);
+            }
+            for (g, d) in group.generators.iter().zip(factors.iter()) {
+                assert_eq!(brute_order(g.to_u64().unwrap(), n), *d);
+            }
+            // the generators span the whole group, so the product is direct
+            let mut span: HashSet<u64> = HashSet::from([1]);
+            for (g, d) in group.generators.iter().zip(factors.iter()) {
+                let g = g.to_u64().unwrap();
+                let mut next = HashSet::new();
+                for x in span.iter() {
+                    let mut y = *x;
+                    for _ in 0..*d {
+                        next.insert(y);
+                        y = y * g % n;
+                    }
+                }
+                span = next;
+            }
+            assert_eq!(Integer::from(span.len()), group.order(), "n = {}", n);
+            assert_eq!(
+                group.exponent(),
+                factorint(&Integer::from(n)).carmichael_lambda()
+            );
+            assert_eq!(
+                group.is_cyclic(),
+                primitive_root(&Integer::from(n)).unwrap().is_some()
+            );
+            let exponents: Vec<Integer> = (1..=group.rank()).map(Integer::from).collect();
+            let element = group.element(&exponents);
+            assert_eq!(Integer::from(element.gcd_ref(&group.modulus)), 1);
+        }
+    }
+}
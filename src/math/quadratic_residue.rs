@@ -0,0 +1,296 @@
+/*
+Legendre, Jacobi and Kronecker symbols and quadratic residuosity
+
+jacobi uses the binary algorithm, no division besides the reduction after
+every swap:
+    pull out the factors 2 of a, each flips the sign when n = 3, 5 mod 8
+    swap a and n, quadratic reciprocity flips the sign when both are 3 mod 4
+    reduce a mod n
+legendre is jacobi for a prime, kronecker extends it to every n
+
+is_quadratic_residue goes prime power by prime power, a = p^k u with u a unit is
+a square mod p^e when k >= e or k is even and u is a square, for odd p that is
+legendre(u, p) = 1, for p = 2 it is u = 1 mod 2^min(e - k, 3)
+*/
+use rug::ops::Pow;
+use rug::Integer;
+
+use super::factorization::{factorint, is_probable_prime};
+
+fn _jacobi_u64(mut a: u64, mut n: u64) -> i32 {
+    // same algorithm in machine words, n odd
+    let mut sign = 1;
+    a %= n;
+    while a != 0 {
+        let zeros = a.trailing_zeros();
+        a >>= zeros;
+        if zeros % 2 == 1 && (n % 8 == 3 || n % 8 == 5) {
+            sign = -sign;
+        }
+        std::mem::swap(&mut a, &mut n);
+        if a % 4 == 3 && n % 4 == 3 {
+            sign = -sign;
+        }
+        a %= n;
+    }
+    if n == 1 {
+        sign
+    } else {
+        0
+    }
+}
+
+fn _jacobi(a: &Integer, n: &Integer) -> i32 {
+    // n odd and positive
+    if let (Some(a), Some(n)) = (a.to_u64(), n.to_u64()) {
+        return _jacobi_u64(a, n);
+    }
+    let mut sign = 1;
+    let mut a = a.clone().modulo(n);
+    let mut n = n.clone();
+    while a != 0 {
+        let zeros = a.find_one(0).unwrap();
+        a >>= zeros;
+        let n_mod_8 = n.mod_u(8);
+        if zeros % 2 == 1 && (n_mod_8 == 3 || n_mod_8 == 5) {
+            sign = -sign;
+        }
+        std::mem::swap(&mut a, &mut n);
+        if a.mod_u(4) == 3 && n.mod_u(4) == 3 {
+            sign = -sign;
+        }
+        /* the word sized tail is much cheaper */
+        if let (Some(small_a), Some(small_n)) = (a.to_u64(), n.to_u64()) {
+            return sign * _jacobi_u64(small_a, small_n);
+        }
+        a.modulo_mut(&n);
+    }
+    if n == 1 {
+        sign
+    } else {
+        0
+    }
+}
+
+pub fn jacobi(a: &Integer, n: &Integer) -> Result<i32, String> {
+    if *n <= 0 || n.is_even() {
+        return Err("n should be an odd positive integer".to_string());
+    }
+    Ok(_jacobi(a, n))
+}
+
+pub fn legendre(a: &Integer, p: &Integer) -> Result<i32, String> {
+    if *p <= 2 || !is_probable_prime(p) {
+        return Err("p should be an odd prime integer".to_string());
+    }
+    Ok(_jacobi(a, p))
+}
+
+pub fn kronecker(a: &Integer, n: &Integer) -> i32 {
+    /*
+    (a / 0) is 1 for a = +-1 and 0 otherwise, (a / -1) is the sign of a,
+    (a / 2) is 0 for even a, 1 for a = +-1 mod 8 and -1 for a = +-3 mod 8
+    */
+    if *n == 0 {
+        return i32::from(a.clone().abs() == 1);
+    }
+    let mut sign = 1;
+    let mut n = n.clone();
+    if n < 0 {
+        n = -n;
+        if *a < 0 {
+            sign = -sign;
+        }
+    }
+    let zeros = n.find_one(0).unwrap();
+    if zeros > 0 {
+        if a.is_even() {
+            return 0;
+        }
+        n >>= zeros;
+        let a_mod_8 = a.mod_u(8);
+        if zeros % 2 == 1 && (a_mod_8 == 3 || a_mod_8 == 5) {
+            sign = -sign;
+        }
+    }
+    sign * _jacobi(a, &n)
+}
+
+fn _is_residue_prime_power(a: &Integer, p: &Integer, e: u32) -> bool {
+    // a square mod p^e
+    let pe = p.clone().pow(e);
+    let mut u = a.clone().modulo(&pe);
+    if u == 0 {
+        return true;
+    }
+    let k = u.remove_factor_mut(p);
+    if k % 2 == 1 {
+        return false;
+    }
+    if *p == 2 {
+        let rest = (e - k).min(3);
+        return u.mod_u(1 << rest) == 1;
+    }
+    _jacobi(&u, p) == 1
+}
+
+pub fn is_quadratic_residue(a: &Integer, n: &Integer) -> Result<bool, String> {
+    // x^2 = a mod n has a solution
+    if *n <= 0 {
+        return Err("n must be > 0".to_string());
+    }
+    let a = a.clone().modulo(n);
+    if a < 2 {
+        return Ok(true);
+    }
+    if is_probable_prime(n) {
+        return Ok(*n == 2 || _jacobi(&a, n) == 1);
+    }
+    Ok(factorint(n)
+        .factors()
+        .iter()
+        .all(|(p, e)| _is_residue_prime_power(&a, p, *e)))
+}
+
+pub fn residue_table(n: u64) -> Vec<bool> {
+    /*
+    table[a] says if a is a square mod n, for every 0 <= a < n
+    x^2 for x <= n / 2 covers every square since (n - x)^2 = x^2, the squares
+    come from (x + 1)^2 = x^2 + 2x + 1 so it is additions only
+    */
+    if n == 0 {
+        panic!("modulus must be positive");
+    }
+    let mut table = vec![false; n as usize];
+    let mut square = 0u64;
+    for x in 0..=n / 2 {
+        table[square as usize] = true;
+        /* square + 2x + 1 <= 2n fits in a u64 for any n < 2^63 */
+        square = (square + 2 * x + 1) % n;
+    }
+    table
+}
+
+pub fn quadratic_non_residues(n: u64) -> Vec<u64> {
+    // every a < n with no square root mod n
+    residue_table(n)
+        .iter()
+        .enumerate()
+        .filter(|(_, residue)| !**residue)
+        .map(|(a, _)| a as u64)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jacobi_and_legendre() {
+        // brute force legendre through euler's criterion
+        for p in [3u64, 5, 7, 11, 13, 101, 65_537] {
+            for a in 0..200u64 {
+                let power = Integer::from(a)
+                    .pow_mod(&Integer::from((p - 1) / 2), &Integer::from(p))
+                    .unwrap();
+                let expected = if power == 0 {
+                    0
+                } else if power == 1 {
+                    1
+                } else {
+                    -1
+                };
+                assert_eq!(
+                    legendre(&Integer::from(a), &Integer::from(p)).unwrap(),
+                    expected
+                );
+            }
+        }
+        assert_eq!(
+            jacobi(&Integer::from(1001), &Integer::from(9907)).unwrap(),
+            -1
+        );
+        assert_eq!(jacobi(&Integer::from(19), &Integer::from(45)).unwrap(), 1);
+        assert_eq!(jacobi(&Integer::from(8), &Integer::from(21)).unwrap(), -1);
+        assert_eq!(jacobi(&Integer::from(5), &Integer::from(21)).unwrap(), 1);
+        assert_eq!(jacobi(&Integer::from(-1), &Integer::from(7)).unwrap(), -1);
+        assert_eq!(jacobi(&Integer::from(3), &Integer::from(9)).unwrap(), 0);
+        assert!(jacobi(&Integer::from(3), &Integer::from(8)).is_err());
+        assert!(jacobi(&Integer::from(3), &Integer::from(-7)).is_err());
+        assert!(legendre(&Integer::from(3), &Integer::from(9)).is_err());
+        assert!(legendre(&Integer::from(2), &Integer::from(2)).is_err());
+
+        // the big number path agrees with the u64 one through multiplicativity
+        let m127 = (Integer::from(1) << 127) - 1u32;
+        let m61 = (Integer::from(1) << 61) - 1u32;
+        let product = Integer::from(&m127 * &m61);
+        for a in [2u32, 3, 5, 7, 1_000_003] {
+            let a = Integer::from(a);
+            let expected = _jacobi(&a, &m127) * _jacobi(&a, &m61);
+            assert_eq!(jacobi(&a, &product).unwrap(), expected);
+        }
+        // 2 is a square mod p = 7 mod 8, 3 is not mod 2^127 - 1
+        assert_eq!(legendre(&Integer::from(2), &m127).unwrap(), 1);
+        assert_eq!(legendre(&Integer::from(3), &m127).unwrap(), -1);
+    }
+
+    #[test]
+    fn test_kronecker() {
+        assert_eq!(kronecker(&Integer::from(1), &Integer::from(0)), 1);
+        assert_eq!(kronecker(&Integer::from(-1), &Integer::from(0)), 1);
+        assert_eq!(kronecker(&Integer::from(2), &Integer::from(0)), 0);
+        assert_eq!(kronecker(&Integer::from(-5), &Integer::from(-1)), -1);
+        assert_eq!(kronecker(&Integer::from(5), &Integer::from(-1)), 1);
+        assert_eq!(kronecker(&Integer::from(4), &Integer::from(2)), 0);
+        assert_eq!(kronecker(&Integer::from(3), &Integer::from(2)), -1);
+        assert_eq!(kronecker(&Integer::from(7), &Integer::from(2)), 1);
+        // kronecker is multiplicative in n
+        for a in -30i32..30 {
+            let a = Integer::from(a);
+            for n in 1..40i32 {
+                for m in 1..12i32 {
+                    let lhs = kronecker(&a, &Integer::from(n * m));
+                    let rhs = kronecker(&a, &Integer::from(n)) * kronecker(&a, &Integer::from(m));
+                    assert_eq!(lhs, rhs, "a = {}, n = {}, m = {}", a, n, m);
+                }
+                if n % 2 == 1 {
+                    assert_eq!(
+                        kronecker(&a, &Integer::from(n)),
+                        jacobi(&a, &Integer::from(n)).unwrap()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_quadratic_residue() {
+        for n in 1..300u64 {
+            let table = residue_table(n);
+            for a in 0..n {
+                assert_eq!(
+                    is_quadratic_residue(&Integer::from(a), &Integer::from(n)).unwrap(),
+                    table[a as usize],
+                    "a = {}, n = {}",
+                    a,
+                    n
+                );
+            }
+        }
+        assert!(is_quadratic_residue(&Integer::from(-1), &Integer::from(13)).unwrap());
+        assert!(!is_quadratic_residue(&Integer::from(-1), &Integer::from(7)).unwrap());
+        assert!(is_quadratic_residue(&Integer::from(3), &Integer::from(0)).is_err());
+    }
+
+    #[test]
+    fn test_residue_table() {
+        assert_eq!(quadratic_non_residues(7), vec![3, 5, 6]);
+        assert_eq!(quadratic_non_residues(1), Vec::<u64>::new());
+        let table = residue_table(12);
+        let residues: Vec<usize> = (0..12).filter(|a| table[*a]).collect();
+        assert_eq!(residues, vec![0, 1, 4, 9]);
+        // half of the units mod a prime are squares
+        let p = 100_003u64;
+        assert_eq!(quadratic_non_residues(p).len() as u64, (p - 1) / 2);
+    }
+}
@@ -0,0 +1,567 @@
+/*
+the algebra layer, a domain object per ring in the style of IntegerRing:
+    Ring, with elements of an associated type and the arithmetic as methods on
+    the domain so the modulus or the field lives in one place
+    EuclideanDomain, a division with remainder, gcd and gcdex come for free
+    Field, inverses, the division with remainder is exact
+
+implementations
+    ZZ: IntegerRing in int_ring
+    Z/nZ: ModularRing over ModInt
+    GF(p): PrimeField, Z/pZ checked prime
+    GF(p^k): FiniteField in finite_field
+    Q: RationalField over rug's Rational
+
+generic algorithms written once against the traits: crt for any euclidean
+domain, matrix products over a ring, determinant and inverse over a field
+*/
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use rug::{Integer, Rational};
+
+use super::factorization::is_probable_prime;
+use super::modint::{ModContext, ModInt};
+
+pub trait Ring {
+    type Element: Clone + PartialEq + Debug;
+
+    fn zero(&self) -> Self::Element;
+    fn one(&self) -> Self::Element;
+    fn convert(&self, n: &Integer) -> Self::Element;
+    fn add(&self, a: &Self::Element, b: &Self::Element) -> Self::Element;
+    fn sub(&self, a: &Self::Element, b: &Self::Element) -> Self::Element;
+    fn mul(&self, a: &Self::Element, b: &Self::Element) -> Self::Element;
+
+    fn neg(&self, a: &Self::Element) -> Self::Element {
+        self.sub(&self.zero(), a)
+    }
+
+    fn is_zero(&self, a: &Self::Element) -> bool {
+        *a == self.zero()
+    }
+
+    fn pow(&self, a: &Self::Element, exponent: u64) -> Self::Element {
+        let mut result = self.one();
+        for bit in (0..u64::BITS - exponent.leading_zeros()).rev() {
+            result = self.mul(&result, &result);
+            if exponent >> bit & 1 == 1 {
+                result = self.mul(&result, a);
+            }
+        }
+        result
+    }
+}
+
+pub trait EuclideanDomain: Ring {
+    fn div_rem(&self, a: &Self::Element, b: &Self::Element) -> (Self::Element, Self::Element);
+
+    fn normal_unit(&self, _a: &Self::Element) -> Self::Element {
+        // the unit u with u a in normal form, a positive integer, a monic polynomial
+        self.one()
+    }
+
+    fn gcdex(
+        &self,
+        a: &Self::Element,
+        b: &Self::Element,
+    ) -> (Self::Element, Self::Element, Self::Element) {
+        // (g, x, y) with a x + b y = g and g normal
+        let (mut r0, mut r1) = (a.clone(), b.clone());
+        let (mut x0, mut x1) = (self.one(), self.zero());
+        let (mut y0, mut y1) = (self.zero(), self.one());
+        while !self.is_zero(&r1) {
+            let (q, r) = self.div_rem(&r0, &r1);
+            r0 = std::mem::replace(&mut r1, r);
+            let x = self.sub(&x0, &self.mul(&q, &x1));
+            x0 = std::mem::replace(&mut x1, x);
+            let y = self.sub(&y0, &self.mul(&q, &y1));
+            y0 = std::mem::replace(&mut y1, y);
+        }
+        let unit = self.normal_unit(&r0);
+        (
+            self.mul(&unit, &r0),
+            self.mul(&unit, &x0),
+            self.mul(&unit, &y0),
+        )
+    }
+
+    fn gcd(&self, a: &Self::Element, b: &Self::Element) -> Self::Element {
+        let (mut r0, mut r1) = (a.clone(), b.clone());
+        while !self.is_zero(&r1) {
+            let (_, r) = self.div_rem(&r0, &r1);
+            r0 = std::mem::replace(&mut r1, r);
+        }
+        self.mul(&self.normal_unit(&r0), &r0)
+    }
+
+    fn lcm(&self, a: &Self::Element, b: &Self::Element) -> Self::Element {
+        if self.is_zero(a) || self.is_zero(b) {
+            return self.zero();
+        }
+        let (quotient, _) = self.div_rem(a, &self.gcd(a, b));
+        let product = self.mul(&quotient, b);
+        self.mul(&self.normal_unit(&product), &product)
+    }
+}
+
+pub trait Field: EuclideanDomain {
+    fn inverse(&self, a: &Self::Element) -> Option<Self::Element>;
+
+    fn div(&self, a: &Self::Element, b: &Self::Element) -> Option<Self::Element> {
+        self.inverse(b).map(|inverse| self.mul(a, &inverse))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModularRing {
+    // Z/nZ
+    context: Arc<ModContext>,
+}
+
+impl ModularRing {
+    pub fn new(n: &Integer) -> Result<ModularRing, String> {
+        Ok(ModularRing {
+            context: Arc::new(ModContext::new(n)?),
+        })
+    }
+
+    pub fn modulus(&self) -> &Integer {
+        self.context.modulus()
+    }
+
+    pub fn context(&self) -> &Arc<ModContext> {
+        &self.context
+    }
+}
+
+impl Ring for ModularRing {
+    type Element = ModInt;
+
+    fn zero(&self) -> ModInt {
+        ModInt::zero(&self.context)
+    }
+
+    fn one(&self) -> ModInt {
+        ModInt::one(&self.context)
+    }
+
+    fn convert(&self, n: &Integer) -> ModInt {
+        ModInt::new(n, &self.context)
+    }
+
+    fn add(&self, a: &ModInt, b: &ModInt) -> ModInt {
+        a + b
+    }
+
+    fn sub(&self, a: &ModInt, b: &ModInt) -> ModInt {
+        a - b
+    }
+
+    fn mul(&self, a: &ModInt, b: &ModInt) -> ModInt {
+        a * b
+    }
+
+    fn neg(&self, a: &ModInt) -> ModInt {
+        -a
+    }
+
+    fn is_zero(&self, a: &ModInt) -> bool {
+        a.is_zero()
+    }
+
+    fn pow(&self, a: &ModInt, exponent: u64) -> ModInt {
+        a.pow(&Integer::from(exponent))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PrimeField {
+    // GF(p), Z/pZ with p checked prime
+    ring: ModularRing,
+}
+
+impl PrimeField {
+    pub fn new(p: &Integer) -> Result<PrimeField, String> {
+        if !is_probable_prime(p) {
+            return Err("p should be a prime integer".to_string());
+        }
+        Ok(PrimeField {
+            ring: ModularRing::new(p)?,
+        })
+    }
+
+    pub fn characteristic(&self) -> &Integer {
+        self.ring.modulus()
+    }
+
+    pub fn context(&self) -> &Arc<ModContext> {
+        self.ring.context()
+    }
+}
+
+impl Ring for PrimeField {
+    type Element = ModInt;
+
+    fn zero(&self) -> ModInt {
+        self.ring.zero()
+    }
+
+    fn one(&self) -> ModInt {
+        self.ring.one()
+    }
+
+    fn convert(&self, n: &Integer) -> ModInt {
+        self.ring.convert(n)
+    }
+
+    fn add(&self, a: &ModInt, b: &ModInt) -> ModInt {
+        a + b
+    }
+
+    fn sub(&self, a: &ModInt, b: &ModInt) -> ModInt {
+        a - b
+    }
+
+    fn mul(&self, a: &ModInt, b: &ModInt) -> ModInt {
+        a * b
+    }
+
+    fn neg(&self, a: &ModInt) -> ModInt {
+        -a
+    }
+
+    fn is_zero(&self, a: &ModInt) -> bool {
+        a.is_zero()
+    }
+
+    fn pow(&self, a: &ModInt, exponent: u64) -> ModInt {
+        a.pow(&Integer::from(exponent))
+    }
+}
+
+impl EuclideanDomain for PrimeField {
+    fn div_rem(&self, a: &ModInt, b: &ModInt) -> (ModInt, ModInt) {
+        (a / b, self.zero())
+    }
+
+    fn normal_unit(&self, a: &ModInt) -> ModInt {
+        a.inverse().unwrap_or_else(|| self.one())
+    }
+}
+
+impl Field for PrimeField {
+    fn inverse(&self, a: &ModInt) -> Option<ModInt> {
+        a.inverse()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RationalField;
+
+impl Ring for RationalField {
+    type Element = Rational;
+
+    fn zero(&self) -> Rational {
+        Rational::new()
+    }
+
+    fn one(&self) -> Rational {
+        Rational::from(1)
+    }
+
+    fn convert(&self, n: &Integer) -> Rational {
+        Rational::from(n)
+    }
+
+    fn add(&self, a: &Rational, b: &Rational) -> Rational {
+        Rational::from(a + b)
+    }
+
+    fn sub(&self, a: &Rational, b: &Rational) -> Rational {
+        Rational::from(a - b)
+    }
+
+    fn mul(&self, a: &Rational, b: &Rational) -> Rational {
+        Rational::from(a * b)
+    }
+
+    fn neg(&self, a: &Rational) -> Rational {
+        Rational::from(-a)
+    }
+}
+
+impl EuclideanDomain for RationalField {
+    fn div_rem(&self, a: &Rational, b: &Rational) -> (Rational, Rational) {
+        (Rational::from(a / b), self.zero())
+    }
+
+    fn normal_unit(&self, a: &Rational) -> Rational {
+        self.inverse(a).unwrap_or_else(|| self.one())
+    }
+}
+
+impl Field for RationalField {
+    fn inverse(&self, a: &Rational) -> Option<Rational> {
+        if *a == 0 {
+            return None;
+        }
+        Some(a.clone().recip())
+    }
+}
+
+pub fn crt<D: EuclideanDomain>(
+    domain: &D,
+    residues: &[D::Element],
+    moduli: &[D::Element],
+) -> Result<D::Element, String> {
+    // x = residues[i] mod moduli[i] for pairwise coprime moduli, reduced mod the product
+    if residues.len() != moduli.len() {
+        return Err("Lists must have equal length".to_string());
+    }
+    let mut x = domain.zero();
+    let mut modulus = domain.one();
+    for (a, m) in residues.iter().zip(moduli.iter()) {
+        let (g, inverse, _) = domain.gcdex(&modulus, m);
+        if g != domain.one() {
+            return Err("Moduli must be pairwise coprime".to_string());
+        }
+        /* x + modulus ((a - x) modulus^-1 mod m) */
+        let step = domain.mul(&domain.sub(a, &x), &inverse);
+        let (_, step) = domain.div_rem(&step, m);
+        x = domain.add(&x, &domain.mul(&modulus, &step));
+        modulus = domain.mul(&modulus, m);
+        let (_, reduced) = domain.div_rem(&x, &modulus);
+        x = reduced;
+    }
+    Ok(x)
+}
+
+pub fn mat_mul<R: Ring>(
+    ring: &R,
+    a: &[Vec<R::Element>],
+    b: &[Vec<R::Element>],
+) -> Result<Vec<Vec<R::Element>>, String> {
+    let inner = b.len();
+    let columns = b.first().map_or(0, |row| row.len());
+    if a.iter().any(|row| row.len() != inner) || b.iter().any(|row| row.len() != columns) {
+        return Err("Matrix dimensions do not match".to_string());
+    }
+    Ok(a.iter()
+        .map(|row| {
+            (0..columns)
+                .map(|j| {
+                    row.iter()
+                        .zip(b.iter())
+                        .fold(ring.zero(), |sum, (x, b_row)| {
+                            ring.add(&sum, &ring.mul(x, &b_row[j]))
+                        })
+                })
+                .collect()
+        })
+        .collect())
+}
+
+fn _check_square<T>(matrix: &[Vec<T>]) -> Result<usize, String> {
+    let n = matrix.len();
+    if matrix.iter().any(|row| row.len() != n) {
+        return Err("Matrix must be square".to_string());
+    }
+    Ok(n)
+}
+
+pub fn determinant<F: Field>(field: &F, matrix: &[Vec<F::Element>]) -> Result<F::Element, String> {
+    // gaussian elimination, a row swap flips the sign
+    let n = _check_square(matrix)?;
+    let mut m = matrix.to_vec();
+    let mut det = field.one();
+    for column in 0..n {
+        let pivot = match (column..n).find(|row| !field.is_zero(&m[*row][column])) {
+            Some(pivot) => pivot,
+            None => return Ok(field.zero()),
+        };
+        if pivot != column {
+            m.swap(pivot, column);
+            det = field.neg(&det);
+        }
+        det = field.mul(&det, &m[column][column]);
+        let inverse = field.inverse(&m[column][column]).unwrap();
+        let (upper, lower) = m.split_at_mut(column + 1);
+        let pivot_row = &upper[column];
+        for row in lower.iter_mut() {
+            let factor = field.mul(&row[column], &inverse);
+            for (entry, above) in row.iter_mut().zip(pivot_row.iter()).skip(column) {
+                *entry = field.sub(entry, &field.mul(&factor, above));
+            }
+        }
+    }
+    Ok(det)
+}
+
+pub fn mat_inverse<F: Field>(
+    field: &F,
+    matrix: &[Vec<F::Element>],
+) -> Result<Option<Vec<Vec<F::Element>>>, String> {
+    // gauss jordan on [M | I], None when M is singular
+    let n = _check_square(matrix)?;
+    let mut m: Vec<Vec<F::Element>> = matrix
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let mut row = row.clone();
+            row.extend((0..n).map(|j| if i == j { field.one() } else { field.zero() }));
+            row
+        })
+        .collect();
+    for column in 0..n {
+        let pivot = match (column..n).find(|row| !field.is_zero(&m[*row][column])) {
+            Some(pivot) => pivot,
+            None => return Ok(None),
+        };
+        m.swap(pivot, column);
+        let inverse = field.inverse(&m[column][column]).unwrap();
+        for entry in m[column].iter_mut() {
+            *entry = field.mul(entry, &inverse);
+        }
+        let pivot_row = m[column].clone();
+        for (i, row) in m.iter_mut().enumerate() {
+            if i == column || field.is_zero(&row[column]) {
+                continue;
+            }
+            let factor = row[column].clone();
+            for (entry, pivot_entry) in row.iter_mut().zip(pivot_row.iter()) {
+                *entry = field.sub(entry, &field.mul(&factor, pivot_entry));
+            }
+        }
+    }
+    Ok(Some(m.into_iter().map(|row| row[n..].to_vec()).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::int_ring::get_zz;
+
+    fn rational(numerator: i64, denominator: i64) -> Rational {
+        Rational::from((numerator, denominator))
+    }
+
+    #[test]
+    fn test_integer_ring() {
+        let zz = get_zz();
+        let (a, b) = (Integer::from(240), Integer::from(-46));
+        let (g, x, y) = zz.gcdex(&a, &b);
+        assert_eq!(g, 2);
+        assert_eq!(Integer::from(&a * &x) + Integer::from(&b * &y), g);
+        assert_eq!(zz.gcd(&a, &b), 2);
+        assert_eq!(zz.lcm(&Integer::from(-4), &Integer::from(6)), 12);
+        assert_eq!(zz.pow(&Integer::from(3), 5), 243);
+        assert_eq!(
+            crt(
+                zz,
+                &[Integer::from(2), Integer::from(3), Integer::from(2)],
+                &[Integer::from(3), Integer::from(5), Integer::from(7)]
+            )
+            .unwrap(),
+            23
+        );
+        assert!(crt(
+            zz,
+            &[Integer::from(1), Integer::from(2)],
+            &[Integer::from(4), Integer::from(6)]
+        )
+        .is_err());
+        // matrix product over ZZ
+        let m = vec![
+            vec![Integer::from(1), Integer::from(1)],
+            vec![Integer::from(1), Integer::from(0)],
+        ];
+        let m2 = mat_mul(zz, &m, &m).unwrap();
+        let m4 = mat_mul(zz, &m2, &m2).unwrap();
+        assert_eq!(m4[0][1], 3);
+        assert_eq!(m4[0][0], 5);
+        assert!(mat_mul(zz, &m, &m[..1]).is_err());
+    }
+
+    #[test]
+    fn test_modular_ring_and_prime_field() {
+        let ring = ModularRing::new(&Integer::from(12)).unwrap();
+        let a = ring.convert(&Integer::from(5));
+        assert_eq!(ring.mul(&a, &a).value(), 1);
+        assert_eq!(ring.neg(&a).value(), 7);
+        assert_eq!(ring.pow(&a, 3).value(), 5);
+        assert!(ring.is_zero(&ring.mul(
+            &ring.convert(&Integer::from(4)),
+            &ring.convert(&Integer::from(3))
+        )));
+
+        assert!(PrimeField::new(&Integer::from(12)).is_err());
+        let field = PrimeField::new(&Integer::from(101)).unwrap();
+        assert_eq!(*field.characteristic(), 101);
+        for x in 1..101u32 {
+            let x = field.convert(&Integer::from(x));
+            assert_eq!(field.mul(&x, &field.inverse(&x).unwrap()), field.one());
+        }
+        assert_eq!(field.inverse(&field.zero()), None);
+        // gcd in a field is 0 or 1
+        let (g, x, _) = field.gcdex(&field.convert(&Integer::from(7)), &field.zero());
+        assert_eq!(g, field.one());
+        assert_eq!(
+            x.value(),
+            Integer::from(7).invert(&Integer::from(101)).unwrap()
+        );
+
+        // vandermonde determinant mod 101 against the product formula
+        let points = [2u32, 3, 5, 7, 11];
+        let matrix: Vec<Vec<ModInt>> = points
+            .iter()
+            .map(|p| {
+                (0..5)
+                    .map(|j| field.pow(&field.convert(&Integer::from(*p)), j))
+                    .collect()
+            })
+            .collect();
+        let mut expected = field.one();
+        for i in 0..5 {
+            for j in i + 1..5 {
+                let difference = Integer::from(points[j]) - points[i];
+                expected = field.mul(&expected, &field.convert(&difference));
+            }
+        }
+        assert_eq!(determinant(&field, &matrix).unwrap(), expected);
+        let inverse = mat_inverse(&field, &matrix).unwrap().unwrap();
+        let identity = mat_mul(&field, &matrix, &inverse).unwrap();
+        for (i, row) in identity.iter().enumerate() {
+            for (j, entry) in row.iter().enumerate() {
+                assert_eq!(entry.value(), u32::from(i == j));
+            }
+        }
+    }
+
+    #[test]
+    fn test_rational_field() {
+        let q = RationalField;
+        let matrix = vec![
+            vec![rational(1, 2), rational(1, 3)],
+            vec![rational(1, 4), rational(1, 5)],
+        ];
+        assert_eq!(determinant(&q, &matrix).unwrap(), rational(1, 60));
+        let inverse = mat_inverse(&q, &matrix).unwrap().unwrap();
+        assert_eq!(inverse[0][0], rational(12, 1));
+        assert_eq!(inverse[0][1], rational(-20, 1));
+        assert_eq!(inverse[1][0], rational(-15, 1));
+        assert_eq!(inverse[1][1], rational(30, 1));
+        let singular = vec![
+            vec![rational(1, 2), rational(1, 3)],
+            vec![rational(3, 2), rational(1, 1)],
+        ];
+        assert_eq!(determinant(&q, &singular).unwrap(), 0);
+        assert_eq!(mat_inverse(&q, &singular).unwrap(), None);
+        assert!(determinant(&q, &matrix[..1]).is_err());
+        assert_eq!(
+            q.div(&rational(3, 4), &rational(-3, 8)),
+            Some(rational(-2, 1))
+        );
+        assert_eq!(q.gcd(&rational(3, 4), &rational(5, 7)), q.one());
+    }
+}
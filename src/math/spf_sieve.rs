@@ -0,0 +1,350 @@
+/*
+smallest prime factor tables and bulk factorization
+
+for experiments over every n <= 10^8 factoring one number at a time is too slow
+SpfTable is a linear sieve, each composite is crossed out exactly once
+by its smallest prime factor, after that factoring any n <= limit
+is just following the spf chain
+
+for ranges that don't start at 0 (or go past what we want to keep in memory)
+factor_range runs a segmented sieve over [a, b] instead
+*/
+use rayon::prelude::*;
+
+use super::factor_u64::isqrt_u64;
+use super::threading::get_large_pool;
+
+// 2^16 numbers per segment, keeps the working set in L2
+const SEGMENT_SIZE: u64 = 1 << 16;
+
+pub struct SpfTable {
+    limit: u64,
+    // only odd numbers are stored, spf[i] is the smallest prime factor of 2i + 1
+    // every even number has 2 as its smallest prime factor
+    spf: Vec<u32>,
+    primes: Vec<u32>,
+}
+
+impl SpfTable {
+    pub fn new(limit: u64) -> Self {
+        if limit > u32::MAX as u64 {
+            panic!("SpfTable limit {} does not fit the u32 storage", limit);
+        }
+        let size = (limit as usize + 1) >> 1;
+        let mut spf = vec![0u32; size.max(1)];
+        let mut primes: Vec<u32> = if limit >= 2 { vec![2] } else { vec![] };
+
+        for i in 1..size {
+            let n = (2 * i + 1) as u32;
+            if spf[i] == 0 {
+                spf[i] = n;
+                primes.push(n);
+            }
+            let spf_n = spf[i];
+            for &p in primes[1..].iter() {
+                if p > spf_n {
+                    break;
+                }
+                let multiple = n as u64 * p as u64;
+                if multiple > limit {
+                    break;
+                }
+                spf[(multiple >> 1) as usize] = p;
+            }
+        }
+
+        Self { limit, spf, primes }
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn primes(&self) -> &[u32] {
+        &self.primes
+    }
+
+    pub fn smallest_prime_factor(&self, n: u64) -> u32 {
+        if n < 2 || n > self.limit {
+            panic!("{} is outside the table [2, {}]", n, self.limit);
+        }
+        if n & 1 == 0 {
+            2
+        } else {
+            self.spf[(n >> 1) as usize]
+        }
+    }
+
+    pub fn is_prime(&self, n: u64) -> bool {
+        n >= 2 && self.smallest_prime_factor(n) as u64 == n
+    }
+
+    pub fn factor(&self, mut n: u64) -> Vec<(u64, u32)> {
+        let mut factors: Vec<(u64, u32)> = Vec::new();
+        while n > 1 {
+            let p = self.smallest_prime_factor(n) as u64;
+            let mut exp = 0;
+            while n.is_multiple_of(p) {
+                n /= p;
+                exp += 1;
+            }
+            factors.push((p, exp));
+        }
+        factors
+    }
+}
+
+fn _factor_segment(low: u64, high: u64, base_primes: &[u32]) -> Vec<Vec<(u64, u32)>> {
+    /*
+    factors every number in [low, high]
+    remaining[i] starts as low + i and every base prime is divided out,
+    whatever is left above 1 is a prime larger than sqrt(high)
+    */
+    let len = (high - low + 1) as usize;
+    let mut remaining: Vec<u64> = (low..=high).collect();
+    let mut factors: Vec<Vec<(u64, u32)>> = vec![Vec::new(); len];
+
+    for &p in base_primes {
+        let p = p as u64;
+        if p * p > high {
+            break;
+        }
+        let mut multiple = low.div_ceil(p) * p;
+        while multiple <= high {
+            let i = (multiple - low) as usize;
+            let mut exp = 0;
+            while remaining[i].is_multiple_of(p) {
+                remaining[i] /= p;
+                exp += 1;
+            }
+            factors[i].push((p, exp));
+            multiple += p;
+        }
+    }
+
+    for (i, rest) in remaining.into_iter().enumerate() {
+        if rest > 1 {
+            factors[i].push((rest, 1));
+        }
+    }
+    factors
+}
+
+fn _segments(a: u64, b: u64) -> Vec<(u64, u64)> {
+    let mut segments = Vec::new();
+    let mut low = a;
+    while low <= b {
+        let high = b.min(low.saturating_add(SEGMENT_SIZE - 1));
+        segments.push((low, high));
+        if high == u64::MAX {
+            break;
+        }
+        low = high + 1;
+    }
+    segments
+}
+
+fn _base_primes(b: u64) -> Vec<u32> {
+    SpfTable::new(isqrt_u64(b).max(2)).primes
+}
+
+pub fn factor_range(a: u64, b: u64) -> Vec<Vec<(u64, u32)>> {
+    /*
+    factorization of every n in [a, b] as sorted (prime, exponent) pairs
+    result[i] is the factorization of a + i, 0 and 1 give an empty vec
+    segments are sieved in parallel
+    */
+    if a > b {
+        panic!("a > b");
+    }
+    let base_primes = _base_primes(b);
+    let segments = _segments(a.max(2), b);
+
+    let pool = get_large_pool();
+    let factored: Vec<Vec<Vec<(u64, u32)>>> = pool.install(|| {
+        segments
+            .par_iter()
+            .map(|(low, high)| _factor_segment(*low, *high, &base_primes))
+            .collect()
+    });
+
+    let mut result: Vec<Vec<(u64, u32)>> =
+        vec![Vec::new(); (a.max(2).min(b.saturating_add(1)) - a) as usize];
+    result.extend(factored.into_iter().flatten());
+    result
+}
+
+pub fn par_for_each_factorization<F>(a: u64, b: u64, f: F)
+where
+    F: Fn(u64, &[(u64, u32)]) + Sync + Send,
+{
+    /*
+    same as factor_range but nothing is collected
+    f is called for every n in [a, b] from the pool threads, in no particular order
+    this is the one to use for ranges that don't fit in memory
+    */
+    if a > b {
+        panic!("a > b");
+    }
+    for n in a..a.max(2).min(b.saturating_add(1)) {
+        f(n, &[]);
+    }
+    let base_primes = _base_primes(b);
+    let segments = _segments(a.max(2), b);
+
+    let pool = get_large_pool();
+    pool.install(|| {
+        segments.par_iter().for_each(|(low, high)| {
+            for (i, factors) in _factor_segment(*low, *high, &base_primes)
+                .iter()
+                .enumerate()
+            {
+                f(low + i as u64, factors);
+            }
+        })
+    });
+}
+
+fn _map_range<T, F>(a: u64, b: u64, empty: T, f: F) -> Vec<T>
+where
+    T: Clone,
+    F: Fn(u64, &[(u64, u32)]) -> T,
+{
+    // applies f to the factorization of every n in [a, b], 0 maps to `empty`
+    factor_range(a, b)
+        .iter()
+        .enumerate()
+        .map(|(i, factors)| {
+            let n = a + i as u64;
+            if n == 0 {
+                empty.clone()
+            } else {
+                f(n, factors)
+            }
+        })
+        .collect()
+}
+
+pub fn mobius_range(a: u64, b: u64) -> Vec<i8> {
+    _map_range(a, b, 0, |_, factors| {
+        if factors.iter().any(|(_, e)| *e > 1) {
+            0
+        } else if factors.len() % 2 == 0 {
+            1
+        } else {
+            -1
+        }
+    })
+}
+
+pub fn totient_range(a: u64, b: u64) -> Vec<u64> {
+    _map_range(a, b, 0, |n, factors| {
+        factors.iter().fold(n, |acc, (p, _)| acc / p * (p - 1))
+    })
+}
+
+pub fn divisor_count_range(a: u64, b: u64) -> Vec<u64> {
+    _map_range(a, b, 0, |_, factors| {
+        factors.iter().map(|(_, e)| *e as u64 + 1).product()
+    })
+}
+
+pub fn divisor_sum_range(a: u64, b: u64) -> Vec<u128> {
+    _map_range(a, b, 0, |_, factors| {
+        factors
+            .iter()
+            .map(|(p, e)| {
+                // 1 + p + ... + p^e
+                let p = *p as u128;
+                (0..*e).fold(1u128, |acc, _| acc * p + 1)
+            })
+            .product()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::factor_u64::factor_u64;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn test_spf_table() {
+        let table = SpfTable::new(100);
+        assert_eq!(table.primes().len(), 25);
+        assert_eq!(table.smallest_prime_factor(2), 2);
+        assert_eq!(table.smallest_prime_factor(91), 7);
+        assert_eq!(table.smallest_prime_factor(97), 97);
+        assert_eq!(table.smallest_prime_factor(100), 2);
+        assert!(table.is_prime(89));
+        assert!(!table.is_prime(99));
+        assert!(table.factor(1).is_empty());
+        assert_eq!(table.factor(72), vec![(2, 3), (3, 2)]);
+        assert_eq!(table.factor(99), vec![(3, 2), (11, 1)]);
+    }
+
+    #[test]
+    fn test_spf_table_matches_factor_u64() {
+        let table = SpfTable::new(100_000);
+        assert_eq!(table.primes().len(), 9592);
+        for n in (2..=100_000).step_by(7) {
+            assert_eq!(table.factor(n), factor_u64(n), "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn test_factor_range() {
+        let result = factor_range(0, 12);
+        assert_eq!(result.len(), 13);
+        assert!(result[0].is_empty());
+        assert!(result[1].is_empty());
+        assert_eq!(result[12], vec![(2, 2), (3, 1)]);
+        // one entry per n even when the range ends below 2
+        let empty: Vec<(u64, u32)> = Vec::new();
+        assert_eq!(factor_range(0, 0), vec![empty.clone()]);
+        assert_eq!(factor_range(0, 1), vec![empty.clone(), empty.clone()]);
+        assert_eq!(factor_range(1, 1), vec![empty.clone()]);
+        assert_eq!(factor_range(1, 2), vec![empty, vec![(2, 1)]]);
+
+        let a = 1_000_000_000_000u64;
+        let b = a + 20_000;
+        for (i, factors) in factor_range(a, b).iter().enumerate() {
+            assert_eq!(*factors, factor_u64(a + i as u64));
+        }
+    }
+
+    #[test]
+    fn test_par_for_each_factorization() {
+        let count = AtomicU64::new(0);
+        let sum = AtomicU64::new(0);
+        par_for_each_factorization(1, 100_000, |n, factors| {
+            count.fetch_add(1, Ordering::Relaxed);
+            let product: u64 = factors.iter().map(|(p, e)| p.pow(*e)).product();
+            assert_eq!(product, n);
+            sum.fetch_add(n, Ordering::Relaxed);
+        });
+        assert_eq!(count.into_inner(), 100_000);
+        assert_eq!(sum.into_inner(), 100_000 * 100_001 / 2);
+    }
+
+    #[test]
+    fn test_arithmetic_functions_over_ranges() {
+        assert_eq!(
+            mobius_range(1, 12),
+            vec![1, -1, -1, 0, -1, 1, -1, 0, 0, 1, -1, 0]
+        );
+        assert_eq!(
+            totient_range(1, 12),
+            vec![1, 1, 2, 2, 4, 2, 6, 4, 6, 4, 10, 4]
+        );
+        assert_eq!(
+            divisor_count_range(1, 12),
+            vec![1, 2, 2, 3, 2, 4, 2, 4, 3, 4, 2, 6]
+        );
+        assert_eq!(
+            divisor_sum_range(1, 12),
+            vec![1, 3, 4, 7, 6, 12, 8, 15, 13, 18, 12, 28]
+        );
+        assert_eq!(mobius_range(0, 1), vec![0, 1]);
+    }
+}
@@ -0,0 +1,377 @@
+/*
+square roots mod n, the x with x^2 = a mod n
+
+prime p: p = 3 mod 4 is a single power a^((p + 1) / 4), otherwise
+    Tonelli-Shanks, p - 1 = 2^s t, O(s^2) multiplications on top of the powering
+    Cipolla, powering t + w in F_p(w) with w^2 = t^2 - a a non-residue, O(log p)
+Tonelli-Shanks is used unless s is large compared to the size of p
+
+prime powers: a = p^k u with u a unit, k has to be even, the roots are
+p^(k / 2) y with y^2 = u mod p^(e - k) and y free mod p^(e - k / 2)
+y mod p^(e - k) comes from newton's iteration y -> y - (y^2 - u) / 2y for odd p,
+and bit by bit for p = 2 where there are 4 roots once e - k >= 3
+
+composite n: every combination of the roots mod the prime powers glued with the CRT,
+as residue classes so a single root costs no more than the number of classes
+*/
+use rug::ops::Pow;
+use rug::Integer;
+
+use super::chineese_remainder_theorem::{expand_classes, CrtContext};
+use super::factorization::{factorint, is_probable_prime};
+use super::num_utils::pow_mod;
+use super::quadratic_residue::jacobi;
+
+pub fn tonelli_shanks(a: &Integer, p: &Integer) -> Option<Integer> {
+    // a root of a mod the odd prime p, None for a non-residue
+    let a = a.clone().modulo(p);
+    if a == 0 {
+        return Some(a);
+    }
+    if jacobi(&a, p).unwrap() != 1 {
+        return None;
+    }
+    let p_minus_1 = Integer::from(p - 1u32);
+    let s = p_minus_1.find_one(0).unwrap();
+    let t = Integer::from(&p_minus_1 >> s);
+
+    let mut z = Integer::from(2);
+    while jacobi(&z, p).unwrap() != -1 {
+        z += 1;
+    }
+    /*
+    invariants: c^(2^(m - 1)) = -1, r^2 = a b, b^(2^(m - 1)) = 1
+    every round halves the order of b
+    */
+    let mut m = s;
+    let mut c = pow_mod(&z, &t, p);
+    let mut b = pow_mod(&a, &t, p);
+    let mut r = pow_mod(&a, &(Integer::from(&t + 1u32) >> 1), p);
+    while b != 1 {
+        let mut i = 0;
+        let mut power = b.clone();
+        while power != 1 {
+            power = power.square().modulo(p);
+            i += 1;
+        }
+        let mut factor = c;
+        for _ in 0..m - i - 1 {
+            factor = factor.square().modulo(p);
+        }
+        m = i;
+        c = Integer::from(factor.square_ref()).modulo(p);
+        b = (b * &c).modulo(p);
+        r = (r * factor).modulo(p);
+    }
+    Some(r)
+}
+
+pub fn cipolla(a: &Integer, p: &Integer) -> Option<Integer> {
+    // a root of a mod the odd prime p through F_p(sqrt(t^2 - a)), None for a non-residue
+    let a = a.clone().modulo(p);
+    if a == 0 {
+        return Some(a);
+    }
+    if jacobi(&a, p).unwrap() != 1 {
+        return None;
+    }
+    let mut t = Integer::from(1);
+    let mut w_squared = (Integer::from(t.square_ref()) - &a).modulo(p);
+    while jacobi(&w_squared, p).unwrap() != -1 {
+        t += 1;
+        w_squared = (Integer::from(t.square_ref()) - &a).modulo(p);
+    }
+    /* (x0 + x1 w)(y0 + y1 w) = x0 y0 + x1 y1 w^2 + (x0 y1 + x1 y0) w */
+    let multiply = |x: &(Integer, Integer), y: &(Integer, Integer)| -> (Integer, Integer) {
+        let real = (Integer::from(&x.0 * &y.0) + Integer::from(&x.1 * &y.1) * &w_squared).modulo(p);
+        let imaginary = (Integer::from(&x.0 * &y.1) + Integer::from(&x.1 * &y.0)).modulo(p);
+        (real, imaginary)
+    };
+    let exponent: Integer = Integer::from(p + 1u32) >> 1;
+    let mut result = (Integer::from(1), Integer::new());
+    let mut base = (t, Integer::from(1));
+    for i in 0..exponent.significant_bits() {
+        if exponent.get_bit(i) {
+            result = multiply(&result, &base);
+        }
+        base = multiply(&base, &base);
+    }
+    Some(result.0)
+}
+
+pub fn sqrt_mod_prime(a: &Integer, p: &Integer) -> Option<Integer> {
+    // some root of a mod the prime p
+    if *p == 2 {
+        return Some(a.clone().modulo(p));
+    }
+    if p.mod_u(4) == 3 {
+        let a = a.clone().modulo(p);
+        let root = pow_mod(&a, &(Integer::from(p + 1u32) >> 2), p);
+        return if Integer::from(root.square_ref()).modulo(p) == a {
+            Some(root)
+        } else {
+            None
+        };
+    }
+    /* Tonelli-Shanks pays s^2, Cipolla about 2 log p multiplications in F_p^2 */
+    let s = Integer::from(p - 1u32).find_one(0).unwrap();
+    if (s * s) as usize > 2 * p.significant_bits() as usize {
+        cipolla(a, p)
+    } else {
+        tonelli_shanks(a, p)
+    }
+}
+
+fn _unit_roots(u: &Integer, p: &Integer, f: u32) -> Vec<Integer> {
+    // y mod p^f with y^2 = u, u a unit, f >= 1
+    let pf = p.clone().pow(f);
+    if *p == 2 {
+        let u = u.clone().modulo(&pf);
+        return match f {
+            1 => vec![Integer::from(1)],
+            2 if u == 1 => vec![Integer::from(1), Integer::from(3)],
+            2 => Vec::new(),
+            _ if u.mod_u(8) != 1 => Vec::new(),
+            _ => {
+                /* fix one more bit at a time, y^2 = u mod 2^(j + 1) from mod 2^j */
+                let mut y = Integer::from(1);
+                for j in 3..f {
+                    let square = Integer::from(y.square_ref());
+                    if !Integer::from(&square - &u).is_divisible_2pow(j + 1) {
+                        y += Integer::from(1) << (j - 1);
+                    }
+                }
+                let half = Integer::from(1) << (f - 1);
+                let minus_y = Integer::from(&pf - &y);
+                let mut roots = vec![
+                    y.clone(),
+                    (Integer::from(&y + &half)).modulo(&pf),
+                    minus_y.clone(),
+                    (minus_y + &half).modulo(&pf),
+                ];
+                roots.sort();
+                roots.dedup();
+                roots
+            }
+        };
+    }
+    let mut y = match sqrt_mod_prime(u, p) {
+        Some(y) => y,
+        None => return Vec::new(),
+    };
+    /* newton doubles the precision every step */
+    let mut precision = 1u32;
+    while precision < f {
+        precision = (2 * precision).min(f);
+        let modulus = p.clone().pow(precision);
+        let inverse = Integer::from(&y << 1).invert(&modulus).unwrap();
+        let error = Integer::from(y.square_ref()) - u;
+        y = (&y - error * inverse).modulo(&modulus);
+    }
+    let minus_y = Integer::from(&pf - &y).modulo(&pf);
+    let mut roots = vec![y, minus_y];
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+fn _root_classes(a: &Integer, p: &Integer, e: u32) -> (Vec<Integer>, Integer) {
+    /*
+    the roots of a mod p^e as residue classes mod m, m a divisor of p^e
+    a = 0: the multiples of p^ceil(e / 2), one class
+    a = p^k u: scale y with y^2 = u mod p^f, f = e - k, scale = p^(k / 2),
+    y is free mod p^(e - k / 2) so scale y is free mod m = scale p^f
+    the classes are sorted and each is its own smallest member
+    */
+    let pe = p.clone().pow(e);
+    let mut u = a.clone().modulo(&pe);
+    if u == 0 {
+        return (vec![Integer::new()], p.clone().pow(e.div_ceil(2)));
+    }
+    let k = u.remove_factor_mut(p);
+    if k % 2 == 1 {
+        return (Vec::new(), pe);
+    }
+    let f = e - k;
+    let scale = p.clone().pow(k / 2);
+    let m = &scale * p.clone().pow(f);
+    let classes = _unit_roots(&u, p, f)
+        .into_iter()
+        .map(|y| y * &scale)
+        .collect();
+    (classes, m)
+}
+
+pub fn sqrt_mod_prime_power(a: &Integer, p: &Integer, e: u32, all_roots: bool) -> Vec<Integer> {
+    // every root of a mod p^e sorted, with all_roots false just the smallest one
+    let (classes, m) = _root_classes(a, p, e);
+    if !all_roots {
+        return classes.into_iter().take(1).collect();
+    }
+    expand_classes(&classes, &m, &p.clone().pow(e))
+}
+
+pub fn sqrt_mod(a: &Integer, n: &Integer, all_roots: bool) -> Result<Vec<Integer>, String> {
+    /*
+    roots of x^2 = a mod n sorted, with all_roots false just the smallest one
+    like sympy's sqrt_mod, an empty vec when a is not a square
+    the classes of every prime power are glued with the CRT before expanding,
+    so the smallest root never needs the others
+    */
+    if *n <= 0 {
+        return Err("n must be > 0".to_string());
+    }
+    let a = a.clone().modulo(n);
+    if *n == 1 {
+        return Ok(vec![Integer::new()]);
+    }
+    if is_probable_prime(n) {
+        let mut roots = match sqrt_mod_prime(&a, n) {
+            Some(root) => {
+                let other = Integer::from(n - &root).modulo(n);
+                vec![root, other]
+            }
+            None => Vec::new(),
+        };
+        roots.sort();
+        roots.dedup();
+        if !all_roots {
+            roots.truncate(1);
+        }
+        return Ok(roots);
+    }
+    let mut local = Vec::new();
+    let mut moduli = Vec::new();
+    for (p, e) in factorint(n).factors().iter() {
+        let (classes, m) = _root_classes(&a, p, *e);
+        if classes.is_empty() {
+            return Ok(Vec::new());
+        }
+        local.push(classes);
+        moduli.push(m);
+    }
+    let context = CrtContext::new(&moduli)?;
+    let mut classes = context.combinations(&local)?;
+    if !all_roots {
+        classes.truncate(1);
+        return Ok(classes);
+    }
+    Ok(expand_classes(&classes, context.modulus(), n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_roots(a: u64, n: u64) -> Vec<Integer> {
+        (0..n)
+            .filter(|x| (x * x) % n == a % n)
+            .map(Integer::from)
+            .collect()
+    }
+
+    #[test]
+    fn test_prime_roots() {
+        // p = 1 mod 2^k for a few k, both algorithms on every residue
+        for p in [5u64, 13, 17, 41, 97, 193, 257, 7681, 12_289, 65_537] {
+            let p_int = Integer::from(p);
+            for a in 0..p.min(500) {
+                let a_int = Integer::from(a);
+                let expected = !brute_roots(a, p).is_empty();
+                for root in [tonelli_shanks(&a_int, &p_int), cipolla(&a_int, &p_int)] {
+                    assert_eq!(root.is_some(), expected, "a = {}, p = {}", a, p);
+                    if let Some(root) = root {
+                        assert_eq!(Integer::from(root.square_ref()).modulo(&p_int), a);
+                    }
+                }
+            }
+        }
+        // 3 * 2^30 + 1 has s = 30, 2^127 - 1 is 3 mod 4
+        for p in [
+            Integer::from(3 * (1u64 << 30) + 1),
+            (Integer::from(1) << 127) - 1u32,
+            Integer::from(1_000_000_007u64),
+        ] {
+            for a in [2u32, 3, 5, 7, 10, 12_345] {
+                let a = Integer::from(a);
+                if let Some(root) = sqrt_mod_prime(&a, &p) {
+                    assert_eq!(Integer::from(root.square_ref()).modulo(&p), a);
+                } else {
+                    assert_eq!(jacobi(&a, &p).unwrap(), -1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sqrt_mod_matches_brute_force() {
+        for n in 1..400u64 {
+            for a in 0..n {
+                let expected = brute_roots(a, n);
+                let got = sqrt_mod(&Integer::from(a), &Integer::from(n), true).unwrap();
+                assert_eq!(got, expected, "a = {}, n = {}", a, n);
+                let smallest = sqrt_mod(&Integer::from(a), &Integer::from(n), false).unwrap();
+                assert_eq!(smallest, expected.into_iter().take(1).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn test_sqrt_mod() {
+        let roots = |a: i64, n: u64| -> Vec<Integer> {
+            sqrt_mod(&Integer::from(a), &Integer::from(n), true).unwrap()
+        };
+        assert_eq!(roots(2, 7), vec![3, 4]);
+        assert_eq!(roots(0, 16), vec![0, 4, 8, 12]);
+        assert_eq!(roots(9, 27), vec![3, 6, 12, 15, 21, 24]);
+        assert_eq!(roots(17, 32), vec![7, 9, 23, 25]);
+        assert_eq!(roots(-1, 13), vec![5, 8]);
+        assert_eq!(roots(7, 1_000_000_007), vec![21_125_790, 978_874_217]);
+        assert_eq!(roots(3, 7), Vec::<Integer>::new());
+        assert!(sqrt_mod(&Integer::from(3), &Integer::from(0), false).is_err());
+
+        // a big prime power and a product of two big primes
+        let p = Integer::from(1_000_000_009u64);
+        let x = Integer::from(123_456_789u64);
+        let n = p.clone().pow(5);
+        let a = Integer::from(x.square_ref()).modulo(&n);
+        let found = sqrt_mod(&a, &n, true).unwrap();
+        assert_eq!(found.len(), 2);
+        assert!(found.contains(&x));
+        let n = Integer::from(&p * 1_000_000_007u64);
+        let a = Integer::from(x.square_ref()).modulo(&n);
+        let found = sqrt_mod(&a, &n, true).unwrap();
+        assert_eq!(found.len(), 4);
+        for root in found.iter() {
+            assert_eq!(Integer::from(root.square_ref()).modulo(&n), a);
+        }
+    }
+
+    #[test]
+    fn test_single_root_of_large_prime_powers() {
+        // 2^30 and 3^15 roots, a single one must not list them
+        let n = Integer::from(1) << 60;
+        assert_eq!(sqrt_mod(&Integer::new(), &n, false).unwrap(), vec![0]);
+        let three = Integer::from(3);
+        let n = three.clone().pow(40);
+        let a = three.clone().pow(30);
+        assert_eq!(
+            sqrt_mod(&a, &n, false).unwrap(),
+            vec![three.clone().pow(15)]
+        );
+        assert_eq!(
+            sqrt_mod_prime_power(&a, &three, 40, false),
+            vec![three.clone().pow(15)]
+        );
+        assert_eq!(
+            sqrt_mod_prime_power(&Integer::new(), &three, 8, true).len(),
+            81
+        );
+
+        // the smallest root of a composite modulus is still the smallest
+        let n = Integer::from(1_000_000_007u64) * three.clone().pow(40);
+        let a = Integer::from(4) * three.clone().pow(30);
+        let smallest = sqrt_mod(&a, &n, false).unwrap();
+        assert_eq!(smallest, vec![Integer::from(2) * three.pow(15)]);
+    }
+}
@@ -0,0 +1,312 @@
+/*
+sums of arithmetic functions up to x without touching every n <= x
+
+mertens and totient_sum use the Dirichlet hyperbola identity
+    f * 1 = g  =>  sum over d <= v of F(v / d) = G(v)
+with F, G the summatory functions, mu * 1 = e gives G = 1 and phi * 1 = id
+gives G = v (v + 1) / 2. F(v) = G(v) - sum over d >= 2 of F(v / d), the v / d
+only take O(sqrt v) values so they are grouped. values up to L ~ x^(2/3) come from
+the linear sieve prefix, the ones above are the x / k and are memoized by k
+so the total is O(x^(2/3))
+
+prime_sum and prime_power_sum are Lucy_Hedgehog's sieve, S(v, p) is the sum over
+n <= v that are prime or have no prime factor <= p, going from p - 1 to p
+    S(v, p) = S(v, p - 1) - f(p) (S(v / p, p - 1) - S(p - 1, p - 1))
+for a completely multiplicative f, again only the values x / k, O(x^(3/4))
+*/
+use rug::ops::Pow;
+use rug::{Complete, Integer, Rational};
+
+use super::factor_u64::isqrt_u64;
+use super::linear_sieve::{mobius_table, totient_table};
+
+// the sieve part is capped at 2^24 values, past x ~ 7 * 10^10 the large part does more work
+const SIEVE_CAP: u64 = 1 << 24;
+
+fn _sieve_limit(x: u64) -> u64 {
+    let l = (x as f64).powf(2.0 / 3.0) as u64;
+    let root = isqrt_u64(x) + 1;
+    l.min(SIEVE_CAP).max(root).min(x)
+}
+
+fn _hyperbola_summatory<G>(x: u64, prefix: &[i64], g: G) -> i128
+where
+    G: Fn(u64) -> i128,
+{
+    /*
+    F(x) from F(v) = G(v) - sum over 2 <= d <= v of F(v / d)
+    prefix[v] = F(v) for every v < prefix.len(), large[k] = F(x / k) above that
+    */
+    let l = prefix.len() as u64 - 1;
+    if x <= l {
+        return prefix[x as usize] as i128;
+    }
+    let k_max = x / (l + 1);
+    let mut large = vec![0i128; k_max as usize + 1];
+    for k in (1..=k_max).rev() {
+        let v = x / k;
+        let mut total = g(v);
+        let mut d = 2;
+        while d <= v {
+            let q = v / d;
+            let d_next = v / q + 1;
+            let f_q = if q <= l {
+                prefix[q as usize] as i128
+            } else {
+                // q = x / (k d) > l, so k d <= k_max
+                large[(k * d) as usize]
+            };
+            total -= (d_next - d) as i128 * f_q;
+            d = d_next;
+        }
+        large[k as usize] = total;
+    }
+    large[1]
+}
+
+pub fn mertens(x: u64) -> i64 {
+    // M(x) = sum of mu(n) for n <= x
+    let mobius = mobius_table(_sieve_limit(x));
+    let mut prefix = Vec::with_capacity(mobius.len());
+    let mut running = 0i64;
+    for mu in mobius {
+        running += mu as i64;
+        prefix.push(running);
+    }
+    _hyperbola_summatory(x, &prefix, |_| 1) as i64
+}
+
+pub fn totient_sum(x: u64) -> u128 {
+    // Phi(x) = sum of phi(n) for n <= x
+    let totient = totient_table(_sieve_limit(x));
+    let mut prefix = Vec::with_capacity(totient.len());
+    let mut running = 0i64;
+    for phi in totient {
+        running += phi as i64;
+        prefix.push(running);
+    }
+    _hyperbola_summatory(x, &prefix, |v| {
+        let v = v as i128;
+        v * (v + 1) / 2
+    }) as u128
+}
+
+pub fn prime_sum(x: u64) -> u128 {
+    // sum of the primes p <= x
+    if x < 2 {
+        return 0;
+    }
+    let r = isqrt_u64(x);
+    let triangle = |v: u64| (v as u128) * (v as u128 + 1) / 2;
+    // small[v] = S(v) for v <= r, large[k] = S(x / k) for k <= r, 1 is not counted
+    let mut small: Vec<u128> = (0..=r).map(|v| triangle(v).saturating_sub(1)).collect();
+    let mut large: Vec<u128> = (0..=r)
+        .map(|k| x.checked_div(k).map_or(0, |v| triangle(v) - 1))
+        .collect();
+
+    for p in 2..=r {
+        if small[p as usize] == small[p as usize - 1] {
+            continue;
+        }
+        let before = small[p as usize - 1];
+        let p_squared = p * p;
+        for k in 1..=r {
+            let v = x / k;
+            if v < p_squared {
+                break;
+            }
+            let kp = k * p;
+            let below = if kp <= r {
+                large[kp as usize]
+            } else {
+                small[(x / kp) as usize]
+            };
+            large[k as usize] -= p as u128 * (below - before);
+        }
+        for v in (p_squared..=r).rev() {
+            small[v as usize] -= p as u128 * (small[(v / p) as usize] - before);
+        }
+    }
+    large[1]
+}
+
+fn _power_sum_coefficients(k: u32) -> (Vec<Integer>, Integer) {
+    /*
+    Faulhaber: sum of i^k for i <= v is
+        1 / (k + 1) sum over j <= k of (-1)^j C(k + 1, j) B_j v^(k + 1 - j)
+    returns the coefficients of v^0 .. v^(k + 1) over a common denominator
+    */
+    let k = k as usize;
+    let mut bernoulli: Vec<Rational> = vec![Rational::from(1)];
+    for m in 1..=k {
+        // B_m = -1 / (m + 1) sum over j < m of C(m + 1, j) B_j
+        let mut total = Rational::new();
+        for (j, b) in bernoulli.iter().enumerate() {
+            total += Integer::binomial_u(m as u32 + 1, j as u32).complete() * b.clone();
+        }
+        bernoulli.push(-total / Integer::from(m + 1));
+    }
+
+    let mut coefficients = vec![Rational::new(); k + 2];
+    for (j, b) in bernoulli.iter().enumerate() {
+        let mut term = Integer::binomial_u(k as u32 + 1, j as u32).complete() * b.clone()
+            / Integer::from(k + 1);
+        if j % 2 == 1 {
+            term = -term;
+        }
+        coefficients[k + 1 - j] = term;
+    }
+    let denominator = coefficients
+        .iter()
+        .fold(Integer::from(1), |acc, c| acc.lcm(c.denom()));
+    let numerators = coefficients
+        .iter()
+        .map(|c| c.numer() * (&denominator / c.denom()).complete())
+        .collect();
+    (numerators, denominator)
+}
+
+fn _power_sum(v: u64, coefficients: &(Vec<Integer>, Integer)) -> Integer {
+    let (numerators, denominator) = coefficients;
+    let mut total = Integer::new();
+    for c in numerators.iter().rev() {
+        total *= v;
+        total += c;
+    }
+    total / denominator
+}
+
+pub fn prime_power_sum(x: u64, k: u32) -> Integer {
+    /*
+    sum of p^k over the primes p <= x, k = 0 counts them
+    same sieve as prime_sum on Integers, the start values are power sums
+    */
+    if x < 2 {
+        return Integer::new();
+    }
+    let r = isqrt_u64(x);
+    let coefficients = _power_sum_coefficients(k);
+    let start = |v: u64| -> Integer {
+        if v == 0 {
+            Integer::new()
+        } else {
+            _power_sum(v, &coefficients) - 1u32
+        }
+    };
+    let mut small: Vec<Integer> = (0..=r).map(start).collect();
+    let mut large: Vec<Integer> = (0..=r)
+        .map(|i| x.checked_div(i).map_or_else(Integer::new, &start))
+        .collect();
+
+    for p in 2..=r {
+        if small[p as usize] == small[p as usize - 1] {
+            continue;
+        }
+        let before = small[p as usize - 1].clone();
+        let weight = Integer::from(p).pow(k);
+        let p_squared = p * p;
+        for i in 1..=r {
+            let v = x / i;
+            if v < p_squared {
+                break;
+            }
+            let ip = i * p;
+            let below = if ip <= r {
+                &large[ip as usize]
+            } else {
+                &small[(x / ip) as usize]
+            };
+            let delta = (below - &before).complete() * &weight;
+            large[i as usize] -= delta;
+        }
+        for v in (p_squared..=r).rev() {
+            let delta = (&small[(v / p) as usize] - &before).complete() * &weight;
+            small[v as usize] -= delta;
+        }
+    }
+    large.swap_remove(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::linear_sieve::linear_sieve;
+    use crate::math::spf_sieve::SpfTable;
+
+    #[test]
+    fn test_mertens() {
+        let table = linear_sieve(100_000);
+        let mut running = 0i64;
+        for x in 1..=100_000u64 {
+            running += table.mobius()[x as usize] as i64;
+            if x % 997 == 0 || x < 50 {
+                assert_eq!(mertens(x), running, "x = {}", x);
+            }
+        }
+        assert_eq!(mertens(0), 0);
+        assert_eq!(mertens(1_000_000), 212);
+        assert_eq!(mertens(1_000_000_000), -222);
+    }
+
+    #[test]
+    fn test_totient_sum() {
+        let table = linear_sieve(100_000);
+        let mut running = 0u128;
+        for x in 1..=100_000u64 {
+            running += table.totient()[x as usize] as u128;
+            if x % 997 == 0 || x < 50 {
+                assert_eq!(totient_sum(x), running, "x = {}", x);
+            }
+        }
+        assert_eq!(totient_sum(1_000_000), 303_963_552_392);
+        assert_eq!(totient_sum(1_000_000_000), 303_963_551_173_008_414);
+    }
+
+    #[test]
+    fn test_prime_sum() {
+        assert_eq!(prime_sum(1), 0);
+        assert_eq!(prime_sum(2), 2);
+        assert_eq!(prime_sum(10), 17);
+        assert_eq!(prime_sum(2_000_000), 142_913_828_922);
+
+        let table = SpfTable::new(50_000);
+        for x in (0..=50_000u64).step_by(1013) {
+            let expected: u128 = table
+                .primes()
+                .iter()
+                .filter(|p| (**p as u64) <= x)
+                .map(|p| *p as u128)
+                .sum();
+            assert_eq!(prime_sum(x), expected);
+        }
+    }
+
+    #[test]
+    fn test_power_sum() {
+        for k in 0..6u32 {
+            let coefficients = _power_sum_coefficients(k);
+            for v in 0..30u64 {
+                let expected: Integer = (1..=v).map(|i| Integer::from(i).pow(k)).sum();
+                assert_eq!(_power_sum(v, &coefficients), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_prime_power_sum() {
+        assert_eq!(prime_power_sum(1_000_000, 0), 78_498);
+        assert_eq!(prime_power_sum(2_000_000, 1), prime_sum(2_000_000));
+        let table = SpfTable::new(10_000);
+        for k in [2u32, 3, 7] {
+            for x in [2u64, 3, 100, 9_973, 10_000] {
+                let expected: Integer = table
+                    .primes()
+                    .iter()
+                    .filter(|p| (**p as u64) <= x)
+                    .map(|p| Integer::from(*p).pow(k))
+                    .sum();
+                assert_eq!(prime_power_sum(x, k), expected, "x = {}, k = {}", x, k);
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+/*
+totient style functions for arbitrary n, all of them go through factorint
+so they are as fast as the factorization of n
+
+inverse_totient goes the other way, every n with phi(n) = m
+phi(n) is the product of phi(p^k) over the prime powers of n, so the candidates are
+primes p with (p - 1) | m. those are combined one prime at a time keeping a table
+partial product of phi -> the n that reach it, only divisors of m are kept
+*/
+use std::collections::HashMap;
+
+use rug::ops::Pow;
+use rug::{Complete, Integer};
+
+use super::factorization::{factorint, is_probable_prime};
+
+fn _check_positive(n: &Integer) -> Result<(), String> {
+    if n.cmp0() != std::cmp::Ordering::Greater {
+        return Err("n should be a positive integer".to_string());
+    }
+    Ok(())
+}
+
+pub fn totient(n: &Integer) -> Result<Integer, String> {
+    _check_positive(n)?;
+    Ok(factorint(n).totient())
+}
+
+pub fn carmichael_lambda(n: &Integer) -> Result<Integer, String> {
+    _check_positive(n)?;
+    Ok(factorint(n).carmichael_lambda())
+}
+
+pub fn reduced_totient(n: &Integer) -> Result<Integer, String> {
+    // sympy's name for the carmichael function
+    carmichael_lambda(n)
+}
+
+pub fn jordan_totient(k: u32, n: &Integer) -> Result<Integer, String> {
+    /*
+    J_k(n) = n^k prod over p | n of (1 - p^-k), J_1 is phi
+    per prime power J_k(p^e) = p^(k(e - 1)) (p^k - 1)
+    */
+    _check_positive(n)?;
+    if k == 0 {
+        return Ok(Integer::from(*n == 1));
+    }
+    Ok(factorint(n)
+        .factors()
+        .iter()
+        .map(|(p, e)| {
+            let pk = p.clone().pow(k);
+            pk.clone().pow(e - 1) * (pk - 1u32)
+        })
+        .product())
+}
+
+pub fn inverse_totient(m: &Integer) -> Result<Vec<Integer>, String> {
+    // every n with phi(n) = m, sorted
+    _check_positive(m)?;
+    if *m == 1 {
+        return Ok(vec![Integer::from(1), Integer::from(2)]);
+    }
+    if m.is_odd() {
+        return Ok(Vec::new());
+    }
+
+    let divisors: Vec<Integer> = factorint(m).divisors().collect();
+    let mut candidates: Vec<Integer> = divisors
+        .iter()
+        .map(|d| (d + 1u32).complete())
+        .filter(is_probable_prime)
+        .collect();
+    // large primes first, their powers cut the table down the most
+    candidates.reverse();
+
+    let mut reachable: HashMap<Integer, Vec<Integer>> = HashMap::new();
+    reachable.insert(Integer::from(1), vec![Integer::from(1)]);
+    for p in candidates.iter() {
+        let mut updates: Vec<(Integer, Vec<Integer>)> = Vec::new();
+        for (phi, values) in reachable.iter() {
+            // phi(p^k) = p^(k - 1) (p - 1)
+            let mut phi_pk = (p - 1u32).complete();
+            let mut pk = p.clone();
+            loop {
+                let next_phi = (phi * &phi_pk).complete();
+                if !m.is_divisible(&next_phi) {
+                    break;
+                }
+                updates.push((
+                    next_phi,
+                    values.iter().map(|v| (v * &pk).complete()).collect(),
+                ));
+                phi_pk *= p;
+                pk *= p;
+            }
+        }
+        for (phi, values) in updates {
+            reachable.entry(phi).or_default().extend(values);
+        }
+    }
+
+    let mut result = reachable.remove(m).unwrap_or_default();
+    result.sort();
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::linear_sieve::linear_sieve;
+
+    #[test]
+    fn test_totient_and_lambda() {
+        let expected = [
+            (1u32, 1u32, 1u32),
+            (2, 1, 1),
+            (8, 4, 2),
+            (12, 4, 2),
+            (100, 40, 20),
+        ];
+        for (n, phi, lambda) in expected {
+            assert_eq!(totient(&Integer::from(n)).unwrap(), phi);
+            assert_eq!(reduced_totient(&Integer::from(n)).unwrap(), lambda);
+        }
+        // 561 is a carmichael number, lambda(561) = 80 divides 560
+        assert_eq!(carmichael_lambda(&Integer::from(561)).unwrap(), 80);
+        assert!(totient(&Integer::from(0)).is_err());
+        assert!(carmichael_lambda(&Integer::from(-5)).is_err());
+
+        let n = Integer::from(10).pow(20);
+        assert_eq!(
+            totient(&n).unwrap(),
+            Integer::from(4) * Integer::from(10).pow(19)
+        );
+        assert_eq!(
+            carmichael_lambda(&n).unwrap(),
+            Integer::from(5).pow(19) << 18
+        );
+    }
+
+    #[test]
+    fn test_totient_matches_sieve() {
+        let table = linear_sieve(5000);
+        for n in 1..=5000u32 {
+            assert_eq!(
+                totient(&Integer::from(n)).unwrap(),
+                table.totient()[n as usize]
+            );
+        }
+    }
+
+    #[test]
+    fn test_jordan_totient() {
+        // J_2 is oeis A007434
+        let expected = [1u32, 3, 8, 12, 24, 24, 48, 48, 72, 72, 120, 96];
+        for (i, value) in expected.iter().enumerate() {
+            let n = Integer::from(i + 1);
+            assert_eq!(jordan_totient(2, &n).unwrap(), *value);
+            assert_eq!(jordan_totient(1, &n).unwrap(), totient(&n).unwrap());
+        }
+        assert_eq!(jordan_totient(0, &Integer::from(1)).unwrap(), 1);
+        assert_eq!(jordan_totient(0, &Integer::from(7)).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_inverse_totient() {
+        let values = |m: u32| -> Vec<u32> {
+            inverse_totient(&Integer::from(m))
+                .unwrap()
+                .iter()
+                .map(|v| v.to_u32().unwrap())
+                .collect()
+        };
+        assert_eq!(values(1), vec![1, 2]);
+        assert_eq!(values(4), vec![5, 8, 10, 12]);
+        assert_eq!(values(8), vec![15, 16, 20, 24, 30]);
+        assert_eq!(values(14), Vec::<u32>::new());
+        assert_eq!(values(7), Vec::<u32>::new());
+
+        // every n <= 20000 shows up in the inverse of its totient
+        let table = linear_sieve(20_000);
+        for m in (2..=480u64).step_by(2) {
+            let expected: Vec<u64> = (1..=20_000u64)
+                .filter(|n| table.totient()[*n as usize] == m)
+                .collect();
+            let got: Vec<u64> = inverse_totient(&Integer::from(m))
+                .unwrap()
+                .iter()
+                .map(|v| v.to_u64().unwrap())
+                .collect();
+            assert_eq!(got, expected, "m = {}", m);
+        }
+    }
+}
@@ -4,6 +4,16 @@ from manifold_rs import (
     miller_rabin_bool_multiple,
     power_of_two_exponent_10n_py,
     chinese_remainder_theorem_py,
+    is_perfect_power_py,
+    iroot_py,
+    batch_gcd_py,
+    totient_py,
+    reduced_totient_py,
+    n_order_py,
+    primitive_root_py,
+    sqrt_mod_py,
+    nthroot_mod_py,
+    polynomial_congruence_py,
 )
 
 
@@ -46,3 +56,82 @@ def test_chinese_remainder_theorem_py():
         [2,3,2], [3,5,7]
     )
     assert res == "23"
+
+
+def test_is_perfect_power_py():
+    assert is_perfect_power_py(64) == (2, 6)
+    assert is_perfect_power_py(-27) == (-3, 3)
+    assert is_perfect_power_py(12) is None
+    assert is_perfect_power_py(3**200) == (3, 200)
+
+
+def test_iroot_py():
+    assert iroot_py(30, 3) == (3, 3)
+    assert iroot_py(10**40 + 1, 2) == (10**20, 1)
+
+
+def test_batch_gcd_py():
+    p, q, r = 1000003, 999983, 999961
+    assert batch_gcd_py([p * q, p * r, 999979 * 999953]) == [p, p, 1]
+    assert batch_gcd_py([]) == []
+    with pytest.raises(ValueError):
+        batch_gcd_py([3, 0])
+
+
+def test_totient_py():
+    assert [totient_py(n) for n in [1, 2, 8, 12, 100]] == [1, 1, 4, 4, 40]
+    assert totient_py(10**20) == 4 * 10**19
+    with pytest.raises(ValueError):
+        totient_py(0)
+
+
+def test_reduced_totient_py():
+    assert [reduced_totient_py(n) for n in [1, 2, 8, 12, 100]] == [1, 1, 2, 2, 20]
+    assert reduced_totient_py(561) == 80
+    with pytest.raises(ValueError):
+        reduced_totient_py(-3)
+
+
+def test_n_order_py():
+    assert [n_order_py(a, 7) for a in [1, 2, 3, -2, 9]] == [1, 3, 6, 6, 3]
+    assert n_order_py(2, 2**127 - 1) == 127
+    with pytest.raises(ValueError):
+        n_order_py(2, 4)
+    with pytest.raises(ValueError):
+        n_order_py(2, 1)
+
+
+def test_primitive_root_py():
+    assert [primitive_root_py(n) for n in [2, 4, 18, 25, 41]] == [1, 3, 5, 2, 6]
+    assert primitive_root_py(8) is None
+    assert n_order_py(primitive_root_py(2 * 3**10, smallest=False), 2 * 3**10) == 2 * 3**9
+    with pytest.raises(ValueError):
+        primitive_root_py(1)
+
+
+def test_sqrt_mod_py():
+    assert sqrt_mod_py(4, 7) == 2
+    assert sqrt_mod_py(3, 7) is None
+    assert sqrt_mod_py(2, 7, True) == [3, 4]
+    assert sqrt_mod_py(9, 27, all_roots=True) == [3, 6, 12, 15, 21, 24]
+    assert sqrt_mod_py(3, 7, True) == []
+    with pytest.raises(ValueError):
+        sqrt_mod_py(3, 0)
+
+
+def test_nthroot_mod_py():
+    assert nthroot_mod_py(11, 4, 19) == 8
+    assert nthroot_mod_py(11, 4, 19, True) == [8, 11]
+    assert nthroot_mod_py(68, 3, 109, all_roots=True) == [23, 32, 54]
+    assert nthroot_mod_py(0, 3, 8, True) == [0, 2, 4, 6]
+    assert nthroot_mod_py(3, 3, 7) is None
+    with pytest.raises(ValueError):
+        nthroot_mod_py(3, 0, 7)
+
+
+def test_polynomial_congruence_py():
+    assert polynomial_congruence_py([1, 0, -2], 7) == [3, 4]
+    assert polynomial_congruence_py([1, -3, 2], 6) == [1, 2, 4, 5]
+    assert polynomial_congruence_py([1, 0, 1], 3) == []
+    with pytest.raises(ValueError):
+        polynomial_congruence_py([1, 0, 1], 0)